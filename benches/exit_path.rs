@@ -0,0 +1,61 @@
+//! 退出路径的基准测试
+//!
+//! 运行:
+//! ```bash
+//! cargo bench --bench exit_path
+//! ```
+//!
+//! `ret/string` 为改写前使用 `format!` 格式化返回值的方式，`ret/inline` 为当前使用的栈上缓冲区。
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use tracing_fn::runtime::InlineString;
+use tracing_fn::tracing_fn;
+
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Order {
+    id: u64,
+    item: &'static str,
+}
+
+#[tracing_fn(force = true)]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+fn ret(c: &mut Criterion) {
+    let cases: [(&str, &dyn std::fmt::Debug); 3] = [
+        ("i32", &42_i32),
+        (
+            "struct",
+            &Order {
+                id: 7,
+                item: "book",
+            },
+        ),
+        ("vec_1k", &vec![0_u8; 1024]),
+    ];
+    for (name, value) in cases {
+        let mut group = c.benchmark_group(format!("ret/{name}"));
+        group.bench_function("string", |b| b.iter(|| format!("{:?}", black_box(value))));
+        group.bench_function("inline", |b| {
+            b.iter(|| InlineString::<128>::debug(black_box(value)))
+        });
+        group.finish();
+    }
+}
+
+fn call(c: &mut Criterion) {
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::TRACE)
+        .with_writer(std::io::sink)
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        c.bench_function("call/enabled", |b| b.iter(|| add(black_box(1), 2)));
+    });
+    c.bench_function("call/disabled", |b| b.iter(|| add(black_box(1), 2)));
+}
+
+criterion_group!(benches, ret, call);
+criterion_main!(benches);
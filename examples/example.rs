@@ -8,8 +8,7 @@
 //! # 在 Release 模式下运行（默认不启用 tracing）
 //! cargo run --example example --release
 //! ```
-use tracing_fn::tracing_fn;
-use tracing_subscriber;
+use tracing_fn::{tracing_fn, tracing_impl};
 
 #[tracing_fn]
 fn hello_world(name: &str) -> String {
@@ -32,6 +31,19 @@ fn process_data(a: i32, b: Vec<i32>, c: &str) -> usize {
     a as usize + b.len() + c.len()
 }
 
+// 解构的参数按绑定的变量逐个输出
+#[tracing_fn]
+fn manhattan((x, y): (i32, i32), Point { x: px, .. }: Point) -> i32 {
+    (x - px).abs() + y.abs()
+}
+
+#[derive(Debug)]
+struct Point {
+    x: i32,
+    #[allow(dead_code)]
+    y: i32,
+}
+
 // 强制在release模式下也启用tracing
 #[tracing_fn(force = true)]
 fn important_function(x: i32) -> i32 {
@@ -44,11 +56,78 @@ fn no_arg_no_ret() {
     println!("Hello from no args function");
 }
 
+// 同一 user_id 只在首次调用时输出
+#[tracing_fn(once_per(key = "user_id"))]
+fn load_profile(user_id: u64) -> String {
+    format!("profile-{}", user_id)
+}
+
+#[tracing_fn(level = "debug")]
+fn query_db(id: u32) -> Result<u32, String> {
+    if id.is_multiple_of(3) {
+        Err(format!("row {} not found", id))
+    } else {
+        Ok(id * 10)
+    }
+}
+
+// 退出事件中输出嵌套调用的次数
+#[tracing_fn(fanout = true)]
+fn handle_request(ids: Vec<u32>) -> usize {
+    ids.into_iter().filter_map(|id| query_db(id).ok()).count()
+}
+
+thread_local! {
+    static CURRENT_TENANT: std::cell::RefCell<Option<String>> = const { std::cell::RefCell::new(None) };
+}
+
+// 入口与退出事件中附带当前租户标识
+#[tracing_fn(level = "info", tenant = true)]
+fn create_order(item: &str) -> u64 {
+    item.len() as u64
+}
+
+#[derive(Debug)]
+struct Connection {
+    peer: &'static str,
+}
+
+// 连续调用时重复的第一个参数只在首次完整输出
+#[tracing_fn(compress_prefix(len = 1, window = "10s"))]
+fn process_item(conn: &Connection, item: u32) -> u32 {
+    if conn.peer.is_empty() { 0 } else { item * 2 }
+}
+
+// 按调用深度缩进, 便于阅读递归调用
+#[tracing_fn(depth = true)]
+fn fib(n: u32) -> u32 {
+    if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+}
+
+struct UserService;
+
+// impl 块级别的参数作用于所有方法
+#[tracing_impl(level = "debug", skip(password))]
+impl UserService {
+    fn login(&self, username: &str, password: &str) -> bool {
+        !username.is_empty() && !password.is_empty()
+    }
+
+    // 在 impl 块参数的基础上追加跳过 token
+    #[tracing_fn(skip(token))]
+    fn reset_password(&self, token: &str, password: &str) -> bool {
+        !token.is_empty() && !password.is_empty()
+    }
+}
+
 fn main() {
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::TRACE)
         .init();
 
+    tracing_fn::set_tenant_provider(|| CURRENT_TENANT.with(|tenant| tenant.borrow().clone()))
+        .unwrap();
+
     let greeting = hello_world("Alice");
     println!("{}", greeting);
 
@@ -61,9 +140,33 @@ fn main() {
     let data_size = process_data(10, vec![1, 2, 3], "test");
     println!("Data size: {}", data_size);
 
+    let distance = manhattan((3, 4), Point { x: 1, y: 2 });
+    println!("Distance: {}", distance);
+
     let result = important_function(21);
     println!("Important result: {}", result);
 
-    let no_arg_no_ret = no_arg_no_ret();
-    println!("No args result: {:#?}", no_arg_no_ret);
+    no_arg_no_ret();
+
+    for user_id in [1, 2, 1, 1, 2] {
+        load_profile(user_id);
+    }
+
+    handle_request(vec![1, 2, 3, 4]);
+
+    CURRENT_TENANT.with(|tenant| *tenant.borrow_mut() = Some("acme".to_string()));
+    create_order("book");
+
+    let conn = Connection {
+        peer: "10.0.0.1:5432",
+    };
+    for item in 0..3 {
+        process_item(&conn, item);
+    }
+
+    fib(3);
+
+    let service = UserService;
+    service.login("user", "password123");
+    service.reset_password("token", "password456");
 }
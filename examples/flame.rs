@@ -0,0 +1,62 @@
+//! 示例程序演示通过 tracing-flame 生成火焰图
+//!
+//! 运行示例:
+//! ```bash
+//! cargo run --example flame
+//! ```
+//!
+//! 程序在当前目录生成 `tracing.folded` (folded stack 格式) 与 `tracing-flamegraph.svg`。
+use std::fs::File;
+use std::hint::black_box;
+use std::io::BufWriter;
+use std::path::Path;
+
+use tracing_flame::FlameLayer;
+use tracing_fn::tracing_fn;
+use tracing_subscriber::prelude::*;
+
+#[tracing_fn(span = true)]
+fn handle_request(ids: &[u64]) -> usize {
+    ids.iter().map(|&id| render_page(query(id)).len()).sum()
+}
+
+#[tracing_fn(span = true)]
+fn query(id: u64) -> u64 {
+    busy(id * 20_000)
+}
+
+#[tracing_fn(span = true)]
+fn render_page(row: u64) -> String {
+    busy(10_000);
+    format!("<p>{row}</p>")
+}
+
+fn busy(n: u64) -> u64 {
+    (0..n).fold(0, |acc, i| black_box(acc ^ i.wrapping_mul(31)))
+}
+
+/// 在 tracing-flame 的 subscriber 下执行示例的调用树，把 folded stack 写入 `folded`
+pub fn record(folded: &Path) {
+    let (flame_layer, guard) = FlameLayer::with_file(folded).expect("failed to create folded file");
+    let subscriber = tracing_subscriber::registry().with(flame_layer);
+    tracing::subscriber::with_default(subscriber, || {
+        handle_request(&[1, 2, 3]);
+    });
+    guard.flush().expect("failed to flush folded file");
+}
+
+/// 由 folded stack 生成 SVG 格式的火焰图
+pub fn render(folded: &Path, svg: &Path) {
+    let mut options = inferno::flamegraph::Options::default();
+    let writer = BufWriter::new(File::create(svg).expect("failed to create svg file"));
+    inferno::flamegraph::from_files(&mut options, &[folded.to_path_buf()], writer)
+        .expect("failed to render flamegraph");
+}
+
+fn main() {
+    let folded = Path::new("tracing.folded");
+    let svg = Path::new("tracing-flamegraph.svg");
+    record(folded);
+    render(folded, svg);
+    println!("flamegraph written to {}", svg.display());
+}
@@ -0,0 +1,26 @@
+//! 示例程序演示通过 log 后端输出事件
+//!
+//! 运行示例:
+//! ```bash
+//! RUST_LOG=trace cargo run --example log_backend
+//! ```
+use tracing_fn::tracing_fn;
+
+#[tracing_fn(backend = "log", level = "info")]
+fn parse_port(input: &str) -> Result<u16, std::num::ParseIntError> {
+    let port = input.trim().parse()?;
+    Ok(port)
+}
+
+#[tracing_fn(backend = "log", depth = true)]
+fn fib(n: u32) -> u32 {
+    if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+}
+
+fn main() {
+    env_logger::init();
+
+    let _ = parse_port("8080");
+    let _ = parse_port("http");
+    fib(3);
+}
@@ -0,0 +1,93 @@
+//! 内存分配统计
+//!
+//! 启用 `alloc-count` feature 并把 [`CountingAllocator`] 设置为全局分配器后，
+//! 使用 `allocs = true` 参数的函数会在退出事件中输出调用期间当前线程的分配次数与分配的字节数。
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    // 使用不需要析构的 const 初始化, 分配器中访问线程局部变量时不会再次分配内存
+    static ALLOCS: Cell<u64> = const { Cell::new(0) };
+    static BYTES: Cell<u64> = const { Cell::new(0) };
+}
+
+/// 统计当前线程分配次数与字节数的全局分配器，实际的分配交由 `A` 完成
+///
+/// # 示例
+/// ```rust
+/// use tracing_fn::CountingAllocator;
+///
+/// #[global_allocator]
+/// static GLOBAL: CountingAllocator = CountingAllocator::new(std::alloc::System);
+/// ```
+pub struct CountingAllocator<A = System> {
+    inner: A,
+}
+
+impl<A> CountingAllocator<A> {
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+fn count(size: usize) {
+    // 线程退出时线程局部变量可能已经销毁, 此时不再统计
+    let _ = ALLOCS.try_with(|allocs| allocs.set(allocs.get() + 1));
+    let _ = BYTES.try_with(|bytes| bytes.set(bytes.get() + size as u64));
+}
+
+// SAFETY: 所有分配与释放均原样转发给 inner, 只额外更新线程局部的计数
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        unsafe { self.inner.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        unsafe { self.inner.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.inner.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size);
+        unsafe { self.inner.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// 调用开始时当前线程的分配计数
+#[doc(hidden)]
+pub struct AllocSnapshot {
+    allocs: u64,
+    bytes: u64,
+}
+
+/// 一次调用期间的分配统计
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct AllocStats {
+    pub allocs: u64,
+    pub bytes: u64,
+}
+
+impl AllocSnapshot {
+    pub fn now() -> Self {
+        Self {
+            allocs: ALLOCS.try_with(Cell::get).unwrap_or_default(),
+            bytes: BYTES.try_with(Cell::get).unwrap_or_default(),
+        }
+    }
+
+    /// 自 `now` 以来当前线程的分配次数与字节数，未使用 [`CountingAllocator`] 时均为 0
+    pub fn elapsed(&self) -> AllocStats {
+        let now = Self::now();
+        AllocStats {
+            allocs: now.allocs.wrapping_sub(self.allocs),
+            bytes: now.bytes.wrapping_sub(self.bytes),
+        }
+    }
+}
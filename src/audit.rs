@@ -0,0 +1,77 @@
+//! 审计链
+//!
+//! 使用 `audit = true` 参数的函数会把每条入口与退出事件的消息追加到该函数的审计链中，
+//! 事件附带结构化字段 `audit.seq` (从 1 开始单调递增的序号)、`audit.prev` (上一条记录的哈希) 与 `audit.hash` (本条记录的哈希)。
+//! 删除、插入或修改其中任意一条记录都会使之后的哈希无法对应，可以通过 [`audit_hash`] 重新计算进行校验。
+//!
+//! 哈希用于发现意外或简单的篡改，不是密码学意义上的签名。
+
+use crate::runtime::sync::{Mutex, const_fn};
+
+/// 一条审计记录的序号与哈希
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// 记录在该函数审计链中的序号，从 1 开始
+    pub seq: u64,
+    /// 上一条记录的哈希，第一条记录为 0
+    pub prev: u64,
+    /// 本条记录的哈希
+    pub hash: u64,
+}
+
+/// 单个函数的审计链
+#[doc(hidden)]
+pub struct AuditChain {
+    // 序号与哈希需要一起更新, 保证链中的记录按序号首尾相接
+    last: Mutex<(u64, u64)>,
+}
+
+impl AuditChain {
+    const_fn! {
+        pub fn new() -> Self {
+            Self {
+                last: Mutex::new((0, 0)),
+            }
+        }
+    }
+
+    /// 追加一条记录，返回其序号与哈希
+    pub fn append(&self, message: &str) -> AuditRecord {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        let (seq, prev) = *last;
+        let seq = seq + 1;
+        let hash = audit_hash(prev, seq, message);
+        *last = (seq, hash);
+        AuditRecord { seq, prev, hash }
+    }
+}
+
+impl Default for AuditChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 计算审计记录的哈希
+///
+/// 依次对上一条记录的哈希、本条记录的序号 (均为小端序的 8 字节) 与事件消息的 UTF-8 字节计算 64 位 FNV-1a 哈希。
+/// 事件中的 `audit.prev` 与 `audit.hash` 以 16 位十六进制输出。
+///
+/// # 示例
+/// ```rust
+/// let first = tracing_fn::audit_hash(0, 1, ">>> [transfer] #1 #Args: amount=100 --- src/main.rs:3");
+/// let second = tracing_fn::audit_hash(first, 2, "<<< [transfer] #1 #Ret: (), duration: 1.2µs");
+/// assert_ne!(first, second);
+/// ```
+pub fn audit_hash(prev: u64, seq: u64, message: &str) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    prev.to_le_bytes()
+        .iter()
+        .chain(&seq.to_le_bytes())
+        .chain(message.as_bytes())
+        .fold(OFFSET, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+        })
+}
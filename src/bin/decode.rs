@@ -0,0 +1,38 @@
+//! 解码 `binary = true` 写入的记录文件，每条记录输出一行文本
+//!
+//! ```sh
+//! tracing-fn-decode calls.bin
+//! ```
+
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut paths = std::env::args().skip(1).peekable();
+    if paths.peek().is_none() {
+        eprintln!("usage: tracing-fn-decode <file>...");
+        return ExitCode::FAILURE;
+    }
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    for path in paths {
+        let log = match tracing_fn::binary::decode(&path) {
+            Ok(log) => log,
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                return ExitCode::FAILURE;
+            }
+        };
+        for record in &log.records {
+            if writeln!(out, "{record}").is_err() {
+                // 输出被关闭, 例如通过管道交给 head
+                return ExitCode::SUCCESS;
+            }
+        }
+        if log.dropped > 0 {
+            eprintln!("{path}: {} records dropped", log.dropped);
+        }
+    }
+    let _ = out.flush();
+    ExitCode::SUCCESS
+}
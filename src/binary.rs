@@ -0,0 +1,468 @@
+//! 紧凑的二进制调用记录
+//!
+//! 使用 `binary = true` 参数的函数在入口与退出处各写入一条定长的二进制记录，不格式化参数与返回值，
+//! 只记录函数、调用序号、线程、时间戳、耗时与是否失败，适用于每秒调用数百万次、无法输出文本日志的函数。
+//! 记录与日志等级无关，通过 [`open`] 打开记录文件之后才会写入。
+//!
+//! 记录写入内存映射的文件，写入时只有一次原子加法与一次内存复制，不加锁也不进行系统调用。
+//! 文件在 [`open`] 时按容量分配，写满之后的记录被丢弃并计数。记录文件可以通过 [`decode`]
+//! 或随 crate 提供的 `tracing-fn-decode` 工具离线解码：
+//!
+//! ```sh
+//! cargo install tracing-fn --features binary --bin tracing-fn-decode
+//! tracing-fn-decode calls.bin
+//! ```
+//!
+//! # 文件格式
+//!
+//! 所有整数均为小端序。文件以 64 字节的文件头开始，之后是 `capacity` 个 32 字节的槽位：
+//!
+//! | 偏移 | 长度 | 文件头                                  |
+//! |------|------|-----------------------------------------|
+//! | 0    | 8    | 魔数 `TFNBIN01`                         |
+//! | 8    | 4    | 格式版本，当前为 1                      |
+//! | 12   | 4    | 槽位长度，当前为 32                     |
+//! | 16   | 8    | 槽位数量 `capacity`                     |
+//! | 24   | 8    | 已使用的槽位数量，由 [`flush`] 写入     |
+//! | 32   | 8    | 丢弃的记录数量，由 [`flush`] 写入       |
+//! | 40   | 8    | 打开文件时的 UNIX 时间戳 (纳秒)         |
+//!
+//! | 偏移 | 长度 | 槽位                                                    |
+//! |------|------|---------------------------------------------------------|
+//! | 0    | 1    | 类型：0 未写入，1 入口，2 退出，3 函数名                |
+//! | 1    | 1    | 退出记录的标志：bit 0 失败，bit 1 panic                 |
+//! | 2    | 2    | 函数名记录中名称的字节数                                |
+//! | 4    | 4    | 函数编号                                                |
+//! | 8    | 8    | 调用序号，同一次调用的入口与退出记录相同                |
+//! | 16   | 8    | 自打开文件经过的纳秒数                                  |
+//! | 24   | 8    | 入口记录为线程编号，退出记录为耗时 (纳秒)               |
+//!
+//! 函数名记录在函数第一次写入记录之前写入，UTF-8 编码的名称从槽位的第 8 字节开始，
+//! 占用之后若干个连续的槽位。类型字节在槽位的其余内容写入之后写入，进程崩溃时未写完的槽位按未写入处理。
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use memmap2::MmapRaw;
+
+use crate::runtime::Instant;
+
+const MAGIC: &[u8; 8] = b"TFNBIN01";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 64;
+const SLOT_LEN: usize = 32;
+
+const KIND_ENTRY: u8 = 1;
+const KIND_EXIT: u8 = 2;
+const KIND_NAME: u8 = 3;
+
+const FLAG_FAILED: u8 = 1;
+const FLAG_PANICKED: u8 = 2;
+
+static SINK: OnceLock<Sink> = OnceLock::new();
+
+struct Sink {
+    map: MmapRaw,
+    capacity: u64,
+    start: Instant,
+    /// 下一个未分配的槽位
+    next: AtomicU64,
+    dropped: AtomicU64,
+    functions: AtomicU32,
+    calls: AtomicU64,
+}
+
+impl Sink {
+    /// 分配 `slots` 个连续的槽位, 容量不足时返回 None
+    fn claim(&self, slots: u64) -> Option<usize> {
+        let index = self.next.fetch_add(slots, Ordering::Relaxed);
+        if index + slots > self.capacity {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        Some(HEADER_LEN + index as usize * SLOT_LEN)
+    }
+
+    /// 写入一条记录, 类型字节最后写入
+    fn write(&self, kind: u8, head: [u8; 8], body: &[u8]) {
+        let slots = (8 + body.len()).div_ceil(SLOT_LEN) as u64;
+        let Some(offset) = self.claim(slots) else {
+            return;
+        };
+        // SAFETY: 槽位由 claim 独占分配且位于映射范围内, 不同线程写入的区域互不重叠
+        unsafe {
+            let slot = self.map.as_mut_ptr().add(offset);
+            std::ptr::copy_nonoverlapping(head.as_ptr().add(1), slot.add(1), 7);
+            std::ptr::copy_nonoverlapping(body.as_ptr(), slot.add(8), body.len());
+            AtomicU8::from_ptr(slot).store(kind, Ordering::Release);
+        }
+    }
+
+    fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.start.elapsed().as_nanos() as u64
+    }
+
+    fn register(&self, name: &str) -> u32 {
+        let id = self.functions.fetch_add(1, Ordering::Relaxed);
+        let len = name.len().min(u16::MAX as usize);
+        self.write(KIND_NAME, head(0, len as u16, id), &name.as_bytes()[..len]);
+        id
+    }
+}
+
+fn head(flags: u8, len: u16, function: u32) -> [u8; 8] {
+    let mut head = [0; 8];
+    head[1] = flags;
+    head[2..4].copy_from_slice(&len.to_le_bytes());
+    head[4..8].copy_from_slice(&function.to_le_bytes());
+    head
+}
+
+fn body(call: u64, timestamp: u64, extra: u64) -> [u8; 24] {
+    let mut body = [0; 24];
+    body[..8].copy_from_slice(&call.to_le_bytes());
+    body[8..16].copy_from_slice(&timestamp.to_le_bytes());
+    body[16..].copy_from_slice(&extra.to_le_bytes());
+    body
+}
+
+/// 创建记录文件并开始写入，`capacity` 为最多容纳的槽位数量
+///
+/// 每条入口或退出记录占用一个 32 字节的槽位，文件大小固定为 `64 + capacity * 32` 字节。
+/// 文件已存在时被覆盖。整个进程只能打开一次，再次调用时返回 [`io::ErrorKind::AlreadyExists`]。
+///
+/// # 示例
+/// ```rust,no_run
+/// // 最多 1600 万条记录, 约 512 MiB
+/// tracing_fn::binary::open("calls.bin", 1 << 24).unwrap();
+/// ```
+pub fn open(path: impl AsRef<Path>, capacity: u64) -> io::Result<()> {
+    if SINK.get().is_some() {
+        return Err(already_open());
+    }
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    let len = capacity
+        .checked_mul(SLOT_LEN as u64)
+        .and_then(|len| len.checked_add(HEADER_LEN as u64))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "capacity is too large"))?;
+    file.set_len(len)?;
+    let map = MmapRaw::map_raw(&file)?;
+
+    let mut header = [0; HEADER_LEN];
+    header[..8].copy_from_slice(MAGIC);
+    header[8..12].copy_from_slice(&VERSION.to_le_bytes());
+    header[12..16].copy_from_slice(&(SLOT_LEN as u32).to_le_bytes());
+    header[16..24].copy_from_slice(&capacity.to_le_bytes());
+    let unix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64);
+    header[40..48].copy_from_slice(&unix.to_le_bytes());
+    // SAFETY: 映射长度不小于文件头, 尚未发布给其他线程
+    unsafe { std::ptr::copy_nonoverlapping(header.as_ptr(), map.as_mut_ptr(), HEADER_LEN) };
+
+    SINK.set(Sink {
+        map,
+        capacity,
+        start: Instant::now(),
+        next: AtomicU64::new(0),
+        dropped: AtomicU64::new(0),
+        functions: AtomicU32::new(0),
+        calls: AtomicU64::new(0),
+    })
+    .map_err(|_| already_open())
+}
+
+fn already_open() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "binary sink has already been opened",
+    )
+}
+
+/// 把已使用的槽位数量与丢弃的记录数量写入文件头，并把映射的内容同步到磁盘
+///
+/// 进程正常退出或被终止时，已写入的记录仍由操作系统写回文件，只有需要在断电等情况下保留记录时才需要调用。
+/// 未打开记录文件时不做任何事。
+pub fn flush() -> io::Result<()> {
+    let Some(sink) = SINK.get() else {
+        return Ok(());
+    };
+    let used = sink.next.load(Ordering::Relaxed).min(sink.capacity);
+    let mut counts = [0; 16];
+    counts[..8].copy_from_slice(&used.to_le_bytes());
+    counts[8..].copy_from_slice(&sink.dropped().to_le_bytes());
+    // SAFETY: 文件头的计数区域只由 flush 写入
+    unsafe { std::ptr::copy_nonoverlapping(counts.as_ptr(), sink.map.as_mut_ptr().add(24), 16) };
+    sink.map.flush()
+}
+
+/// 因文件写满而丢弃的记录数量
+pub fn dropped() -> u64 {
+    SINK.get().map_or(0, Sink::dropped)
+}
+
+/// 记录的类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordKind {
+    /// 进入函数
+    Entry,
+    /// 函数返回或 panic
+    Exit {
+        /// 以纳秒为单位的耗时
+        duration_ns: u64,
+        /// 返回 `Err` 或 panic
+        failed: bool,
+        /// panic 导致的退出
+        panicked: bool,
+    },
+}
+
+/// 一条解码后的记录
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BinaryRecord {
+    /// 函数的完整路径，例如 `my_crate::codec::decode_frame`
+    pub function: Arc<str>,
+    /// 调用序号，同一次调用的入口与退出记录相同
+    pub call: u64,
+    /// 进入函数的线程编号，退出记录中为 `None`
+    pub thread: Option<u64>,
+    /// 自打开文件经过的纳秒数
+    pub timestamp_ns: u64,
+    pub kind: RecordKind,
+}
+
+impl fmt::Display for BinaryRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            RecordKind::Entry => write!(
+                f,
+                "{} thread={} >>> [{}] #{}",
+                self.timestamp_ns,
+                self.thread.unwrap_or_default(),
+                self.function,
+                self.call
+            ),
+            RecordKind::Exit {
+                duration_ns,
+                failed,
+                panicked,
+            } => {
+                write!(
+                    f,
+                    "{} <<< [{}] #{} #Elapsed: {}ns",
+                    self.timestamp_ns, self.function, self.call, duration_ns
+                )?;
+                if panicked {
+                    f.write_str(" #Panicked")
+                } else if failed {
+                    f.write_str(" #Failed")
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// 解码后的记录文件
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BinaryLog {
+    /// 打开文件时的 UNIX 时间戳 (纳秒)
+    pub start_unix_ns: u64,
+    /// 因文件写满而丢弃的记录数量，未调用 [`flush`] 时为 0
+    pub dropped: u64,
+    /// 按写入顺序排列的记录
+    pub records: Vec<BinaryRecord>,
+}
+
+/// 读取并解码 [`open`] 写入的记录文件
+///
+/// # 示例
+/// ```rust,no_run
+/// let log = tracing_fn::binary::decode("calls.bin").unwrap();
+/// for record in &log.records {
+///     println!("{record}");
+/// }
+/// ```
+pub fn decode(path: impl AsRef<Path>) -> io::Result<BinaryLog> {
+    decode_bytes(&std::fs::read(path)?)
+}
+
+/// 解码内存中的记录文件内容, 内容不是有效的记录文件时返回 `InvalidData` 错误
+pub fn decode_bytes(bytes: &[u8]) -> io::Result<BinaryLog> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    if bytes.len() < HEADER_LEN || &bytes[..8] != MAGIC {
+        return Err(invalid("not a tracing-fn binary log"));
+    }
+    if u32_at(bytes, 8) != VERSION || u32_at(bytes, 12) as usize != SLOT_LEN {
+        return Err(invalid("unsupported tracing-fn binary log version"));
+    }
+    let capacity = u64_at(bytes, 16) as usize;
+    let slots = &bytes[HEADER_LEN..];
+    let slots = &slots[..slots.len().min(capacity.saturating_mul(SLOT_LEN))];
+
+    // 函数编号按注册顺序分配, 每个函数名至少占用一个槽位, 有效的编号小于槽位数量
+    let max_functions = slots.len() / SLOT_LEN;
+    let mut names: Vec<Option<Arc<str>>> = Vec::new();
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while offset + SLOT_LEN <= slots.len() {
+        let slot = &slots[offset..];
+        let function = u32_at(slot, 4) as usize;
+        match slot[0] {
+            KIND_NAME => {
+                if function >= max_functions {
+                    return Err(invalid("function index out of range"));
+                }
+                let len = u16::from_le_bytes([slot[2], slot[3]]) as usize;
+                let name = slot
+                    .get(8..8 + len)
+                    .ok_or_else(|| invalid("truncated function name"))?;
+                if names.len() <= function {
+                    names.resize(function + 1, None);
+                }
+                names[function] = Some(String::from_utf8_lossy(name).into());
+                offset += (8 + len).div_ceil(SLOT_LEN) * SLOT_LEN;
+                continue;
+            }
+            kind @ (KIND_ENTRY | KIND_EXIT) => {
+                let function = names
+                    .get(function)
+                    .cloned()
+                    .flatten()
+                    .unwrap_or_else(|| format!("<fn#{function}>").into());
+                let extra = u64_at(slot, 24);
+                let (thread, kind) = if kind == KIND_ENTRY {
+                    (Some(extra), RecordKind::Entry)
+                } else {
+                    let flags = slot[1];
+                    (
+                        None,
+                        RecordKind::Exit {
+                            duration_ns: extra,
+                            failed: flags & (FLAG_FAILED | FLAG_PANICKED) != 0,
+                            panicked: flags & FLAG_PANICKED != 0,
+                        },
+                    )
+                };
+                records.push(BinaryRecord {
+                    function,
+                    call: u64_at(slot, 8),
+                    thread,
+                    timestamp_ns: u64_at(slot, 16),
+                    kind,
+                });
+            }
+            // 未写入或未写完的槽位
+            _ => {}
+        }
+        offset += SLOT_LEN;
+    }
+
+    Ok(BinaryLog {
+        start_unix_ns: u64_at(bytes, 40),
+        dropped: u64_at(bytes, 32),
+        records,
+    })
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+/// 使用 `binary = true` 的函数, 第一次写入记录时分配编号并写入函数名
+#[doc(hidden)]
+pub struct BinaryFn {
+    name: &'static str,
+    id: OnceLock<u32>,
+}
+
+impl BinaryFn {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            id: OnceLock::new(),
+        }
+    }
+
+    /// 写入入口记录, 未打开记录文件时返回 None
+    #[inline]
+    pub fn enter(&self) -> Option<BinaryCall> {
+        let sink = SINK.get()?;
+        let function = *self.id.get_or_init(|| sink.register(self.name));
+        let call = sink.calls.fetch_add(1, Ordering::Relaxed);
+        let timestamp = sink.timestamp();
+        sink.write(
+            KIND_ENTRY,
+            head(0, 0, function),
+            &body(call, timestamp, thread_id()),
+        );
+        Some(BinaryCall {
+            function,
+            call,
+            timestamp,
+        })
+    }
+}
+
+/// 一次写入了入口记录的调用
+#[doc(hidden)]
+pub struct BinaryCall {
+    function: u32,
+    call: u64,
+    timestamp: u64,
+}
+
+impl BinaryCall {
+    /// 写入退出记录
+    #[inline]
+    pub fn exit(self, failed: bool, panicked: bool) {
+        let Some(sink) = SINK.get() else {
+            return;
+        };
+        let timestamp = sink.timestamp();
+        let flags = if panicked {
+            FLAG_PANICKED
+        } else if failed {
+            FLAG_FAILED
+        } else {
+            0
+        };
+        sink.write(
+            KIND_EXIT,
+            head(flags, 0, self.function),
+            &body(
+                self.call,
+                timestamp,
+                timestamp.saturating_sub(self.timestamp),
+            ),
+        );
+    }
+}
+
+/// 进程内从 1 开始分配的线程编号
+fn thread_id() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    thread_local! {
+        static ID: u64 = NEXT.fetch_add(1, Ordering::Relaxed);
+    }
+    ID.with(|id| *id)
+}
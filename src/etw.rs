@@ -0,0 +1,83 @@
+//! ETW 后端
+//!
+//! 使用 `backend = "etw"` 参数的函数通过 TraceLogging 把事件写入名为 `TracingFn` 的 ETW provider，
+//! 可以在 WPA 中与内核事件一起查看。入口事件名为 `FunctionEntry` (opcode 为 Start)，
+//! 退出事件名为 `FunctionExit` (opcode 为 Stop)，其余事件名为 `FunctionEvent`，
+//! 均包含 `module` 与 `message` 两个字段。
+//!
+//! provider 需要在程序启动时通过 [`register_etw_provider`] 注册，未注册或没有会话启用 provider 时不格式化、不输出事件。
+//! 在非 Windows 平台上注册与输出均为空操作。
+
+use std::fmt;
+
+use tracelogging as tlg;
+
+tlg::define_provider!(PROVIDER, "TracingFn");
+
+/// 注册 `TracingFn` provider，返回 Win32 错误码，成功时为 0
+///
+/// 重复注册时返回错误码，已注册的 provider 不受影响。
+///
+/// # Safety
+///
+/// 在 DLL 中使用时，必须在 DLL 卸载前调用 [`unregister_etw_provider`]。
+pub unsafe fn register_etw_provider() -> u32 {
+    // SAFETY: 由调用者保证在 DLL 卸载前注销
+    unsafe { PROVIDER.register() }
+}
+
+/// 注销 `TracingFn` provider，返回 Win32 错误码，成功时为 0
+pub fn unregister_etw_provider() -> u32 {
+    PROVIDER.unregister()
+}
+
+/// ETW 事件的种类
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EtwKind {
+    /// 入口事件
+    Entry,
+    /// 退出事件
+    Exit,
+    /// 其他事件, 例如批处理进度
+    Event,
+}
+
+/// 指定等级的事件是否会被某个 ETW 会话记录
+///
+/// `level` 为 ETW 等级: error 为 2，warn 为 3，info 为 4，debug 与 trace 为 5。
+pub fn etw_enabled(level: u8) -> bool {
+    PROVIDER.enabled(tlg::Level::from_int(level), 0)
+}
+
+/// 输出一个 ETW 事件
+pub fn etw_write(kind: EtwKind, level: u8, module: &str, message: fmt::Arguments<'_>) {
+    if !etw_enabled(level) {
+        return;
+    }
+    let message = message.to_string();
+
+    // 事件的等级、名称与 opcode 必须是常量, 按取值逐一展开
+    macro_rules! write_events {
+        ($($level:ident = $value:literal),*) => {
+            match (kind, level) {
+                $(
+                    (EtwKind::Entry, $value) => tlg::write_event!(
+                        PROVIDER, "FunctionEntry", level($level), opcode(Start),
+                        str8("module", module), str8("message", &message),
+                    ),
+                    (EtwKind::Exit, $value) => tlg::write_event!(
+                        PROVIDER, "FunctionExit", level($level), opcode(Stop),
+                        str8("module", module), str8("message", &message),
+                    ),
+                    (EtwKind::Event, $value) => tlg::write_event!(
+                        PROVIDER, "FunctionEvent", level($level),
+                        str8("module", module), str8("message", &message),
+                    ),
+                )*
+                _ => 0,
+            }
+        };
+    }
+
+    write_events!(Error = 2, Warning = 3, Informational = 4, Verbose = 5);
+}
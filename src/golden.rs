@@ -0,0 +1,225 @@
+//! 固定事件输出的快照测试
+//!
+//! [`capture`] 在当前线程中运行一段代码并收集其间输出的所有事件。收集期间计时被冻结 (耗时始终为 `0ns`)，
+//! 调用 ID 从 1 开始编号，因此同一段代码每次收集到的输出都相同。[`assert_golden!`](crate::assert_golden)
+//! 把收集到的输出与快照文件比较，下游 crate 可以据此锁定日志的内容，发现对输出的意外修改。
+//!
+//! 快照文件与 [insta](https://insta.rs) 的格式兼容，位于测试源文件所在目录的 `snapshots` 子目录中，
+//! 输出与快照不一致或快照不存在时写入同名的 `.snap.new` 文件并使测试失败，可以使用 `cargo insta review` 审阅，
+//! 或设置环境变量 `TRACING_FN_GOLDEN=overwrite` (或 `INSTA_UPDATE=always`) 直接覆盖快照。
+//!
+//! 事件中的源码位置、线程信息、CPU 时间与 `clock` 参数的耗时不受控制，需要稳定的快照时应避免使用。
+//!
+//! # 示例
+//! ```rust,ignore
+//! use tracing_fn::tracing_fn;
+//!
+//! #[tracing_fn(location = "none")]
+//! fn add(a: i32, b: i32) -> i32 {
+//!     a + b
+//! }
+//!
+//! #[test]
+//! fn add_is_logged() {
+//!     let output = tracing_fn::golden::capture(|| {
+//!         add(1, 2);
+//!     });
+//!     // tests/snapshots/golden__add.snap:
+//!     // TRACE >>> [add] #1 #Args: a=1, b=2
+//!     // TRACE <<< [add] #1 #Ret: 3, duration: 0ns duration_us=0
+//!     tracing_fn::assert_golden!("add", output);
+//! }
+//! ```
+
+use std::fmt::{self, Debug, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// 一次 [`capture`] 收集到的事件，每个事件一行
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Capture {
+    lines: Vec<String>,
+}
+
+impl Capture {
+    /// 按输出顺序排列的事件，格式为 `等级 消息 字段=值 ...`
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+}
+
+impl Display for Capture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            f.write_str(line)?;
+        }
+        Ok(())
+    }
+}
+
+/// 在当前线程中运行 `f`，收集其间输出的所有事件
+///
+/// 收集期间当前线程的计时被冻结，调用 ID 从 1 开始编号，结束后恢复。其他线程中输出的事件不会被收集。
+pub fn capture(f: impl FnOnce()) -> Capture {
+    let subscriber = Collector::default();
+    let lines = subscriber.lines.clone();
+    {
+        let _clock = crate::runtime::freeze_clock();
+        let _ids = crate::id::restart_seq();
+        tracing::subscriber::with_default(subscriber, f);
+    }
+    let lines = std::mem::take(&mut *lines.lock().unwrap_or_else(|err| err.into_inner()));
+    Capture { lines }
+}
+
+/// 只收集事件的 subscriber, span 不参与输出
+#[derive(Default)]
+struct Collector {
+    lines: std::sync::Arc<Mutex<Vec<String>>>,
+    next_span: AtomicU64,
+}
+
+impl Subscriber for Collector {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut line = Line {
+            message: String::new(),
+            fields: String::new(),
+        };
+        event.record(&mut line);
+        let line = format!(
+            "{} {}{}",
+            event.metadata().level(),
+            line.message,
+            line.fields
+        );
+        self.lines
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push(line);
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+struct Line {
+    message: String,
+    fields: String,
+}
+
+impl Visit for Line {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.fields.push_str(&format!(" {}={value}", field.name()));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message.push_str(&format!("{value:?}"));
+        } else {
+            self.fields
+                .push_str(&format!(" {}={value:?}", field.name()));
+        }
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_snapshot(
+    manifest_dir: &str,
+    file: &str,
+    name: &str,
+    expression: &str,
+    capture: &Capture,
+) {
+    let snapshot = snapshot_path(manifest_dir, file, name);
+    let contents = format!(
+        "---\nsource: {}\nexpression: {expression}\n---\n{capture}\n",
+        file.replace('\\', "/")
+    );
+    let expected = fs::read_to_string(&snapshot).ok();
+    if expected.as_deref().map(snapshot_body) == Some(capture.to_string().trim_end()) {
+        return;
+    }
+
+    let pending = snapshot.with_extension("snap.new");
+    let overwrite = std::env::var("TRACING_FN_GOLDEN").is_ok_and(|value| value == "overwrite")
+        || std::env::var("INSTA_UPDATE").is_ok_and(|value| value == "always");
+    let target = if overwrite { &snapshot } else { &pending };
+    if let Some(dir) = target.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Err(err) = fs::write(target, &contents) {
+        panic!("failed to write snapshot {}: {err}", target.display());
+    }
+    if overwrite {
+        let _ = fs::remove_file(&pending);
+        return;
+    }
+    match expected {
+        Some(expected) => panic!(
+            "snapshot `{name}` does not match, new output written to {}\n--- expected\n{}\n+++ actual\n{capture}",
+            target.display(),
+            snapshot_body(&expected)
+        ),
+        None => panic!(
+            "snapshot `{name}` does not exist, new output written to {}\n{capture}",
+            target.display()
+        ),
+    }
+}
+
+/// 测试源文件所在目录中的 `snapshots/<文件名>__<name>.snap`
+///
+/// `file!()` 在 workspace 中相对于 workspace 的根目录, 依次在 crate 目录及其上层目录中查找源文件
+fn snapshot_path(manifest_dir: &str, file: &str, name: &str) -> PathBuf {
+    let file = Path::new(file);
+    let source = Path::new(manifest_dir)
+        .ancestors()
+        .map(|dir| dir.join(file))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| Path::new(manifest_dir).join(file));
+    let stem = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    source
+        .parent()
+        .unwrap_or(Path::new(manifest_dir))
+        .join("snapshots")
+        .join(format!("{stem}__{name}.snap"))
+}
+
+/// 去掉快照文件开头的元数据
+fn snapshot_body(contents: &str) -> &str {
+    let body = contents
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.split_once("\n---\n"))
+        .map_or(contents, |(_, body)| body);
+    body.trim_end()
+}
@@ -0,0 +1,111 @@
+//! 调用 ID
+//!
+//! 宏为每次调用生成的 ID 默认为进程内单调递增的整数，
+//! 可以通过 [`set_id_generator`] 替换为与现有关联 ID 方案兼容的生成器 (例如 snowflake、UUIDv7)。
+
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+type Generator = Box<dyn Fn() -> String + Send + Sync>;
+
+static GENERATOR: OnceLock<Generator> = OnceLock::new();
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(1);
+
+/// 一次调用的 ID
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct InvocationId(Repr);
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Repr {
+    Seq(u64),
+    Custom(Box<str>),
+}
+
+impl InvocationId {
+    /// 生成下一个调用 ID，未设置生成器时使用进程内单调递增的整数
+    pub fn next() -> Self {
+        #[cfg(feature = "golden")]
+        if let Some(seq) = GOLDEN_SEQ.get() {
+            GOLDEN_SEQ.set(Some(seq + 1));
+            return Self(Repr::Seq(seq));
+        }
+        match GENERATOR.get() {
+            Some(generator) => Self(Repr::Custom(generator().into_boxed_str())),
+            None => Self(Repr::Seq(NEXT_SEQ.fetch_add(1, Ordering::Relaxed))),
+        }
+    }
+}
+
+impl fmt::Display for InvocationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Repr::Seq(seq) => fmt::Display::fmt(seq, f),
+            Repr::Custom(id) => f.write_str(id),
+        }
+    }
+}
+
+impl fmt::Debug for InvocationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// 重复设置 ID 生成器时返回的错误
+#[derive(Debug)]
+pub struct SetIdGeneratorError(());
+
+impl fmt::Display for SetIdGeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("id generator has already been set")
+    }
+}
+
+impl Error for SetIdGeneratorError {}
+
+/// 设置全局的调用 ID 生成器
+///
+/// 只能设置一次，应在程序启动时、调用任何被追踪的函数之前设置。
+///
+/// # 示例
+/// ```rust
+/// use std::sync::atomic::{AtomicU64, Ordering};
+///
+/// static SEQ: AtomicU64 = AtomicU64::new(0);
+///
+/// tracing_fn::set_id_generator(|| {
+///     format!("req-{:08x}", SEQ.fetch_add(1, Ordering::Relaxed))
+/// })
+/// .unwrap();
+/// ```
+pub fn set_id_generator<F>(generator: F) -> Result<(), SetIdGeneratorError>
+where
+    F: Fn() -> String + Send + Sync + 'static,
+{
+    GENERATOR
+        .set(Box::new(generator))
+        .map_err(|_| SetIdGeneratorError(()))
+}
+
+#[cfg(feature = "golden")]
+thread_local! {
+    static GOLDEN_SEQ: std::cell::Cell<Option<u64>> = const { std::cell::Cell::new(None) };
+}
+
+/// golden: 当前线程的调用 ID 从 1 开始重新编号, 忽略设置的生成器, 返回的守卫析构时恢复
+#[cfg(feature = "golden")]
+pub(crate) fn restart_seq() -> RestartedSeq {
+    RestartedSeq(GOLDEN_SEQ.replace(Some(1)))
+}
+
+#[cfg(feature = "golden")]
+pub(crate) struct RestartedSeq(Option<u64>);
+
+#[cfg(feature = "golden")]
+impl Drop for RestartedSeq {
+    fn drop(&mut self) {
+        GOLDEN_SEQ.set(self.0);
+    }
+}
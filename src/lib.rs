@@ -1,185 +1,224 @@
-use proc_macro::TokenStream;
-use quote::quote;
-use syn::{ItemFn, parse_macro_input};
+//! 为函数添加 tracing 功能的过程宏
+//!
+//! 在函数调用前输出调用参数，在函数调用后输出返回值和执行耗时。
+//! 具体用法参见 [`macro@tracing_fn`]、[`macro@tracing_impl`] 与 [`macro@tracing_mod`]，
+//! 无法标注的函数可以在调用处使用 [`traced_extern!`]，函数中的一段代码与闭包可以使用 [`trace_block!`] 与 [`tracing_closure!`]，单个表达式的耗时可以使用 [`trace_expr!`] 测量，多个函数共用的参数可以通过 [`opts!`] 定义为预设。
+//!
+//! 默认启用的 `std` feature 关闭后可以在 no_std 环境中使用，此时参数作为结构化字段输出，
+//! 耗时由 [`set_tick_source`] 注册的计时函数测量。
 
-/// 为函数添加 tracing 功能的过程宏
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use tracing_fn_macros::{
+    opts, trace_block, trace_expr, traced_extern, tracing_closure, tracing_fn, tracing_impl,
+    tracing_main, tracing_mod,
+};
+
+#[cfg(feature = "alloc-count")]
+mod alloc;
+#[cfg(feature = "std")]
+mod audit;
+#[cfg(feature = "win-etw")]
+mod etw;
+#[cfg(feature = "std")]
+mod id;
+mod opts;
+#[cfg(feature = "std")]
+mod pressure;
+#[cfg(feature = "record-replay")]
+mod replay;
+#[cfg(feature = "std")]
+mod tenant;
+#[cfg(not(feature = "std"))]
+mod ticks;
+#[cfg(feature = "traced")]
+mod traced;
+#[cfg(feature = "std")]
+mod verbosity;
+
+#[cfg(feature = "alloc-count")]
+pub use alloc::CountingAllocator;
+#[cfg(feature = "std")]
+pub use audit::audit_hash;
+#[cfg(feature = "win-etw")]
+pub use etw::{register_etw_provider, unregister_etw_provider};
+#[cfg(feature = "std")]
+pub use id::{InvocationId, SetIdGeneratorError, set_id_generator};
+pub use opts::Opts;
+#[cfg(feature = "std")]
+pub use pressure::{Pressure, SetPressureFnError, pressure, set_pressure_fn};
+#[cfg(feature = "record-replay")]
+pub use replay::{
+    ReplayRecord, ReplayResult, SetReplayPathError, load_replay, replay_path, set_replay_path,
+};
+#[cfg(feature = "std")]
+pub use tenant::{SetTenantProviderError, set_tenant_provider};
+#[cfg(not(feature = "std"))]
+pub use ticks::set_tick_source;
+#[cfg(feature = "traced")]
+pub use traced::Traced;
+#[cfg(feature = "std")]
+pub use verbosity::{set_verbosity, verbosity};
+
+/// `summarize` 参数使用的集合摘要，可以为自定义的集合类型实现
+pub use runtime::Summarize;
+
+#[cfg(feature = "binary")]
+pub mod binary;
+#[cfg(feature = "golden")]
+pub mod golden;
+#[cfg(feature = "std")]
+pub mod redaction;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+
+#[doc(hidden)]
+pub mod runtime;
+
+/// 在设置了 `batch` 参数的函数中记录已处理的条目
 ///
-/// # 参数
-/// - `level`: 日志等级 (trace, debug, info, warn, error)，默认为 trace
-/// - `skip`: 跳过的参数列表
-/// - `force`: 是否强制在release模式下启用tracing，默认为false
+/// `batch_tick!()` 记录一个条目，`batch_tick!(n)` 记录 `n` 个条目，已处理的条目数每跨过 `chunk` 的整数倍时输出一次进度事件。
+/// 宏由 `#[tracing_fn]` 在展开时替换，函数体中的闭包与 async 块内同样可以使用，但不能嵌套在其他宏中。
+/// 在其他位置使用时编译报错。
 ///
 /// # 示例
-/// ```rust
-/// #[tracing_fn]
-/// fn example_fn(a: i32, b: String) -> i32 {
-///     a + b.len() as i32
+/// ```rust,ignore
+/// #[tracing_fn(batch(rows, chunk = 1000))]
+/// fn import(rows: Vec<Row>) {
+///     for row in rows {
+///         insert(row);
+///         tracing_fn::batch_tick!();
+///     }
 /// }
+/// ```
+#[macro_export]
+macro_rules! batch_tick {
+    // `#[tracing_fn]` 替换后的调用, 保留宏的路径使 `use tracing_fn::batch_tick` 不会被视为未使用
+    (@tracing_fn $tick:block) => {
+        $tick
+    };
+    ($($count:expr)?) => {
+        compile_error!("`batch_tick!()` 只能直接用于设置了 `batch` 参数的 #[tracing_fn] 函数体中, 不能嵌套在其他宏中")
+    };
+}
+
+/// 为当前线程中最内层的被追踪调用记录一个字段，附加在其退出事件的末尾
 ///
-/// #[tracing_fn(level = "info")]
-/// fn example_fn2(a: i32, b: String) -> i32 {
-///     a + b.len() as i32
-/// }
+/// 值以 Debug 格式化，同名的字段只保留最后一次记录的值。被追踪的调用基于与 `fanout` 相同的线程局部栈帧，
+/// async fn 不参与：在 async fn 中记录的字段属于外层最近的被追踪的同步调用，没有被追踪的调用时忽略。
 ///
-/// #[tracing_fn(skip = "b")]
-/// fn example_fn3(a: i32, b: String) -> i32 {
-///     a + b.len() as i32
-/// }
+/// # 示例
+/// ```rust
+/// use tracing_fn::tracing_fn;
 ///
-/// // 强制在release模式下也启用tracing
-/// #[tracing_fn(force = true)]
-/// fn example_fn4(a: i32) -> i32 {
-///     a * 2
+/// #[tracing_fn]
+/// fn import(lines: &[&str]) -> usize {
+///     let rows = lines.iter().filter(|line| !line.is_empty()).count();
+///     tracing_fn::record_on_current!("rows", rows);
+///     rows
 /// }
+/// // <<< [import] #1 #Ret: 2, duration: 1.2µs, rows=2
+/// import(&["a", "", "b"]);
 /// ```
-#[proc_macro_attribute]
-pub fn tracing_fn(args: TokenStream, input: TokenStream) -> TokenStream {
-    let mut level = "trace".to_string();
-    let mut skip_args = Vec::new();
-    let mut force = false;
-
-    // 解析参数
-    if !args.is_empty() {
-        let args_string = args.to_string();
-        // 解析参数字符串
-        for arg in args_string.split(',') {
-            let arg = arg.trim();
-            if let Some(eq_pos) = arg.find('=') {
-                let key = arg[..eq_pos].trim();
-                let value = arg[eq_pos + 1..].trim();
-                // 去掉引号
-                let value = value.trim_matches(|c| c == '"' || c == '\'');
-
-                match key {
-                    "level" => level = value.to_string(),
-                    "skip" => {
-                        skip_args = value.split(',').map(|s| s.trim().to_string()).collect();
-                    }
-                    "force" => {
-                        force = value == "true";
-                    }
-                    _ => {} // 忽略未知参数
-                }
-            }
-        }
-    }
-
-    let input_fn = parse_macro_input!(input as ItemFn);
-    let fn_name = &input_fn.sig.ident;
-    let fn_vis = &input_fn.vis;
-    let fn_block = &input_fn.block;
-    let fn_sig = &input_fn.sig;
-    let fn_attrs = &input_fn.attrs;
-
-    // 获取所有参数名
-    let mut arg_names = Vec::new();
-    let mut arg_values = Vec::new();
-    for arg in &fn_sig.inputs {
-        if let syn::FnArg::Typed(pat_type) = arg {
-            if let syn::Pat::Ident(ident) = &*pat_type.pat {
-                let arg_name = ident.ident.to_string();
-                arg_names.push(arg_name.clone());
-                if !skip_args.contains(&arg_name) {
-                    let ident = &ident.ident;
-                    arg_values.push(quote! {
-                        format!("{}={:?}", #arg_name, #ident)
-                    });
-                } else {
-                    arg_values.push(quote! {
-                        format!("{}={}", #arg_name, "***")
-                    });
-                }
-            }
-        }
-    }
-
-    let level_ident = syn::Ident::new(&level.to_uppercase(), proc_macro2::Span::call_site());
-    let fn_name_str = fn_name.to_string();
-
-    // 根据force参数决定是否在release模式下强制启用
-    let expanded = if force {
-        // 如果force=true，则无论什么模式都启用tracing
-        quote! {
-            #(#fn_attrs)*
-            #fn_vis #fn_sig {
-                {
-                    let __tracing_fn_args: Vec<String> = vec![#(#arg_values),*];
-                    let __tracing_fn_args_str = if __tracing_fn_args.is_empty() {
-                        "()".to_string()
-                    } else {
-                        __tracing_fn_args.join(", ")
-                    };
-                    tracing::event!(
-                        tracing::Level::#level_ident,
-                        ">>> [{}] #Args: {} --- {}:{}",
-                        #fn_name_str,
-                        __tracing_fn_args_str,
-                        file!(),
-                        line!()
-                    );
-                }
-
-                let __tracing_fn_start = std::time::Instant::now();
-                let __tracing_fn_result = (move || #fn_block )();
-                let __tracing_fn_duration = __tracing_fn_start.elapsed();
-
-                tracing::event!(
-                    tracing::Level::#level_ident,
-                    "<<< [{}] #Ret: {:?}, duration: {:?}",
-                    #fn_name_str,
-                    __tracing_fn_result,
-                    __tracing_fn_duration
-                );
-
-                __tracing_fn_result
-            }
-        }
-    } else {
-        // 否则仅在debug模式下启用tracing
-        quote! {
-            #(#fn_attrs)*
-            #fn_vis #fn_sig {
-                #[cfg(debug_assertions)]
-                {
-                    let __tracing_fn_args: Vec<String> = vec![#(#arg_values),*];
-                    let __tracing_fn_args_str = if __tracing_fn_args.is_empty() {
-                        "()".to_string()
-                    } else {
-                        __tracing_fn_args.join(", ")
-                    };
-                    tracing::event!(
-                        tracing::Level::#level_ident,
-                        ">>> [{}] #Args: {} --- {}:{}",
-                        #fn_name_str,
-                        __tracing_fn_args_str,
-                        file!(),
-                        line!()
-                    );
-                }
-
-                #[cfg(debug_assertions)]
-                {
-                    let __tracing_fn_start = std::time::Instant::now();
-                    let __tracing_fn_result = (move || #fn_block )();
-                    let __tracing_fn_duration = __tracing_fn_start.elapsed();
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! record_on_current {
+    ($key:literal, $value:expr $(,)?) => {
+        $crate::runtime::record_on_current($key, &$value)
+    };
+}
 
-                    tracing::event!(
-                        tracing::Level::#level_ident,
-                        "<<< [{}] #Ret:  {:?}, duration: {:?}",
-                        #fn_name_str,
-                        __tracing_fn_result,
-                        __tracing_fn_duration
-                    );
+/// 把 [`golden::capture`] 收集到的输出与快照文件比较，不一致时使测试失败
+///
+/// 快照文件为测试源文件所在目录中的 `snapshots/<文件名>__<name>.snap`，格式与 insta 兼容，参见 [`golden`]。
+///
+/// # 示例
+/// ```rust,ignore
+/// let output = tracing_fn::golden::capture(|| {
+///     add(1, 2);
+/// });
+/// tracing_fn::assert_golden!("add", output);
+/// ```
+#[cfg(feature = "golden")]
+#[macro_export]
+macro_rules! assert_golden {
+    ($name:expr, $capture:expr $(,)?) => {
+        $crate::golden::assert_snapshot(
+            ::core::env!("CARGO_MANIFEST_DIR"),
+            ::core::file!(),
+            $name,
+            ::core::stringify!($capture),
+            &$capture,
+        )
+    };
+}
 
-                    __tracing_fn_result
-                }
+/// 断言 [`test_util::capture`] 之后当前线程中函数被记录的调用次数
+///
+/// 不指定 `times` 时断言至少被调用一次。调用次数为入口事件与退出事件中较多的一方的数量，
+/// 函数名可以省略 `qualified`、`self_type` 等参数附加的前缀。
+///
+/// # 示例
+/// ```rust,ignore
+/// let _capture = tracing_fn::test_util::capture();
+/// login("admin", "hunter2");
+/// login("guest", "guest");
+/// tracing_fn::assert_traced!("login", times = 2);
+/// ```
+#[cfg(feature = "test-util")]
+#[macro_export]
+macro_rules! assert_traced {
+    ($name:expr $(,)?) => {
+        $crate::test_util::assert_traced($name, ::core::option::Option::None)
+    };
+    ($name:expr, times = $times:expr $(,)?) => {
+        $crate::test_util::assert_traced($name, ::core::option::Option::Some($times))
+    };
+}
 
-                // 在 Release 模式下直接执行原函数
-                #[cfg(not(debug_assertions))]
-                (move || #fn_block )()
-            }
-        }
+/// 断言 [`test_util::capture`] 之后当前线程中函数的每次调用的耗时都小于上限
+///
+/// 上限可以写为 `10ms`、`1.5s` 等带单位的字面量 (可选单位: ns, us, µs, ms, s)，或 `Duration` 类型的表达式。
+/// 耗时取自退出事件的 `duration_us` 字段，精度为微秒；没有带耗时的退出事件时断言失败。
+///
+/// # 示例
+/// ```rust,ignore
+/// let _capture = tracing_fn::test_util::capture();
+/// login("admin", "hunter2");
+/// tracing_fn::assert_duration_under!("login", 10ms);
+/// tracing_fn::assert_duration_under!("login", std::time::Duration::from_millis(10));
+/// ```
+#[cfg(feature = "test-util")]
+#[macro_export]
+macro_rules! assert_duration_under {
+    ($name:expr, $limit:literal $(,)?) => {
+        $crate::test_util::assert_duration_under(
+            $name,
+            $crate::test_util::parse_limit(::core::stringify!($limit)),
+        )
+    };
+    ($name:expr, $limit:expr $(,)?) => {
+        $crate::test_util::assert_duration_under($name, $limit)
     };
+}
 
-    TokenStream::from(expanded)
+/// 引入编译期生成的 tracing 统计报告
+///
+/// 设置环境变量 `TRACING_FN_REPORT=true` 编译时，宏会在 `$OUT_DIR/tracing_fn_report.tsv` 中按模块统计添加了 tracing 的函数数量、
+/// 各日志等级的数量以及使用 `force` 的数量。该宏展开为报告内容的 `&'static str`，需要 crate 带有 build.rs 以提供 `OUT_DIR`。
+///
+/// 报告随宏的展开逐步写入，应在所有被标注的函数之后调用 (例如 crate 根的末尾)。
+///
+/// # 示例
+/// ```rust,ignore
+/// pub const TRACING_REPORT: &str = tracing_fn::build_report!();
+/// ```
+#[macro_export]
+macro_rules! build_report {
+    () => {
+        ::core::include_str!(::core::concat!(
+            ::core::env!("OUT_DIR"),
+            "/tracing_fn_report.tsv"
+        ))
+    };
 }
@@ -0,0 +1,21 @@
+//! 参数预设
+//!
+//! [`opts!`](crate::opts) 定义的每个预设对应一个 [`Opts`] 常量，记录预设的参数，便于在运行时输出或检查。
+
+/// 由 [`opts!`](crate::opts) 定义的一组 `#[tracing_fn]` 参数
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Opts {
+    args: &'static str,
+}
+
+impl Opts {
+    #[doc(hidden)]
+    pub const fn new(args: &'static str) -> Self {
+        Self { args }
+    }
+
+    /// 预设参数的源码，例如 `level = "debug", skip(password)`
+    pub const fn args(&self) -> &'static str {
+        self.args
+    }
+}
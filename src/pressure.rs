@@ -0,0 +1,67 @@
+//! 日志管道压力
+//!
+//! 日志的输出端 (网络、磁盘或下游的采集服务) 变慢时，继续输出大量事件会拖慢应用本身。
+//! 通过 [`set_pressure_fn`] 注册一个返回当前压力的函数后，被追踪的函数在入口处读取一次压力，
+//! 按 `pressure` 参数指定的策略降级或丢弃事件。
+
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
+
+static PRESSURE_FN: OnceLock<fn() -> Pressure> = OnceLock::new();
+
+/// 日志管道的压力
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Pressure {
+    /// 正常输出所有事件，未注册压力函数时总是为该值
+    #[default]
+    Normal,
+    /// `pressure = "downgrade"` (默认) 的函数只输出函数名与耗时，`pressure = "drop"` 的函数不输出事件
+    High,
+    /// 只有 `pressure = "keep"` 的函数输出事件
+    Critical,
+}
+
+/// 重复设置压力函数时返回的错误
+#[derive(Debug)]
+pub struct SetPressureFnError(());
+
+impl fmt::Display for SetPressureFnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("pressure function has already been set")
+    }
+}
+
+impl Error for SetPressureFnError {}
+
+/// 设置全局的日志管道压力函数
+///
+/// 压力函数在每次调用被追踪的函数、且其事件会被记录时执行一次，应当只读取一个原子变量等开销很小的状态，
+/// 例如日志写入队列的长度。只能设置一次，应在程序启动时设置。
+///
+/// # 示例
+/// ```rust
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use tracing_fn::Pressure;
+///
+/// static QUEUED: AtomicUsize = AtomicUsize::new(0);
+///
+/// tracing_fn::set_pressure_fn(|| match QUEUED.load(Ordering::Relaxed) {
+///     0..1_000 => Pressure::Normal,
+///     1_000..10_000 => Pressure::High,
+///     _ => Pressure::Critical,
+/// })
+/// .unwrap();
+/// ```
+pub fn set_pressure_fn(pressure: fn() -> Pressure) -> Result<(), SetPressureFnError> {
+    PRESSURE_FN
+        .set(pressure)
+        .map_err(|_| SetPressureFnError(()))
+}
+
+/// 当前的日志管道压力，未设置压力函数时为 [`Pressure::Normal`]
+pub fn pressure() -> Pressure {
+    PRESSURE_FN
+        .get()
+        .map_or(Pressure::Normal, |pressure| pressure())
+}
@@ -0,0 +1,127 @@
+//! 运行时跳过的参数
+//!
+//! 使用 `runtime_skip = true` 参数的函数在输出事件时查询通过 [`set`] 为其设置的参数名，
+//! 这些参数与 `skip` 中的参数一样按 `redact` 指定的方式输出。发现某个参数泄露敏感信息时，
+//! 可以通过配置中心或管理接口调用 [`set`] 立即隐藏该参数，无需重新编译与部署。
+//!
+//! 函数以全限定名标识：`module_path!()` 加上函数名，impl 块中的方法还包括类型名，
+//! 例如 `my_crate::auth::login`、`my_crate::auth::Session::refresh`。
+//!
+//! [`set`] 返回之后开始的调用一定使用新的设置；与 [`set`] 同时进行的调用使用修改前或修改后的完整设置，
+//! 不会只看到其中一部分参数。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::runtime::sync::{AtomicBool, Ordering, RwLock, const_fn};
+
+#[cfg(not(loom))]
+static REGISTRY: Registry = Registry::new();
+
+/// 设置函数在运行时跳过的参数，替换之前的设置，`params` 为空时等同于 [`clear`]
+///
+/// 只对使用了 `runtime_skip = true` 参数的函数生效，之后开始的调用使用新的设置。
+///
+/// # 示例
+/// ```rust
+/// tracing_fn::redaction::set("my_crate::login", &["password", "otp"]);
+/// assert_eq!(tracing_fn::redaction::get("my_crate::login"), ["password", "otp"]);
+/// ```
+#[cfg(not(loom))]
+pub fn set(function: &str, params: &[&str]) {
+    REGISTRY.set(function, params);
+}
+
+/// 清除函数在运行时跳过的参数
+#[cfg(not(loom))]
+pub fn clear(function: &str) {
+    REGISTRY.clear(function);
+}
+
+/// 函数当前在运行时跳过的参数
+#[cfg(not(loom))]
+pub fn get(function: &str) -> Vec<String> {
+    REGISTRY
+        .current(function)
+        .0
+        .map(|params| params.to_vec())
+        .unwrap_or_default()
+}
+
+/// 取得函数当前在运行时跳过的参数, 同一次调用中的所有参数使用同一份设置
+#[doc(hidden)]
+#[cfg(not(loom))]
+pub fn current(function: &str) -> Redacted {
+    REGISTRY.current(function)
+}
+
+/// 按函数记录运行时跳过的参数
+#[doc(hidden)]
+pub struct Registry {
+    /// 是否设置过任何函数, 未设置时跳过加锁
+    active: AtomicBool,
+    table: RwLock<Option<HashMap<String, Arc<[String]>>>>,
+}
+
+impl Registry {
+    const_fn! {
+        pub fn new() -> Self {
+            Self {
+                active: AtomicBool::new(false),
+                table: RwLock::new(None),
+            }
+        }
+    }
+
+    pub fn set(&self, function: &str, params: &[&str]) {
+        if params.is_empty() {
+            self.clear(function);
+            return;
+        }
+        let params = params.iter().map(|param| param.to_string()).collect();
+        let mut table = self.table.write().unwrap_or_else(|e| e.into_inner());
+        table
+            .get_or_insert_with(HashMap::new)
+            .insert(function.to_string(), params);
+        // 在持有写锁时设置, 读到 true 的调用随后获取读锁时一定能看到这次修改
+        self.active.store(true, Ordering::Release);
+    }
+
+    pub fn clear(&self, function: &str) {
+        let mut table = self.table.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(table) = table.as_mut() {
+            table.remove(function);
+        }
+    }
+
+    pub fn current(&self, function: &str) -> Redacted {
+        if !self.active.load(Ordering::Acquire) {
+            return Redacted(None);
+        }
+        let table = self.table.read().unwrap_or_else(|e| e.into_inner());
+        Redacted(
+            table
+                .as_ref()
+                .and_then(|table| table.get(function))
+                .cloned(),
+        )
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 一次调用中在运行时跳过的参数
+#[doc(hidden)]
+pub struct Redacted(Option<Arc<[String]>>);
+
+impl Redacted {
+    pub fn contains(&self, param: &str) -> bool {
+        self.0
+            .as_ref()
+            .is_some_and(|params| params.iter().any(|p| p == param))
+    }
+}
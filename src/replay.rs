@@ -0,0 +1,181 @@
+//! 调用记录与回放
+//!
+//! 使用 `record = true` 参数的函数在每次正常返回时，把参数与返回值通过 serde 序列化为一行 JSON，
+//! 追加到进程的回放文件中。文件可以通过 [`load_replay`] 读回，用生产环境中的真实调用构造快照式的回归测试。
+
+use std::error::Error;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+// 首次写入时打开, 打开失败时为 None, 之后的调用不再记录
+static FILE: OnceLock<Option<Mutex<File>>> = OnceLock::new();
+
+/// 回放文件中的一次调用
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayRecord {
+    /// 函数的完整路径，例如 `my_crate::db::query`
+    pub function: String,
+    /// 按参数顺序排列的参数数组，`skip` 中的参数为 `null`
+    pub args: Value,
+    /// 返回值
+    pub ret: Value,
+}
+
+impl ReplayRecord {
+    /// 把参数反序列化为元组等类型，例如 `(u32, String)`
+    pub fn decode_args<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        T::deserialize(&self.args)
+    }
+
+    /// 把返回值反序列化为函数的返回值类型
+    pub fn decode_ret<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        T::deserialize(&self.ret)
+    }
+}
+
+/// 重新执行一次记录的调用的结果
+///
+/// 由 `replay = true` 生成的 `<函数名>__replay` 函数返回。
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplayResult {
+    /// 返回值与记录相同
+    Matched,
+    /// 返回值与记录不同
+    Mismatched {
+        /// 记录中的返回值
+        expected: Value,
+        /// 重新执行得到的返回值
+        actual: Value,
+    },
+    /// 记录不属于该函数，或参数无法反序列化 (例如 `skip` 中的参数被记录为 `null`)
+    Invalid(String),
+}
+
+impl ReplayResult {
+    /// 返回值是否与记录相同
+    pub fn is_matched(&self) -> bool {
+        matches!(self, ReplayResult::Matched)
+    }
+}
+
+/// 回放文件已经打开后设置路径时返回的错误
+#[derive(Debug)]
+pub struct SetReplayPathError(());
+
+impl fmt::Display for SetReplayPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("replay path has already been set or used")
+    }
+}
+
+impl Error for SetReplayPathError {}
+
+/// 设置回放文件的路径
+///
+/// 默认为系统临时目录下的 `tracing-fn-replay-<pid>.jsonl`。只能在首次记录调用之前设置一次，
+/// 文件不存在时创建，已存在时在末尾追加。
+///
+/// # 示例
+/// ```rust
+/// tracing_fn::set_replay_path("replay.jsonl").unwrap();
+/// ```
+pub fn set_replay_path(path: impl Into<PathBuf>) -> Result<(), SetReplayPathError> {
+    PATH.set(path.into()).map_err(|_| SetReplayPathError(()))
+}
+
+/// 当前进程的回放文件路径
+pub fn replay_path() -> &'static Path {
+    PATH.get_or_init(|| {
+        std::env::temp_dir().join(format!("tracing-fn-replay-{}.jsonl", std::process::id()))
+    })
+}
+
+/// 读取回放文件中的全部调用记录
+///
+/// # 示例
+/// ```rust,ignore
+/// for record in tracing_fn::load_replay("replay.jsonl")? {
+///     if record.function == "my_crate::pricing::quote" {
+///         let (sku, qty): (String, u32) = record.decode_args()?;
+///         assert_eq!(quote(&sku, qty), record.decode_ret()?);
+///     }
+/// }
+/// ```
+pub fn load_replay(path: impl AsRef<Path>) -> io::Result<Vec<ReplayRecord>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut value: Value = serde_json::from_str(&line)?;
+        let function = match value.get_mut("fn").map(Value::take) {
+            Some(Value::String(function)) => function,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "replay record is missing the `fn` field",
+                ));
+            }
+        };
+        records.push(ReplayRecord {
+            function,
+            args: value.get_mut("args").map(Value::take).unwrap_or_default(),
+            ret: value.get_mut("ret").map(Value::take).unwrap_or_default(),
+        });
+    }
+    Ok(records)
+}
+
+/// 比较重新执行得到的返回值与记录中的返回值
+#[doc(hidden)]
+pub fn replay_compare<R: Serialize + ?Sized>(record: &ReplayRecord, ret: &R) -> ReplayResult {
+    match serde_json::to_value(ret) {
+        Ok(actual) if actual == record.ret => ReplayResult::Matched,
+        Ok(actual) => ReplayResult::Mismatched {
+            expected: record.ret.clone(),
+            actual,
+        },
+        Err(err) => ReplayResult::Invalid(err.to_string()),
+    }
+}
+
+/// 在入口处序列化参数，参数可能在函数体中被移动
+#[doc(hidden)]
+pub fn replay_args<T: Serialize + ?Sized>(args: &T) -> Value {
+    serde_json::to_value(args).unwrap_or_default()
+}
+
+/// 把一次调用追加到回放文件，序列化失败的返回值记录为 `null`
+#[doc(hidden)]
+pub fn replay_append<R: Serialize + ?Sized>(function: &str, args: &Value, ret: &R) {
+    let Some(file) = FILE.get_or_init(|| {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(replay_path())
+            .ok()
+            .map(Mutex::new)
+    }) else {
+        return;
+    };
+    let record = serde_json::json!({
+        "fn": function,
+        "args": args,
+        "ret": serde_json::to_value(ret).unwrap_or_default(),
+    });
+    let mut line = record.to_string();
+    line.push('\n');
+    // 每条记录一次写入, 进程崩溃时最多丢失正在写入的一条
+    let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = file.write_all(line.as_bytes());
+}
@@ -0,0 +1,170 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+use super::sync::{AtomicU64, Ordering};
+use super::time::Instant;
+use crate::InvocationId;
+
+/// `batch` 参数的运行时状态
+///
+/// 每次调用持有一个实例，函数体中的 `batch_tick!()` 累加已处理的条目数，跨过 `chunk` 的整数倍时返回进度。
+/// 计数使用原子变量，闭包中的 `batch_tick!()` 可以在其他线程中执行 (例如 rayon 的并行迭代器)。
+pub struct BatchProgress {
+    /// 不输出事件时为 `None`
+    id: Option<InvocationId>,
+    total: Option<u64>,
+    chunk: u64,
+    start: Instant,
+    processed: AtomicU64,
+}
+
+/// 一次进度事件的内容
+pub struct BatchTick {
+    pub id: InvocationId,
+    /// 已处理的条目数
+    pub processed: u64,
+    /// 条目总数，无法得知时为 `None`
+    pub total: Option<u64>,
+    /// 自调用开始经过的时间
+    pub elapsed: Duration,
+}
+
+impl BatchProgress {
+    pub fn new(id: Option<InvocationId>, total: Option<usize>, chunk: u64) -> Self {
+        Self {
+            id,
+            total: total.map(|total| total as u64),
+            chunk: chunk.max(1),
+            start: Instant::now(),
+            processed: AtomicU64::new(0),
+        }
+    }
+
+    /// 累加 `count` 个已处理的条目，跨过 `chunk` 的整数倍时返回进度
+    pub fn tick(&self, count: u64) -> Option<BatchTick> {
+        let id = self.id.as_ref()?;
+        let prev = self.processed.fetch_add(count, Ordering::Relaxed);
+        let processed = prev.saturating_add(count);
+        if processed / self.chunk == prev / self.chunk {
+            return None;
+        }
+        Some(BatchTick {
+            id: id.clone(),
+            processed,
+            total: self.total,
+            elapsed: self.start.elapsed(),
+        })
+    }
+}
+
+impl BatchTick {
+    /// 每秒处理的条目数
+    pub fn rate(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.processed as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// 输出为 `300/1000` 或总数未知时的 `300`
+impl Display for BatchTick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.total {
+            Some(total) => write!(f, "{}/{}", self.processed, total),
+            None => write!(f, "{}", self.processed),
+        }
+    }
+}
+
+/// 可以在入口处得知条目总数的集合
+pub trait BatchLen {
+    fn batch_len(&self) -> usize;
+}
+
+impl<T> BatchLen for [T] {
+    fn batch_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T, const N: usize> BatchLen for [T; N] {
+    fn batch_len(&self) -> usize {
+        N
+    }
+}
+
+impl<T> BatchLen for Vec<T> {
+    fn batch_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> BatchLen for VecDeque<T> {
+    fn batch_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K, V, S> BatchLen for HashMap<K, V, S> {
+    fn batch_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T, S> BatchLen for HashSet<T, S> {
+    fn batch_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K, V> BatchLen for BTreeMap<K, V> {
+    fn batch_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> BatchLen for BTreeSet<T> {
+    fn batch_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: BatchLen + ?Sized> BatchLen for &T {
+    fn batch_len(&self) -> usize {
+        (**self).batch_len()
+    }
+}
+
+impl<T: BatchLen + ?Sized> BatchLen for &mut T {
+    fn batch_len(&self) -> usize {
+        (**self).batch_len()
+    }
+}
+
+/// `batch(items)` 中的集合，与 [`DebugWrap`](super::DebugWrap) 一样通过 autoref 选择实现:
+/// 实现了 [`BatchLen`] 时返回条目总数，否则返回 `None`
+pub struct BatchItems<'a, T>(pub &'a T);
+
+pub trait ViaBatchLen {
+    fn __tracing_fn_batch_len(&self) -> Option<usize>;
+}
+
+impl<T: BatchLen> ViaBatchLen for &BatchItems<'_, T> {
+    fn __tracing_fn_batch_len(&self) -> Option<usize> {
+        Some(self.0.batch_len())
+    }
+}
+
+pub trait ViaUnknownLen {
+    fn __tracing_fn_batch_len(&self) -> Option<usize>;
+}
+
+impl<T> ViaUnknownLen for BatchItems<'_, T> {
+    fn __tracing_fn_batch_len(&self) -> Option<usize> {
+        None
+    }
+}
@@ -0,0 +1,173 @@
+// 以 loom 编译时只保留可以单独测试的 RecentCalls, panic hook 与线程局部的记录不参与编译
+#![cfg_attr(loom, allow(dead_code, unused_imports))]
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::marker::PhantomData;
+use std::sync::{Once, TryLockError};
+use std::thread::{self, ThreadId};
+
+use super::sync::{Mutex, const_fn};
+use crate::InvocationId;
+
+/// 黑匣子中最多保留的调用记录数
+const CAPACITY: usize = 64;
+
+#[cfg(not(loom))]
+static RECENT: RecentCalls = RecentCalls::new(CAPACITY);
+#[cfg(not(loom))]
+static HOOK: Once = Once::new();
+
+thread_local! {
+    // 当前线程中尚未返回的 abort_safe 调用
+    static IN_FLIGHT: RefCell<Vec<Record>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Clone)]
+struct Record {
+    id: InvocationId,
+    name: &'static str,
+    args: String,
+    thread: ThreadId,
+}
+
+/// `abort_safe` 调用的入口记录
+///
+/// 进入函数时写入黑匣子，析构时从当前线程的未返回调用中移除。
+/// 在 `panic = "abort"` 下析构不会执行，panic hook 依然能从中取得 panic 时尚未返回的调用。
+pub struct InFlight {
+    // 记录保存在线程局部变量中, 不能跨线程移动
+    _not_send: PhantomData<*const ()>,
+}
+
+#[cfg(not(loom))]
+impl InFlight {
+    pub fn enter(id: InvocationId, name: &'static str, args: String) -> Self {
+        let record = push_recent(id, name, args);
+        IN_FLIGHT.with(|in_flight| in_flight.borrow_mut().push(record));
+        Self {
+            _not_send: PhantomData,
+        }
+    }
+
+    /// 只写入最近调用记录，用于可能在线程间切换的 async fn
+    pub fn record(id: InvocationId, name: &'static str, args: String) {
+        push_recent(id, name, args);
+    }
+}
+
+#[cfg(not(loom))]
+fn push_recent(id: InvocationId, name: &'static str, args: String) -> Record {
+    RECENT.push(id, name, args)
+}
+
+/// 最近的调用记录, 超出容量时丢弃最早的记录
+#[doc(hidden)]
+pub struct RecentCalls {
+    capacity: usize,
+    records: Mutex<VecDeque<Record>>,
+}
+
+impl RecentCalls {
+    const_fn! {
+        pub fn new(capacity: usize) -> Self {
+            Self {
+                capacity,
+                records: Mutex::new(VecDeque::new()),
+            }
+        }
+    }
+
+    fn push(&self, id: InvocationId, name: &'static str, args: String) -> Record {
+        let record = Record {
+            id,
+            name,
+            args,
+            thread: thread::current().id(),
+        };
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record.clone());
+        record
+    }
+
+    /// 写入一条记录
+    pub fn record(&self, id: InvocationId, name: &'static str, args: String) {
+        self.push(id, name, args);
+    }
+
+    /// 按写入顺序排列的记录的函数名
+    pub fn names(&self) -> Vec<&'static str> {
+        self.snapshot().iter().map(|record| record.name).collect()
+    }
+
+    /// 记录的副本, 用于 panic hook, 其他线程持有锁时返回空的副本以免死锁
+    fn snapshot(&self) -> VecDeque<Record> {
+        match self.records.try_lock() {
+            Ok(records) => records.clone(),
+            Err(TryLockError::Poisoned(e)) => e.into_inner().clone(),
+            Err(TryLockError::WouldBlock) => VecDeque::new(),
+        }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        IN_FLIGHT.with(|in_flight| {
+            in_flight.borrow_mut().pop();
+        });
+    }
+}
+
+/// 安装 panic hook，在 panic 时通过 `report` 输出黑匣子中的内容，然后调用原有的 hook
+///
+/// 只有首次调用生效。`report` 由宏在使用方 crate 中生成，事件因此经由使用方的 tracing 输出。
+#[cfg(not(loom))]
+pub fn install_panic_hook(report: fn(&str)) {
+    HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            report(&render(&info.to_string()));
+            previous(info);
+        }));
+    });
+}
+
+#[cfg(not(loom))]
+fn render(panic: &str) -> String {
+    let mut out = String::from(panic);
+
+    // panic 可能发生在持有借用或锁时, 均使用 try 版本避免死锁
+    let in_flight = IN_FLIGHT
+        .try_with(|in_flight| in_flight.try_borrow().map(|v| v.clone()).ok())
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    out.push_str("\nin-flight: ");
+    if in_flight.is_empty() {
+        out.push_str("(none)");
+    }
+    for (i, record) in in_flight.iter().enumerate() {
+        if i > 0 {
+            out.push_str(" > ");
+        }
+        let _ = write!(out, "#{} {}({})", record.id, record.name, record.args);
+    }
+
+    let recent = RECENT.snapshot();
+    out.push_str("\nrecent:");
+    if recent.is_empty() {
+        out.push_str(" (none)");
+    }
+    for record in recent.iter().rev() {
+        let _ = write!(
+            out,
+            "\n  [{:?}] #{} {}({})",
+            record.thread, record.id, record.name, record.args
+        );
+    }
+    out
+}
@@ -0,0 +1,18 @@
+use std::fmt::{Display, Write};
+
+/// 把 `with` 参数指定的提供函数返回的键值对格式化为 `k1=v1, k2=v2`，没有键值对时返回 `None`
+pub fn render_context<I, K, V>(pairs: I) -> Option<String>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Display,
+    V: Display,
+{
+    let mut out = String::new();
+    for (key, value) in pairs {
+        if !out.is_empty() {
+            out.push_str(", ");
+        }
+        let _ = write!(out, "{key}={value}");
+    }
+    (!out.is_empty()).then_some(out)
+}
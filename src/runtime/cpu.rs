@@ -0,0 +1,20 @@
+use std::time::Duration;
+
+use cpu_time::ThreadTime;
+
+/// 当前线程的 CPU 时间，用于 `measure = "cpu"`
+pub struct CpuTime(Option<ThreadTime>);
+
+impl CpuTime {
+    pub fn now() -> Self {
+        Self(ThreadTime::try_now().ok())
+    }
+
+    /// 当前线程自 `now` 以来消耗的 CPU 时间，无法获取时为 0
+    pub fn elapsed(&self) -> Duration {
+        self.0
+            .as_ref()
+            .and_then(|start| start.try_elapsed().ok())
+            .unwrap_or_default()
+    }
+}
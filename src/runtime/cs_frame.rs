@@ -0,0 +1,109 @@
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+
+/// 记录嵌套调用统计的最大层数，更深的调用仍然计入深度，但不再单独统计
+const MAX_DEPTH: usize = 32;
+
+static STACK: Mutex<RefCell<Stack>> = Mutex::new(RefCell::new(Stack {
+    frames: [FrameData {
+        calls: 0,
+        failed: 0,
+    }; MAX_DEPTH],
+    len: 0,
+}));
+
+struct Stack {
+    frames: [FrameData; MAX_DEPTH],
+    len: usize,
+}
+
+#[derive(Clone, Copy)]
+struct FrameData {
+    calls: u64,
+    failed: u64,
+}
+
+/// 一次被追踪函数调用的栈帧，no_std 下的实现
+///
+/// 没有线程局部变量，栈帧保存在由 `critical-section` 保护的全局栈中，
+/// 适用于单核的裸机环境。中断处理函数中的调用会计为被中断函数的嵌套调用。
+pub struct Frame {
+    depth: usize,
+    exited: bool,
+}
+
+/// 一次调用期间发生的嵌套调用统计 (包含所有层级)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CallCounts {
+    /// 被追踪的嵌套调用次数
+    pub calls: u64,
+    /// 其中返回 `Err` 的次数
+    pub failed: u64,
+}
+
+impl Frame {
+    pub fn enter() -> Self {
+        let depth = critical_section::with(|cs| {
+            let mut stack = STACK.borrow_ref_mut(cs);
+            let depth = stack.len;
+            if let Some(frame) = stack.frames.get_mut(depth) {
+                *frame = FrameData {
+                    calls: 0,
+                    failed: 0,
+                };
+            }
+            stack.len += 1;
+            depth
+        });
+        Self {
+            depth,
+            exited: false,
+        }
+    }
+
+    /// 外层尚未返回的被追踪调用数，最外层为 0
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// 退出栈帧，返回本次调用期间的嵌套调用统计
+    pub fn exit(mut self, failed: bool) -> CallCounts {
+        self.exited = true;
+        pop(failed)
+    }
+}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        if !self.exited {
+            pop(true);
+        }
+    }
+}
+
+fn pop(failed: bool) -> CallCounts {
+    critical_section::with(|cs| {
+        let mut stack = STACK.borrow_ref_mut(cs);
+        stack.len = stack.len.saturating_sub(1);
+        let len = stack.len;
+        let frame = stack.frames.get(len).copied().unwrap_or(FrameData {
+            calls: 0,
+            failed: 0,
+        });
+        if let Some(parent) = len.checked_sub(1).and_then(|i| stack.frames.get_mut(i)) {
+            parent.calls += frame.calls + 1;
+            parent.failed += frame.failed + u64::from(failed);
+        }
+        CallCounts {
+            calls: frame.calls,
+            failed: frame.failed,
+        }
+    })
+}
+
+/// 与调用深度对应的缩进，每层两个空格，超过 32 层时不再增加
+pub fn indent(depth: usize) -> &'static str {
+    const SPACES: &str = "                                                                ";
+    &SPACES[..depth.min(SPACES.len() / 2) * 2]
+}
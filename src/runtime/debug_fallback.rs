@@ -0,0 +1,75 @@
+//! 未实现 `Debug` 的参数与返回值的输出
+//!
+//! 宏生成的代码通过 autoref 选择实现: `(&&DebugWrap(&value)).__tracing_fn_debug()` 优先匹配要求 `T: Debug`
+//! 的 [`ViaDebug`] (接收者为 `&DebugWrap`)，不满足时退回到对任意类型实现的 [`ViaOpaque`] (接收者为 `DebugWrap`)，
+//! 输出 `<non-Debug 类型名>`。选择在调用处按具体类型进行，泛型参数只有在带有 `Debug` 约束时才会输出其 `Debug`。
+
+use core::any::type_name;
+use core::fmt::{self, Debug};
+
+use super::try_value::FmtBreak;
+
+/// 被输出的值
+pub struct DebugWrap<'a, T>(pub &'a T);
+
+/// 选择的输出方式
+pub enum MaybeDebug<'a> {
+    /// 值的 `Debug` 输出
+    Debug(&'a dyn Debug),
+    /// `?` 的操作数中导致提前返回的部分
+    Residual(&'a dyn FmtBreak),
+    /// 未实现 `Debug` 的值的类型名
+    Opaque(&'static str),
+    /// 函数指针与闭包的类型名
+    Callable(&'static str),
+}
+
+impl Debug for MaybeDebug<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Debug(value) => value.fmt(f),
+            Self::Residual(value) => value.fmt_break(f),
+            Self::Opaque(name) => write!(f, "<non-Debug {name}>"),
+            Self::Callable(name) => write!(f, "<fn {name}>"),
+        }
+    }
+}
+
+/// 实现了 `Debug` 的值
+pub trait ViaDebug<'a> {
+    fn __tracing_fn_debug(&self) -> MaybeDebug<'a>;
+}
+
+impl<'a, T: Debug> ViaDebug<'a> for &DebugWrap<'a, T> {
+    fn __tracing_fn_debug(&self) -> MaybeDebug<'a> {
+        MaybeDebug::Debug(self.0)
+    }
+}
+
+/// 可以输出导致提前返回的部分的 `?` 的操作数
+pub trait ViaFmtBreak<'a> {
+    fn __tracing_fn_residual(&self) -> MaybeDebug<'a>;
+}
+
+impl<'a, T: FmtBreak> ViaFmtBreak<'a> for &DebugWrap<'a, T> {
+    fn __tracing_fn_residual(&self) -> MaybeDebug<'a> {
+        MaybeDebug::Residual(self.0)
+    }
+}
+
+/// 其余的值, 只输出类型名
+pub trait ViaOpaque<'a> {
+    fn __tracing_fn_debug(&self) -> MaybeDebug<'a>;
+
+    fn __tracing_fn_residual(&self) -> MaybeDebug<'a>;
+}
+
+impl<'a, T> ViaOpaque<'a> for DebugWrap<'a, T> {
+    fn __tracing_fn_debug(&self) -> MaybeDebug<'a> {
+        MaybeDebug::Opaque(type_name::<T>())
+    }
+
+    fn __tracing_fn_residual(&self) -> MaybeDebug<'a> {
+        MaybeDebug::Opaque(type_name::<T>())
+    }
+}
@@ -0,0 +1,61 @@
+use super::sync::{Mutex, const_fn};
+
+/// `dedup` 参数的运行时状态
+///
+/// 每个函数持有一个静态实例，记录最近一次调用的参数。参数与上一次相同的调用不输出事件，只计数；
+/// 参数不同的调用到来时先汇总之前被省略的次数，再输出该调用的事件。
+pub struct Dedup {
+    last: Mutex<Option<Last>>,
+}
+
+struct Last {
+    args: String,
+    repeated: u64,
+}
+
+/// 单次调用的判定结果
+pub struct DedupDecision {
+    /// 是否输出该调用的事件
+    pub emit: bool,
+    /// 参数变化前被省略的重复调用次数
+    pub repeated: Option<u64>,
+}
+
+impl Dedup {
+    const_fn! {
+        pub fn new() -> Self {
+            Self {
+                last: Mutex::new(None),
+            }
+        }
+    }
+
+    pub fn observe(&self, args: String) -> DedupDecision {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        match &mut *last {
+            Some(last) if last.args == args => {
+                last.repeated += 1;
+                DedupDecision {
+                    emit: false,
+                    repeated: None,
+                }
+            }
+            _ => {
+                let repeated = last
+                    .replace(Last { args, repeated: 0 })
+                    .map(|last| last.repeated)
+                    .filter(|&repeated| repeated > 0);
+                DedupDecision {
+                    emit: true,
+                    repeated,
+                }
+            }
+        }
+    }
+}
+
+impl Default for Dedup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
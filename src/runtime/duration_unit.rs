@@ -0,0 +1,29 @@
+use core::fmt::{self, Display, Formatter};
+use core::time::Duration;
+
+/// `duration_unit`: 耗时的输出单位
+#[derive(Clone, Copy)]
+pub enum DurationUnit {
+    Ns,
+    Us,
+    Ms,
+    S,
+}
+
+/// 以固定的单位输出耗时，例如 `2.813us`、`0.003ms`
+///
+/// `Duration` 的 Debug 输出按大小在 ns、µs、ms、s 之间切换单位，不便于按文本匹配与比较。
+/// 纳秒输出为整数，其余单位保留到纳秒精度。
+pub struct InUnit(pub Duration, pub DurationUnit);
+
+impl Display for InUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let nanos = self.0.as_nanos();
+        match self.1 {
+            DurationUnit::Ns => write!(f, "{nanos}ns"),
+            DurationUnit::Us => write!(f, "{}.{:03}us", nanos / 1_000, nanos % 1_000),
+            DurationUnit::Ms => write!(f, "{}.{:06}ms", nanos / 1_000_000, nanos % 1_000_000),
+            DurationUnit::S => write!(f, "{}.{:09}s", nanos / 1_000_000_000, nanos % 1_000_000_000),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::Deref;
+
+/// `err = true`: 以 `Err(外层错误: 内层错误: ...)` 的形式输出错误及其 `source()` 链
+///
+/// 每一层使用 `Display` 输出，外层错误的 `Debug` 往往不包含真正导致失败的底层 IO 或数据库错误。
+pub struct ErrChain<'a, E: ?Sized>(pub &'a E);
+
+impl<E: Error + ?Sized> fmt::Display for ErrChain<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Err({}", self.0)?;
+        let mut source = self.0.source();
+        while let Some(err) = source {
+            write!(f, ": {err}")?;
+            source = err.source();
+        }
+        f.write_str(")")
+    }
+}
+
+/// `err = true` 中的错误, 与 [`DebugWrap`](super::DebugWrap) 一样通过 autoref 选择实现:
+/// `(&&ErrSource(err)).__tracing_fn_err_chain()` 优先匹配实现了 `Error` 的错误类型 ([`ViaError`])，
+/// 不满足时匹配解引用为 `Error` 的类型 ([`ViaDerefError`])，例如没有实现 `Error` 的 `Box<dyn Error>`
+/// 与 `Box<dyn Error + Send + Sync>`。
+pub struct ErrSource<'a, E>(pub &'a E);
+
+/// 实现了 `Error` 的错误
+pub trait ViaError<'a, E: ?Sized> {
+    fn __tracing_fn_err_chain(&self) -> ErrChain<'a, E>;
+}
+
+impl<'a, E: Error> ViaError<'a, E> for &ErrSource<'a, E> {
+    fn __tracing_fn_err_chain(&self) -> ErrChain<'a, E> {
+        ErrChain(self.0)
+    }
+}
+
+/// 解引用为 `Error` 的错误
+pub trait ViaDerefError<'a, E: ?Sized> {
+    fn __tracing_fn_err_chain(&self) -> ErrChain<'a, E>;
+}
+
+impl<'a, B: Deref> ViaDerefError<'a, B::Target> for ErrSource<'a, B>
+where
+    B::Target: Error,
+{
+    fn __tracing_fn_err_chain(&self) -> ErrChain<'a, B::Target> {
+        ErrChain(&**self.0)
+    }
+}
+
+/// `err = "alternate"`: 以 `Err({:#})` 的形式输出错误
+///
+/// `anyhow::Error` 与 `eyre::Report` 的 `{:#}` 输出在一行内包含所有的上下文，`Debug` 输出则跨越多行。
+pub struct ErrAlternate<'a, E: ?Sized>(pub &'a E);
+
+impl<E: fmt::Display + ?Sized> fmt::Display for ErrAlternate<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Err({:#})", self.0)
+    }
+}
@@ -0,0 +1,47 @@
+use super::sync::{AtomicU64, Ordering, const_fn};
+
+/// `first` 参数的运行时状态
+///
+/// 每个函数持有一个静态实例，统计事件会被记录的调用次数。前 `limit` 次调用输出事件，
+/// 第 `limit + 1` 次调用只输出一条提示，之后的调用不再输出。
+pub struct FirstN {
+    limit: u64,
+    calls: AtomicU64,
+}
+
+/// 单次调用的判定结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FirstDecision {
+    /// 输出事件
+    Emit,
+    /// 刚超过次数限制, 输出一条提示
+    Notice,
+    /// 不输出
+    Suppress,
+}
+
+impl FirstN {
+    const_fn! {
+        pub fn new(limit: u64) -> Self {
+            Self {
+                limit,
+                calls: AtomicU64::new(0),
+            }
+        }
+    }
+
+    pub fn admit(&self) -> FirstDecision {
+        // 超过限制后只读取计数, 避免高频调用时争用缓存行
+        if self.calls.load(Ordering::Relaxed) > self.limit {
+            return FirstDecision::Suppress;
+        }
+        let calls = self.calls.fetch_add(1, Ordering::Relaxed);
+        if calls < self.limit {
+            FirstDecision::Emit
+        } else if calls == self.limit {
+            FirstDecision::Notice
+        } else {
+            FirstDecision::Suppress
+        }
+    }
+}
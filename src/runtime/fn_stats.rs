@@ -0,0 +1,156 @@
+//! `#[tracing_main]` 的退出统计
+//!
+//! 启用 main-stats feature 时，每个添加了 tracing 的函数在函数体中持有一个 [`FnStats`]，
+//! `#[tracing_main]` 调用 [`main_enter`] 之后累计被记录的调用的次数、失败次数与耗时，
+//! main 返回时由 [`main_summary`] 取得所有函数的统计。未使用 `#[tracing_main]` 时每次调用只增加一次原子读取，
+//! 未启用该 feature 时被追踪的函数不生成统计。
+
+#[cfg(not(loom))]
+use std::sync::Once;
+use std::time::Duration;
+
+use super::sync::{AtomicBool, AtomicU64, Mutex, Ordering, const_fn};
+
+#[cfg(not(loom))]
+static REGISTRY: StatsRegistry = StatsRegistry::new();
+#[cfg(not(loom))]
+static HOOK: Once = Once::new();
+
+/// 一个函数的调用统计
+pub struct FnStats {
+    name: &'static str,
+    registered: AtomicBool,
+    calls: AtomicU64,
+    failed: AtomicU64,
+    total_ns: AtomicU64,
+    max_ns: AtomicU64,
+}
+
+impl FnStats {
+    const_fn! {
+        pub fn new(name: &'static str) -> Self {
+            Self {
+                name,
+                registered: AtomicBool::new(false),
+                calls: AtomicU64::new(0),
+                failed: AtomicU64::new(0),
+                total_ns: AtomicU64::new(0),
+                max_ns: AtomicU64::new(0),
+            }
+        }
+    }
+
+    /// 记录一次调用, 未调用 [`main_enter`] 时不做任何事
+    #[cfg(not(loom))]
+    pub fn record(&'static self, duration: Duration, failed: bool) {
+        self.record_in(&REGISTRY, duration, failed);
+    }
+
+    /// 在 `registry` 中记录一次调用, 每个函数只在首次记录时加入 `registry`
+    #[doc(hidden)]
+    pub fn record_in(&'static self, registry: &StatsRegistry, duration: Duration, failed: bool) {
+        if !registry.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        if !self.registered.swap(true, Ordering::Relaxed) {
+            registry
+                .stats
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .push(self);
+        }
+        let ns = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.failed.fetch_add(u64::from(failed), Ordering::Relaxed);
+        self.total_ns.fetch_add(ns, Ordering::Relaxed);
+        self.max_ns.fetch_max(ns, Ordering::Relaxed);
+    }
+}
+
+/// [`main_summary`] 中一个函数的统计
+#[derive(Clone, Debug)]
+pub struct FnSummary {
+    pub name: &'static str,
+    pub calls: u64,
+    pub failed: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl FnSummary {
+    /// 平均每次调用的耗时
+    pub fn mean(&self) -> Duration {
+        match self.calls {
+            0 => Duration::ZERO,
+            calls => Duration::from_nanos(self.total.as_nanos() as u64 / calls),
+        }
+    }
+}
+
+/// 开始统计并安装 panic hook, panic 时先通过 `report` 输出 panic 的信息, 然后调用原有的 hook
+///
+/// 只有首次调用安装 panic hook。`report` 由宏在使用方 crate 中生成，事件因此经由使用方的 tracing 输出。
+#[cfg(not(loom))]
+pub fn main_enter(report: fn(&str)) {
+    REGISTRY.enable();
+    HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            report(&info.to_string());
+            previous(info);
+        }));
+    });
+}
+
+/// 所有被调用过的函数的统计, 按总耗时从大到小排列
+#[cfg(not(loom))]
+pub fn main_summary() -> Vec<FnSummary> {
+    REGISTRY.summary()
+}
+
+/// 被调用过的函数的 [`FnStats`]
+#[doc(hidden)]
+pub struct StatsRegistry {
+    /// 是否已调用 [`main_enter`]
+    enabled: AtomicBool,
+    stats: Mutex<Vec<&'static FnStats>>,
+}
+
+impl StatsRegistry {
+    const_fn! {
+        pub fn new() -> Self {
+            Self {
+                enabled: AtomicBool::new(false),
+                stats: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    /// 开始统计, 之后开始的调用被记录
+    pub fn enable(&self) {
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    /// 所有被调用过的函数的统计, 按总耗时从大到小排列
+    pub fn summary(&self) -> Vec<FnSummary> {
+        let stats = self.stats.lock().unwrap_or_else(|err| err.into_inner());
+        let mut summary = stats
+            .iter()
+            .map(|stats| FnSummary {
+                name: stats.name,
+                calls: stats.calls.load(Ordering::Relaxed),
+                failed: stats.failed.load(Ordering::Relaxed),
+                total: Duration::from_nanos(stats.total_ns.load(Ordering::Relaxed)),
+                max: Duration::from_nanos(stats.max_ns.load(Ordering::Relaxed)),
+            })
+            .collect::<Vec<_>>();
+        summary.sort_by(|a, b| b.total.cmp(&a.total).then(a.name.cmp(b.name)));
+        summary
+    }
+}
+
+impl Default for StatsRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
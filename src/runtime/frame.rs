@@ -0,0 +1,145 @@
+use std::cell::RefCell;
+use std::fmt::{self, Debug, Display};
+use std::marker::PhantomData;
+
+thread_local! {
+    static FRAMES: RefCell<Vec<FrameData>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Default)]
+struct FrameData {
+    name: &'static str,
+    calls: u64,
+    failed: u64,
+    recorded: Vec<(&'static str, String)>,
+}
+
+/// 当前线程中一次被追踪函数调用的栈帧
+///
+/// 进入函数时压栈，退出时出栈并把该调用期间发生的嵌套调用统计累加到上层栈帧。
+/// 如果函数发生 panic，栈帧在析构时出栈并计为一次失败的调用。
+pub struct Frame {
+    depth: usize,
+    caller: Option<&'static str>,
+    exited: bool,
+    // 栈帧保存在线程局部变量中, 不能跨线程移动
+    _not_send: PhantomData<*const ()>,
+}
+
+/// 一次调用期间发生的嵌套调用统计 (包含所有层级)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CallCounts {
+    /// 被追踪的嵌套调用次数
+    pub calls: u64,
+    /// 其中返回 `Err` 或 panic 的次数
+    pub failed: u64,
+}
+
+impl Frame {
+    /// 进入名为 `name` 的函数的栈帧
+    pub fn enter(name: &'static str) -> Self {
+        let (depth, caller) = FRAMES.with(|frames| {
+            let mut frames = frames.borrow_mut();
+            let caller = frames.last().map(|frame| frame.name);
+            frames.push(FrameData {
+                name,
+                ..FrameData::default()
+            });
+            (frames.len() - 1, caller)
+        });
+        Self {
+            depth,
+            caller,
+            exited: false,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// 当前线程中外层尚未返回的被追踪调用数，最外层为 0
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// 当前线程中最近的外层被追踪调用的函数名，最外层为 `None`
+    pub fn caller(&self) -> Option<&'static str> {
+        self.caller
+    }
+
+    /// 取出函数体中通过 `record_on_current!` 记录的字段
+    pub fn recorded(&self) -> Recorded {
+        FRAMES.with(|frames| {
+            let mut frames = frames.borrow_mut();
+            Recorded(
+                frames
+                    .get_mut(self.depth)
+                    .map(|frame| std::mem::take(&mut frame.recorded))
+                    .unwrap_or_default(),
+            )
+        })
+    }
+
+    /// 退出栈帧，返回本次调用期间的嵌套调用统计
+    pub fn exit(mut self, failed: bool) -> CallCounts {
+        self.exited = true;
+        pop(failed)
+    }
+}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        if !self.exited {
+            pop(true);
+        }
+    }
+}
+
+fn pop(failed: bool) -> CallCounts {
+    FRAMES.with(|frames| {
+        let mut frames = frames.borrow_mut();
+        let frame = frames.pop().unwrap_or_default();
+        if let Some(parent) = frames.last_mut() {
+            parent.calls += frame.calls + 1;
+            parent.failed += frame.failed + u64::from(failed);
+        }
+        CallCounts {
+            calls: frame.calls,
+            failed: frame.failed,
+        }
+    })
+}
+
+/// 以 Debug 格式化 `value`，记录到当前线程中最内层的被追踪调用的栈帧，没有被追踪的调用时忽略
+///
+/// 同名的字段只保留最后一次记录的值。
+pub fn record_on_current(key: &'static str, value: &dyn Debug) {
+    FRAMES.with(|frames| {
+        let mut frames = frames.borrow_mut();
+        let Some(frame) = frames.last_mut() else {
+            return;
+        };
+        let value = format!("{value:?}");
+        match frame.recorded.iter_mut().find(|(name, _)| *name == key) {
+            Some((_, recorded)) => *recorded = value,
+            None => frame.recorded.push((key, value)),
+        }
+    });
+}
+
+/// 一次调用中记录的字段，输出为 `, key=value` 的序列
+#[derive(Default)]
+pub struct Recorded(Vec<(&'static str, String)>);
+
+impl Display for Recorded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.0 {
+            write!(f, ", {key}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// 与调用深度对应的缩进，每层两个空格，超过 32 层时不再增加
+pub fn indent(depth: usize) -> &'static str {
+    const SPACES: &str = "                                                                ";
+    &SPACES[..depth.min(SPACES.len() / 2) * 2]
+}
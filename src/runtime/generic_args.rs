@@ -0,0 +1,20 @@
+use core::fmt::{self, Display, Formatter};
+
+/// `generics`: 泛型函数的类型参数，输出为 `::<u32, alloc::string::String>`
+///
+/// 类型名来自 `core::any::type_name`，在每次调用时取得，不分配内存。
+#[derive(Clone, Copy)]
+pub struct GenericArgs<const N: usize>(pub [&'static str; N]);
+
+impl<const N: usize> Display for GenericArgs<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("::<")?;
+        for (i, name) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(name)?;
+        }
+        f.write_str(">")
+    }
+}
@@ -0,0 +1,84 @@
+use std::fmt::{self, Debug, Display, Write};
+
+/// 退出路径上使用的字符串缓冲区
+///
+/// 内容不超过 `N` 字节时保存在栈上的固定缓冲区中，超出后才转为堆上的 `String`，
+/// 常见的返回值 (整数、短字符串、`Ok(())` 等) 在格式化时因此不分配内存。
+pub struct InlineString<const N: usize = 128> {
+    repr: Repr<N>,
+}
+
+enum Repr<const N: usize> {
+    Inline { buf: [u8; N], len: usize },
+    Heap(String),
+}
+
+impl<const N: usize> InlineString<N> {
+    pub const fn new() -> Self {
+        Self {
+            repr: Repr::Inline {
+                buf: [0; N],
+                len: 0,
+            },
+        }
+    }
+
+    /// 以 `{:?}` 格式化 `value`
+    pub fn debug<T: Debug + ?Sized>(value: &T) -> Self {
+        let mut out = Self::new();
+        let _ = write!(out, "{value:?}");
+        out
+    }
+
+    /// 以 `{}` 格式化 `value`
+    pub fn display<T: Display + ?Sized>(value: &T) -> Self {
+        let mut out = Self::new();
+        let _ = write!(out, "{value}");
+        out
+    }
+
+    pub fn as_str(&self) -> &str {
+        match &self.repr {
+            // SAFETY: 缓冲区只通过 `write_str` 以完整的 `&str` 为单位写入, 前 len 字节总是合法的 UTF-8
+            Repr::Inline { buf, len } => unsafe { std::str::from_utf8_unchecked(&buf[..*len]) },
+            Repr::Heap(s) => s,
+        }
+    }
+
+    /// 内容是否保存在栈上
+    pub fn is_inline(&self) -> bool {
+        matches!(self.repr, Repr::Inline { .. })
+    }
+}
+
+impl<const N: usize> Default for InlineString<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Write for InlineString<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match &mut self.repr {
+            Repr::Inline { buf, len } if *len + s.len() <= N => {
+                buf[*len..*len + s.len()].copy_from_slice(s.as_bytes());
+                *len += s.len();
+            }
+            Repr::Inline { buf, len } => {
+                let mut heap = String::with_capacity((*len + s.len()).max(N * 2));
+                // SAFETY: 同 as_str
+                heap.push_str(unsafe { std::str::from_utf8_unchecked(&buf[..*len]) });
+                heap.push_str(s);
+                self.repr = Repr::Heap(heap);
+            }
+            Repr::Heap(heap) => heap.push_str(s),
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> fmt::Display for InlineString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
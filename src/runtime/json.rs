@@ -0,0 +1,6 @@
+use serde::Serialize;
+
+/// `json`: 把参数序列化为 JSON 字符串，序列化失败时输出错误信息
+pub fn to_json<T: Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_else(|err| format!("<json error: {err}>"))
+}
@@ -0,0 +1,122 @@
+//! 运行时支持
+//!
+//! 该模块仅供宏生成的代码使用，不属于公开 API，可能随时变化。
+
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "std")]
+mod blackbox;
+#[cfg(feature = "std")]
+mod context;
+#[cfg(feature = "cpu")]
+mod cpu;
+#[cfg(all(not(feature = "std"), feature = "critical-section"))]
+mod cs_frame;
+mod debug_fallback;
+#[cfg(feature = "std")]
+mod dedup;
+mod duration_unit;
+#[cfg(feature = "std")]
+mod error_chain;
+#[cfg(feature = "std")]
+mod first;
+#[cfg(feature = "std")]
+mod fn_stats;
+#[cfg(feature = "std")]
+mod frame;
+mod generic_args;
+#[cfg(feature = "std")]
+mod inline_string;
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "std")]
+mod once_per;
+#[cfg(feature = "std")]
+mod poll_state;
+#[cfg(feature = "std")]
+mod poll_timer;
+#[cfg(feature = "std")]
+mod prefix;
+mod redact;
+mod summary;
+#[cfg(feature = "std")]
+pub(crate) mod sync;
+#[cfg(feature = "tokio")]
+mod task;
+#[cfg(feature = "test-subscriber")]
+mod test_subscriber;
+#[cfg(feature = "std")]
+mod time;
+mod truncate;
+mod try_value;
+#[cfg(feature = "usdt")]
+mod usdt;
+
+#[cfg(feature = "std")]
+pub use batch::{BatchItems, BatchLen, BatchProgress, BatchTick, ViaBatchLen, ViaUnknownLen};
+#[cfg(all(feature = "std", not(loom)))]
+pub use blackbox::install_panic_hook;
+#[cfg(feature = "std")]
+pub use blackbox::{InFlight, RecentCalls};
+#[cfg(feature = "std")]
+pub use context::render_context;
+#[cfg(feature = "cpu")]
+pub use cpu::CpuTime;
+#[cfg(all(not(feature = "std"), feature = "critical-section"))]
+pub use cs_frame::{CallCounts, Frame, indent};
+pub use debug_fallback::{DebugWrap, MaybeDebug, ViaDebug, ViaFmtBreak, ViaOpaque};
+#[cfg(feature = "std")]
+pub use dedup::{Dedup, DedupDecision};
+pub use duration_unit::{DurationUnit, InUnit};
+#[cfg(feature = "std")]
+pub use error_chain::{ErrAlternate, ErrChain, ErrSource, ViaDerefError, ViaError};
+#[cfg(feature = "std")]
+pub use first::{FirstDecision, FirstN};
+#[cfg(feature = "std")]
+pub use fn_stats::{FnStats, FnSummary, StatsRegistry};
+#[cfg(all(feature = "std", not(loom)))]
+pub use fn_stats::{main_enter, main_summary};
+#[cfg(feature = "std")]
+pub use frame::{CallCounts, Frame, Recorded, indent, record_on_current};
+pub use generic_args::GenericArgs;
+#[cfg(feature = "std")]
+pub use inline_string::InlineString;
+#[cfg(feature = "serde")]
+pub use json::to_json;
+#[cfg(feature = "std")]
+pub use once_per::{OncePer, OncePerDecision, OncePerSummary};
+#[cfg(feature = "std")]
+pub use poll_state::{PollTracker, ReadyStats};
+#[cfg(feature = "std")]
+pub use poll_timer::{PollStats, PollTimer};
+#[cfg(feature = "std")]
+pub use prefix::PrefixCache;
+pub use redact::Sha256Prefix;
+pub use summary::{Summarize, Summary, SummaryWrap, ViaSummarize, ViaTypeName};
+#[cfg(feature = "tokio")]
+pub use task::task_id;
+#[cfg(feature = "test-subscriber")]
+pub use test_subscriber::init_test_subscriber;
+#[cfg(feature = "std")]
+pub use time::Instant;
+#[cfg(feature = "golden")]
+pub(crate) use time::freeze_clock;
+pub use truncate::Truncate;
+pub use try_value::{FmtBreak, TryValue, async_body, call_once};
+#[cfg(feature = "usdt")]
+pub use usdt::{usdt_entry, usdt_exit};
+
+#[cfg(feature = "alloc-count")]
+pub use crate::alloc::{AllocSnapshot, AllocStats};
+#[cfg(feature = "std")]
+pub use crate::audit::{AuditChain, AuditRecord};
+#[cfg(feature = "binary")]
+pub use crate::binary::{BinaryCall, BinaryFn};
+#[cfg(feature = "win-etw")]
+pub use crate::etw::{EtwKind, etw_enabled, etw_write};
+#[cfg(feature = "record-replay")]
+pub use crate::replay::{replay_append, replay_args, replay_compare};
+#[cfg(feature = "std")]
+pub use crate::tenant::current_tenant;
+#[cfg(not(feature = "std"))]
+pub use crate::ticks::ticks;
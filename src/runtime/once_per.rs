@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Write};
+use std::hash::{DefaultHasher, Hasher};
+use std::time::Duration;
+
+use super::sync::{Mutex, const_fn};
+use super::time::Instant;
+
+/// `once_per` 参数的运行时状态
+///
+/// 每个函数持有一个静态实例，使用容量有限的 LRU 记录已出现过的 key。
+/// key 首次出现时输出完整的事件，之后同一 key 的调用只计数，并按 `period` 周期汇总输出。
+pub struct OncePer {
+    capacity: usize,
+    period: Duration,
+    state: Mutex<State>,
+}
+
+/// 单次调用的判定结果
+pub struct OncePerDecision {
+    /// 是否输出完整的事件
+    pub emit: bool,
+    /// 距上次汇总已超过汇总周期时，返回期间被抑制的调用统计
+    pub summary: Option<OncePerSummary>,
+}
+
+/// 汇总周期内被抑制的调用统计
+pub struct OncePerSummary {
+    /// 被抑制的调用次数
+    pub suppressed: u64,
+    /// 当前 LRU 中记录的 key 数量
+    pub keys: usize,
+}
+
+struct State {
+    tick: u64,
+    // key 的哈希 -> 最近一次访问的 tick
+    keys: BTreeMap<u64, u64>,
+    // 最近一次访问的 tick -> key 的哈希, 用于淘汰最久未访问的 key
+    ticks: BTreeMap<u64, u64>,
+    suppressed: u64,
+    last_summary: Option<Instant>,
+}
+
+impl OncePer {
+    const_fn! {
+        pub fn new(capacity: usize, period: Duration) -> Self {
+            Self {
+                capacity,
+                period,
+                state: Mutex::new(State {
+                    tick: 0,
+                    keys: BTreeMap::new(),
+                    ticks: BTreeMap::new(),
+                    suppressed: 0,
+                    last_summary: None,
+                }),
+            }
+        }
+    }
+
+    /// 记录一次调用，key 以其 `Debug` 输出区分
+    pub fn observe<K: Debug + ?Sized>(&self, key: &K) -> OncePerDecision {
+        let hash = hash_debug(key);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let last_summary = *state.last_summary.get_or_insert(now);
+
+        state.tick += 1;
+        let tick = state.tick;
+        let emit = match state.keys.insert(hash, tick) {
+            Some(prev) => {
+                state.ticks.remove(&prev);
+                state.suppressed += 1;
+                false
+            }
+            None => true,
+        };
+        state.ticks.insert(tick, hash);
+        if state.keys.len() > self.capacity.max(1)
+            && let Some((_, oldest)) = state.ticks.pop_first()
+        {
+            state.keys.remove(&oldest);
+        }
+
+        let summary = if state.suppressed > 0 && now.duration_since(last_summary) >= self.period {
+            let summary = OncePerSummary {
+                suppressed: state.suppressed,
+                keys: state.keys.len(),
+            };
+            state.suppressed = 0;
+            state.last_summary = Some(now);
+            Some(summary)
+        } else {
+            None
+        };
+
+        OncePerDecision { emit, summary }
+    }
+}
+
+/// 不分配内存地计算 `Debug` 输出的哈希
+fn hash_debug<K: Debug + ?Sized>(key: &K) -> u64 {
+    struct HashWriter(DefaultHasher);
+
+    impl Write for HashWriter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0.write(s.as_bytes());
+            Ok(())
+        }
+    }
+
+    let mut writer = HashWriter(DefaultHasher::new());
+    let _ = write!(writer, "{key:?}");
+    writer.0.finish()
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::Duration;
+
+use super::sync::{Mutex, const_fn};
+use super::time::Instant;
+
+/// 同时记录的等待中实例数的上限，超出时丢弃等待时间最长的实例
+const CAPACITY: usize = 1024;
+
+/// `poll` 参数的运行时状态
+///
+/// 每个函数持有一个静态实例，按 id 记录返回 `Poll::Pending` 后尚未就绪的 future 或 stream。
+/// 实例在返回 `Poll::Ready` 时移除，未就绪就被丢弃的实例在超出容量后按等待时间淘汰。
+pub struct PollTracker {
+    pending: Mutex<BTreeMap<u64, Pending>>,
+}
+
+struct Pending {
+    since: Instant,
+    polls: u64,
+}
+
+/// 一个实例从首次返回 `Pending` 到返回 `Ready` 期间的统计
+#[derive(Clone, Copy, Default)]
+pub struct ReadyStats {
+    /// poll 次数，包括返回 `Ready` 的一次
+    pub polls: u64,
+    /// 从首次返回 `Pending` 到返回 `Ready` 经过的时间，首次 poll 即就绪时为 0
+    pub pending: Duration,
+}
+
+impl PollTracker {
+    const_fn! {
+        pub fn new() -> Self {
+            Self {
+                pending: Mutex::new(BTreeMap::new()),
+            }
+        }
+    }
+
+    /// 记录一次返回 `Pending` 的 poll，实例由未等待变为等待时返回 true
+    pub fn pending(&self, id: &str) -> bool {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(state) = pending.get_mut(&hash(id)) {
+            state.polls += 1;
+            return false;
+        }
+        if pending.len() >= CAPACITY
+            && let Some(oldest) = pending
+                .iter()
+                .max_by_key(|(_, state)| state.since.elapsed())
+                .map(|(key, _)| *key)
+        {
+            pending.remove(&oldest);
+        }
+        pending.insert(
+            hash(id),
+            Pending {
+                since: Instant::now(),
+                polls: 1,
+            },
+        );
+        true
+    }
+
+    /// 记录一次返回 `Ready` 的 poll，返回该实例本次等待期间的统计并清除其状态
+    pub fn ready(&self, id: &str) -> ReadyStats {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        match pending.remove(&hash(id)) {
+            Some(state) => ReadyStats {
+                polls: state.polls + 1,
+                pending: state.since.elapsed(),
+            },
+            None => ReadyStats {
+                polls: 1,
+                pending: Duration::ZERO,
+            },
+        }
+    }
+}
+
+impl Default for PollTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn hash(id: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,49 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use super::time::Instant;
+
+/// 记录每次 poll 耗时的 future 包装
+///
+/// 用于 `slow_poll` 参数，完成时连同输出返回期间最长的单次 poll 耗时，
+/// 用于发现在两个 await 点之间长时间占用执行器线程的 async fn。
+pub struct PollTimer<F> {
+    inner: F,
+    stats: PollStats,
+}
+
+/// 一个 future 从创建到完成期间的 poll 统计
+#[derive(Clone, Copy, Default)]
+pub struct PollStats {
+    /// 最长的单次 poll 耗时
+    pub longest: Duration,
+    /// poll 次数
+    pub polls: u64,
+}
+
+impl<F> PollTimer<F> {
+    pub fn new(inner: F) -> Self {
+        Self {
+            inner,
+            stats: PollStats::default(),
+        }
+    }
+}
+
+impl<F: Future> Future for PollTimer<F> {
+    type Output = (F::Output, PollStats);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: `inner` 不会被移出, `PollTimer` 也没有实现 Drop,
+        // 因此对 `inner` 的结构化 pin 投影是安全的
+        let this = unsafe { self.get_unchecked_mut() };
+        let inner = unsafe { Pin::new_unchecked(&mut this.inner) };
+        let start = Instant::now();
+        let poll = inner.poll(cx);
+        this.stats.longest = this.stats.longest.max(start.elapsed());
+        this.stats.polls += 1;
+        poll.map(|output| (output, this.stats))
+    }
+}
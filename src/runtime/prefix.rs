@@ -0,0 +1,61 @@
+// 引用编号只需要唯一, 不与其他状态同步, 使用标准库的原子类型以便以 loom 编译时仍可用于 static
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use super::sync::{Mutex, const_fn};
+use super::time::Instant;
+
+/// 全局递增的引用编号, 保证不同函数的引用在日志中不会混淆
+static NEXT_TOKEN: AtomicU64 = AtomicU64::new(1);
+
+/// `compress_prefix` 参数的运行时状态
+///
+/// 每个函数持有一个静态实例，记录最近一次输出的参数前缀。
+/// 前缀与上一次相同且距其完整输出未超过 `window` 时，只输出引用编号；
+/// 超过 `window` 后重新输出完整前缀，避免日志被截断或轮转后无法找到引用的内容。
+pub struct PrefixCache {
+    window: Duration,
+    last: Mutex<Option<Entry>>,
+}
+
+struct Entry {
+    prefix: String,
+    token: u64,
+    defined: Instant,
+}
+
+impl PrefixCache {
+    const_fn! {
+        pub fn new(window: Duration) -> Self {
+            Self {
+                window,
+                last: Mutex::new(None),
+            }
+        }
+    }
+
+    /// 返回用于输出的前缀，首次出现时为 `[@N: 前缀]`，之后为 `[@N]`
+    pub fn compress(&self, prefix: String) -> String {
+        let now = Instant::now();
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        match &mut *last {
+            Some(entry) if entry.prefix == prefix => {
+                if now.duration_since(entry.defined) < self.window {
+                    return format!("[@{}]", entry.token);
+                }
+                entry.defined = now;
+                format!("[@{}: {}]", entry.token, prefix)
+            }
+            _ => {
+                let token = NEXT_TOKEN.fetch_add(1, Ordering::Relaxed);
+                let out = format!("[@{}: {}]", token, prefix);
+                *last = Some(Entry {
+                    prefix,
+                    token,
+                    defined: now,
+                });
+                out
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! `summarize` 参数: 只输出集合的类型与长度, 不输出其中的内容
+
+use core::any::type_name;
+use core::fmt::{self, Debug, Display};
+
+/// 可以输出摘要的集合
+pub trait Summarize {
+    /// 输出摘要，例如 `Vec(len=10432)`
+    fn summarize(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+macro_rules! summarize_len {
+    ($name:literal, [$($generics:tt)*] $ty:ty) => {
+        impl<$($generics)*> Summarize for $ty {
+            fn summarize(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, concat!($name, "(len={})"), self.len())
+            }
+        }
+    };
+}
+
+summarize_len!("slice", [T][T]);
+summarize_len!("array", [T, const N: usize] [T; N]);
+summarize_len!("str", [] str);
+
+#[cfg(feature = "std")]
+mod std_impls {
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+    use std::fmt;
+
+    use super::Summarize;
+
+    summarize_len!("Vec", [T] Vec<T>);
+    summarize_len!("VecDeque", [T] VecDeque<T>);
+    summarize_len!("String", [] String);
+    summarize_len!("HashMap", [K, V, S] HashMap<K, V, S>);
+    summarize_len!("HashSet", [T, S] HashSet<T, S>);
+    summarize_len!("BTreeMap", [K, V] BTreeMap<K, V>);
+    summarize_len!("BTreeSet", [T] BTreeSet<T>);
+
+    impl<T: Summarize + ?Sized> Summarize for Box<T> {
+        fn summarize(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            (**self).summarize(f)
+        }
+    }
+
+    impl<T: Summarize + ?Sized> Summarize for std::rc::Rc<T> {
+        fn summarize(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            (**self).summarize(f)
+        }
+    }
+
+    impl<T: Summarize + ?Sized> Summarize for std::sync::Arc<T> {
+        fn summarize(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            (**self).summarize(f)
+        }
+    }
+}
+
+impl<T: Summarize + ?Sized> Summarize for &T {
+    fn summarize(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).summarize(f)
+    }
+}
+
+impl<T: Summarize + ?Sized> Summarize for &mut T {
+    fn summarize(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).summarize(f)
+    }
+}
+
+impl<T: Summarize> Summarize for Option<T> {
+    fn summarize(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Some(value) => {
+                f.write_str("Some(")?;
+                value.summarize(f)?;
+                f.write_str(")")
+            }
+            None => f.write_str("None"),
+        }
+    }
+}
+
+/// 被输出摘要的参数，与 [`DebugWrap`](super::DebugWrap) 一样通过 autoref 选择实现:
+/// 实现了 [`Summarize`] 时输出摘要，否则只输出类型名
+pub struct SummaryWrap<'a, T>(pub &'a T);
+
+/// 选择的输出方式，同时实现了 `Display` 与 `Debug`
+pub enum Summary<'a> {
+    Summarize(&'a dyn Summarize),
+    Opaque(&'static str),
+}
+
+impl Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Summarize(value) => value.summarize(f),
+            Self::Opaque(name) => write!(f, "<{name}>"),
+        }
+    }
+}
+
+impl Debug for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+pub trait ViaSummarize<'a> {
+    fn __tracing_fn_summary(&self) -> Summary<'a>;
+}
+
+impl<'a, T: Summarize> ViaSummarize<'a> for &SummaryWrap<'a, T> {
+    fn __tracing_fn_summary(&self) -> Summary<'a> {
+        Summary::Summarize(self.0)
+    }
+}
+
+pub trait ViaTypeName<'a> {
+    fn __tracing_fn_summary(&self) -> Summary<'a>;
+}
+
+impl<'a, T> ViaTypeName<'a> for SummaryWrap<'a, T> {
+    fn __tracing_fn_summary(&self) -> Summary<'a> {
+        Summary::Opaque(type_name::<T>())
+    }
+}
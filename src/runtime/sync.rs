@@ -0,0 +1,35 @@
+//! 运行时状态使用的同步原语
+//!
+//! 宏生成的代码为每个函数创建静态的运行时状态 (调用计数、上一次调用的参数、审计链等)，
+//! 被追踪的函数在多个线程中以很高的频率调用时，这些状态由所有线程共享。
+//! 以 `--cfg loom` 编译时同步原语替换为 loom 的实现，`tests/loom.rs` 穷举线程的交错检查其并发正确性：
+//!
+//! ```sh
+//! RUSTFLAGS="--cfg loom" cargo test --release --test loom
+//! ```
+//!
+//! loom 的原语不能在常量上下文中创建，需要用于 static 的构造函数通过 [`const_fn!`] 定义，以 loom 编译时不是 const fn。
+
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(loom)]
+pub(crate) use loom::sync::{Mutex, RwLock};
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(not(loom))]
+pub(crate) use std::sync::{Mutex, RwLock};
+
+/// 定义一个通常为 const fn、以 loom 编译时为普通函数的构造函数
+macro_rules! const_fn {
+    ($(#[$attr:meta])* $vis:vis fn $name:ident($($args:tt)*) -> $ret:ty $body:block) => {
+        #[cfg(not(loom))]
+        $(#[$attr])*
+        $vis const fn $name($($args)*) -> $ret $body
+
+        #[cfg(loom)]
+        $(#[$attr])*
+        $vis fn $name($($args)*) -> $ret $body
+    };
+}
+
+pub(crate) use const_fn;
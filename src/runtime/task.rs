@@ -0,0 +1,4 @@
+/// 当前 tokio 任务的 ID，不在 tokio 任务中执行时返回 `None`
+pub fn task_id() -> Option<tokio::task::Id> {
+    tokio::task::try_id()
+}
@@ -0,0 +1,11 @@
+//! `test_subscriber = true`: 测试函数的 subscriber
+//!
+//! 测试线程的输出由 libtest 捕获，只在测试失败或使用 `--nocapture` 时显示。
+
+/// 初始化输出所有等级事件的全局 subscriber, 已设置全局 subscriber 时不做任何事
+pub fn init_test_subscriber() {
+    let _ = tracing_subscriber::fmt()
+        .with_test_writer()
+        .with_max_level(tracing::Level::TRACE)
+        .try_init();
+}
@@ -0,0 +1,85 @@
+//! 计时
+//!
+//! `wasm32-unknown-unknown` 上 `std::time::Instant::now()` 会 panic。启用 `wasm` feature 时改用浏览器的
+//! `performance.now()`，未启用时在该目标上退化为不计时 (耗时始终为 0)，其余目标使用 `std::time::Instant`。
+
+use std::time::Duration;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+type Repr = std::time::Instant;
+
+/// 自时间原点经过的毫秒数
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+type Repr = f64;
+
+/// 单调时钟的时间点
+#[derive(Clone, Copy)]
+pub struct Instant(Repr);
+
+impl Instant {
+    pub fn now() -> Self {
+        #[cfg(feature = "golden")]
+        if let Some(frozen) = FROZEN.get() {
+            return Self(frozen);
+        }
+        Self(now())
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Self::now().duration_since(*self)
+    }
+
+    /// `earlier` 晚于当前时间点时返回 0
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        {
+            self.0.saturating_duration_since(earlier.0)
+        }
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        {
+            Duration::from_secs_f64((self.0 - earlier.0).max(0.0) / 1000.0)
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn now() -> Repr {
+    std::time::Instant::now()
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", feature = "wasm"))]
+fn now() -> Repr {
+    use wasm_bindgen::JsCast;
+
+    // 同时支持主线程 (Window) 与 Web Worker, 取全局对象上的 performance
+    js_sys::Reflect::get(&js_sys::global(), &"performance".into())
+        .ok()
+        .and_then(|performance| performance.dyn_into::<web_sys::Performance>().ok())
+        .map_or(0.0, |performance| performance.now())
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", not(feature = "wasm")))]
+fn now() -> Repr {
+    0.0
+}
+
+#[cfg(feature = "golden")]
+thread_local! {
+    static FROZEN: std::cell::Cell<Option<Repr>> = const { std::cell::Cell::new(None) };
+}
+
+/// golden: 冻结当前线程的计时, 冻结期间所有的耗时均为 0, 返回的守卫析构时恢复
+#[cfg(feature = "golden")]
+pub(crate) fn freeze_clock() -> FrozenClock {
+    FrozenClock(FROZEN.replace(Some(now())))
+}
+
+#[cfg(feature = "golden")]
+pub(crate) struct FrozenClock(Option<Repr>);
+
+#[cfg(feature = "golden")]
+impl Drop for FrozenClock {
+    fn drop(&mut self) {
+        FROZEN.set(self.0.take());
+    }
+}
@@ -0,0 +1,60 @@
+use core::fmt::{self, Debug, Display, Formatter, Write};
+
+/// `max_len`: 格式化结果超过指定字节数时截断，并追加 `…(+N bytes)`
+///
+/// 超出的部分仍会被格式化以统计字节数，但不会写入输出，较大的值因此不会产生同样大的字符串。
+/// 截断位置向前对齐到字符边界。
+pub struct Truncate<T>(pub T, pub usize);
+
+impl<T: Debug> Debug for Truncate<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        truncate(f, self.1, |out| write!(out, "{:?}", self.0))
+    }
+}
+
+impl<T: Display> Display for Truncate<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        truncate(f, self.1, |out| write!(out, "{}", self.0))
+    }
+}
+
+fn truncate(
+    f: &mut Formatter<'_>,
+    max_len: usize,
+    write: impl FnOnce(&mut Limit<'_, '_>) -> fmt::Result,
+) -> fmt::Result {
+    let mut limit = Limit {
+        f,
+        remaining: max_len,
+        skipped: 0,
+    };
+    write(&mut limit)?;
+    if limit.skipped > 0 {
+        write!(limit.f, "…(+{} bytes)", limit.skipped)?;
+    }
+    Ok(())
+}
+
+/// 只写入前 `remaining` 字节, 之后的内容只计数
+struct Limit<'a, 'b> {
+    f: &'a mut Formatter<'b>,
+    remaining: usize,
+    skipped: usize,
+}
+
+impl Write for Limit<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.len() <= self.remaining {
+            self.remaining -= s.len();
+            return self.f.write_str(s);
+        }
+        let mut end = self.remaining;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        // 截断后不再写入, 避免之后较短的片段接在截断处
+        self.remaining = 0;
+        self.skipped += s.len() - end;
+        self.f.write_str(&s[..end])
+    }
+}
@@ -0,0 +1,111 @@
+use core::fmt::{self, Debug};
+use core::future::Future;
+use core::ops::ControlFlow;
+use core::task::Poll;
+
+/// `?` 运算符的操作数
+///
+/// 宏把函数体中的 `expr?` 改写为先观察 `expr` 再执行 `?`，从而在不改变控制流的前提下得知函数经 `?` 提前返回的原因。
+/// 实现覆盖了标准库中所有实现了 `Try` 的类型。
+pub trait TryValue {
+    /// `?` 是否会提前返回
+    fn is_break(&self) -> bool;
+}
+
+/// 可以输出导致提前返回的部分的 [`TryValue`]，要求其中的错误等类型实现了 `Debug`
+pub trait FmtBreak: TryValue {
+    /// 输出导致提前返回的部分，例如 `Err(e)`、`None`
+    fn fmt_break(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+impl<T, E> TryValue for Result<T, E> {
+    fn is_break(&self) -> bool {
+        self.is_err()
+    }
+}
+
+impl<T, E: Debug> FmtBreak for Result<T, E> {
+    fn fmt_break(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Err(e) => write!(f, "Err({e:?})"),
+            Ok(_) => Ok(()),
+        }
+    }
+}
+
+impl<T> TryValue for Option<T> {
+    fn is_break(&self) -> bool {
+        self.is_none()
+    }
+}
+
+impl<T> FmtBreak for Option<T> {
+    fn fmt_break(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            None => f.write_str("None"),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+impl<B, C> TryValue for ControlFlow<B, C> {
+    fn is_break(&self) -> bool {
+        self.is_break()
+    }
+}
+
+impl<B: Debug, C> FmtBreak for ControlFlow<B, C> {
+    fn fmt_break(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControlFlow::Break(b) => write!(f, "Break({b:?})"),
+            ControlFlow::Continue(_) => Ok(()),
+        }
+    }
+}
+
+impl<T, E> TryValue for Poll<Result<T, E>> {
+    fn is_break(&self) -> bool {
+        matches!(self, Poll::Ready(Err(_)))
+    }
+}
+
+impl<T, E: Debug> FmtBreak for Poll<Result<T, E>> {
+    fn fmt_break(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Poll::Ready(Err(e)) => write!(f, "Err({e:?})"),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<T, E> TryValue for Poll<Option<Result<T, E>>> {
+    fn is_break(&self) -> bool {
+        matches!(self, Poll::Ready(Some(Err(_))))
+    }
+}
+
+impl<T, E: Debug> FmtBreak for Poll<Option<Result<T, E>>> {
+    fn fmt_break(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Poll::Ready(Some(Err(e))) => write!(f, "Err({e:?})"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 以 `FnOnce` 调用函数体
+///
+/// 函数体中有可能提前返回的宏调用时，宏在闭包中执行函数体，使宏展开出的 `return` 同样经过返回值的记录。
+/// 以 `FnOnce` 调用的闭包可以返回借用自其捕获的 `&mut` 引用的值。
+#[doc(hidden)]
+pub fn call_once<R>(body: impl FnOnce() -> R) -> R {
+    body()
+}
+
+/// 约束 async 块的输出类型
+///
+/// 同 [`call_once`]，用于 async fn。async 块不能标注返回值类型，其中 `?` 的错误类型由这里的约束推断。
+#[doc(hidden)]
+pub fn async_body<T, F: Future<Output = T>>(body: F) -> F {
+    body
+}
@@ -0,0 +1,26 @@
+//! USDT 探针
+//!
+//! 启用 `usdt` feature 时，被追踪的函数在入口与退出处经过 SystemTap 格式的静态探针 `tracing_fn:entry`
+//! 与 `tracing_fn:exit`，bpftrace、perf 等工具附加到探针后才会求值参数，未附加时只有一次信号量检查。
+//! 不支持 SDT 的平台上探针为空操作。
+
+use std::time::Duration;
+
+/// 入口探针, 参数为函数全限定名的指针与长度
+#[inline]
+pub fn usdt_entry(function: &'static str) {
+    probe::probe_lazy!(tracing_fn, entry, function.as_ptr(), function.len());
+}
+
+/// 退出探针, 参数为函数全限定名的指针与长度、以纳秒为单位的耗时、是否失败 (返回 `Err` 或 panic 时为 1)
+#[inline]
+pub fn usdt_exit(function: &'static str, duration: Duration, failed: bool) {
+    probe::probe_lazy!(
+        tracing_fn,
+        exit,
+        function.as_ptr(),
+        function.len(),
+        duration.as_nanos() as u64,
+        u8::from(failed)
+    );
+}
@@ -0,0 +1,54 @@
+//! 租户标识
+//!
+//! 多租户服务可以通过 [`set_tenant_provider`] 注册一个获取当前租户 (客户) 标识的函数，
+//! 使用 `tenant = true` 参数的函数会在入口与退出事件中附带 `tenant` 字段，无需在每个函数中传递上下文参数。
+
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
+
+type Provider = Box<dyn Fn() -> Option<String> + Send + Sync>;
+
+static PROVIDER: OnceLock<Provider> = OnceLock::new();
+
+/// 获取当前的租户标识，未设置提供函数或提供函数返回 `None` 时返回 `None`
+#[doc(hidden)]
+pub fn current_tenant() -> Option<String> {
+    PROVIDER.get().and_then(|provider| provider())
+}
+
+/// 重复设置租户提供函数时返回的错误
+#[derive(Debug)]
+pub struct SetTenantProviderError(());
+
+impl fmt::Display for SetTenantProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("tenant provider has already been set")
+    }
+}
+
+impl Error for SetTenantProviderError {}
+
+/// 设置全局的租户提供函数
+///
+/// 提供函数在每次调用使用 `tenant = true` 的函数时执行，返回 `None` 表示当前没有租户上下文，
+/// 此时事件中不包含 `tenant` 字段。只能设置一次，应在程序启动时设置。
+///
+/// # 示例
+/// ```rust
+/// use std::cell::RefCell;
+///
+/// thread_local! {
+///     static TENANT: RefCell<Option<String>> = const { RefCell::new(None) };
+/// }
+///
+/// tracing_fn::set_tenant_provider(|| TENANT.with(|tenant| tenant.borrow().clone())).unwrap();
+/// ```
+pub fn set_tenant_provider<F>(provider: F) -> Result<(), SetTenantProviderError>
+where
+    F: Fn() -> Option<String> + Send + Sync + 'static,
+{
+    PROVIDER
+        .set(Box::new(provider))
+        .map_err(|_| SetTenantProviderError(()))
+}
@@ -0,0 +1,295 @@
+//! 断言被追踪函数输出的事件
+//!
+//! [`capture`] 为当前线程设置收集事件的 subscriber，之后可以使用 [`assert_traced!`](crate::assert_traced)
+//! 断言函数的调用次数、使用 [`assert_duration_under!`](crate::assert_duration_under) 断言调用的耗时，
+//! 或通过 [`events`] 检查事件的内容，例如确认 `skip` 的参数没有以明文输出。
+//! 已有的 subscriber 可以通过 [`CaptureLayer`] 组合。
+//!
+//! 事件保存在输出事件的线程的线程局部变量中，断言只检查当前线程输出的事件，并行运行的测试互不影响。
+//!
+//! # 示例
+//! ```rust,ignore
+//! use tracing_fn::{assert_duration_under, assert_traced, tracing_fn};
+//!
+//! #[tracing_fn(skip(password))]
+//! fn login(user: &str, password: &str) -> bool {
+//!     user == "admin" && password == "hunter2"
+//! }
+//!
+//! #[test]
+//! fn login_is_traced() {
+//!     let _capture = tracing_fn::test_util::capture();
+//!     login("admin", "hunter2");
+//!     login("guest", "guest");
+//!     assert_traced!("login", times = 2);
+//!     assert_duration_under!("login", 10ms);
+//!     assert!(tracing_fn::test_util::events().iter().all(|event| !event.message().contains("hunter2")));
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::fmt::{self, Debug};
+use std::time::Duration;
+
+use tracing::field::{Field, Visit};
+use tracing::subscriber::DefaultGuard;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+thread_local! {
+    static EVENTS: RefCell<Vec<CapturedEvent>> = const { RefCell::new(Vec::new()) };
+}
+
+/// 事件的种类, 按消息开头的 `>>>`、`<<<` 区分
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    /// 入口事件
+    Entry,
+    /// 退出事件
+    Exit,
+    /// 其他事件, 例如 `===` 开头的进度与统计事件
+    Other,
+}
+
+/// 收集到的一个事件
+#[derive(Clone, Debug)]
+pub struct CapturedEvent {
+    level: Level,
+    kind: EventKind,
+    name: Option<String>,
+    message: String,
+    fields: Vec<(&'static str, String)>,
+}
+
+impl CapturedEvent {
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    pub fn kind(&self) -> EventKind {
+        self.kind
+    }
+
+    /// 消息中 `[...]` 内的函数名, 不是由被追踪函数输出的事件为 `None`
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// 除消息以外的字段, 值为其 `Debug` 输出
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 退出事件中的耗时, 取自 `duration_us` 字段, 精度为微秒
+    pub fn duration(&self) -> Option<Duration> {
+        self.field("duration_us")?
+            .parse()
+            .ok()
+            .map(Duration::from_micros)
+    }
+
+    /// 函数名为 `name` 或以 `::name` 结尾 (`qualified`、`self_type` 等参数附加了前缀)
+    fn is_named(&self, name: &str) -> bool {
+        self.name().is_some_and(|own| {
+            own == name
+                || own
+                    .strip_suffix(name)
+                    .is_some_and(|prefix| prefix.ends_with("::"))
+        })
+    }
+}
+
+/// 把事件保存到当前线程的收集结果中的 layer
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CaptureLayer;
+
+impl<S: Subscriber> Layer<S> for CaptureLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = Visitor::default();
+        event.record(&mut visitor);
+        let captured = parse(*event.metadata().level(), visitor);
+        EVENTS.with(|events| events.borrow_mut().push(captured));
+    }
+}
+
+/// [`capture`] 设置的 subscriber, 析构时恢复原来的 subscriber
+#[must_use = "析构后不再收集事件"]
+pub struct CaptureGuard {
+    _default: DefaultGuard,
+}
+
+impl Debug for CaptureGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CaptureGuard").finish_non_exhaustive()
+    }
+}
+
+/// 清空当前线程的收集结果, 并为当前线程设置只收集事件的 subscriber
+pub fn capture() -> CaptureGuard {
+    clear();
+    let subscriber = tracing_subscriber::registry().with(CaptureLayer);
+    CaptureGuard {
+        _default: tracing::subscriber::set_default(subscriber),
+    }
+}
+
+/// 当前线程收集到的所有事件, 按输出顺序排列
+pub fn events() -> Vec<CapturedEvent> {
+    EVENTS.with(|events| events.borrow().clone())
+}
+
+/// 当前线程收集到的函数 `name` 的事件
+pub fn events_of(name: &str) -> Vec<CapturedEvent> {
+    EVENTS.with(|events| {
+        events
+            .borrow()
+            .iter()
+            .filter(|event| event.is_named(name))
+            .cloned()
+            .collect()
+    })
+}
+
+/// 清空当前线程的收集结果
+pub fn clear() {
+    EVENTS.with(|events| events.borrow_mut().clear());
+}
+
+/// 函数 `name` 被记录的调用次数, 为入口事件与退出事件中较多的一方的数量
+///
+/// `paired`、`exit_only` 等参数只输出其中一种事件。
+pub fn calls(name: &str) -> usize {
+    let events = events_of(name);
+    let count = |kind| events.iter().filter(|event| event.kind == kind).count();
+    count(EventKind::Entry).max(count(EventKind::Exit))
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_traced(name: &str, times: Option<usize>) {
+    let calls = calls(name);
+    match times {
+        Some(times) if calls != times => panic!(
+            "expected `{name}` to be traced {times} times, but it was traced {calls} times\n{}",
+            render(&events())
+        ),
+        None if calls == 0 => panic!(
+            "expected `{name}` to be traced, but no events were captured for it\n{}",
+            render(&events())
+        ),
+        _ => {}
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_duration_under(name: &str, limit: Duration) {
+    let exits = events_of(name)
+        .into_iter()
+        .filter(|event| event.kind == EventKind::Exit)
+        .collect::<Vec<_>>();
+    if exits.iter().all(|event| event.duration().is_none()) {
+        panic!(
+            "expected exit events with a duration for `{name}`, found none\n{}",
+            render(&events())
+        );
+    }
+    if let Some(slow) = exits
+        .iter()
+        .find(|event| event.duration().is_some_and(|duration| duration >= limit))
+    {
+        panic!(
+            "expected every call of `{name}` to take less than {limit:?}, found: {}",
+            slow.message
+        );
+    }
+}
+
+/// 解析 `10ms`、`1.5s` 形式的时长, 可选单位: ns, us, µs, ms, s
+#[doc(hidden)]
+#[track_caller]
+pub fn parse_limit(text: &str) -> Duration {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.' && c != '_')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = match number.replace('_', "").parse() {
+        Ok(number) => number,
+        Err(_) => panic!("invalid duration `{text}`, expected e.g. `10ms`"),
+    };
+    let secs = match unit {
+        "ns" => number / 1e9,
+        "us" | "µs" => number / 1e6,
+        "ms" => number / 1e3,
+        "s" => number,
+        _ => panic!("unknown unit in duration `{text}`, expected one of: ns, us, ms, s"),
+    };
+    Duration::from_secs_f64(secs)
+}
+
+fn render(events: &[CapturedEvent]) -> String {
+    let mut out = String::from("captured events:");
+    if events.is_empty() {
+        out.push_str(" (none)");
+    }
+    for event in events {
+        out.push_str(&format!("\n  {} {}", event.level, event.message));
+    }
+    out
+}
+
+#[derive(Default)]
+struct Visitor {
+    message: String,
+    fields: Vec<(&'static str, String)>,
+}
+
+impl Visit for Visitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.fields.push((field.name(), value.to_string()));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.message.push_str(&format!("{value:?}"));
+        } else {
+            self.fields.push((field.name(), format!("{value:?}")));
+        }
+    }
+}
+
+/// 消息的格式为 `[缩进]>>> [函数名] ...` 或 `[缩进]<<< [函数名] ...`, 缩进为 `depth` 参数的空格
+fn parse(level: Level, visitor: Visitor) -> CapturedEvent {
+    let Visitor { message, fields } = visitor;
+    let body = message.trim_start();
+    let (kind, rest) = if let Some(rest) = body.strip_prefix(">>> ") {
+        (EventKind::Entry, Some(rest))
+    } else if let Some(rest) = body.strip_prefix("<<< ") {
+        (EventKind::Exit, Some(rest))
+    } else {
+        (EventKind::Other, body.strip_prefix("=== "))
+    };
+    let name = rest
+        .and_then(|rest| rest.strip_prefix('['))
+        .and_then(|rest| rest.split_once(']'))
+        // generics: 去掉函数名之后的类型参数
+        .map(|(name, _)| name.split("::<").next().unwrap_or(name).to_string());
+    CapturedEvent {
+        level,
+        kind,
+        name,
+        message,
+        fields,
+    }
+}
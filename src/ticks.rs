@@ -0,0 +1,37 @@
+//! no_std 模式的计时
+//!
+//! 未启用 std feature 时没有 `std::time::Instant`，耗时由用户通过 [`set_tick_source`] 注册的计时函数测量。
+
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// 计时函数的指针, 未设置时为空
+static TICK_SOURCE: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// 设置 no_std 模式下用于计算耗时的计时函数
+///
+/// 计时函数返回单调递增的 tick 数 (例如硬件计数器的值)，退出事件中的 `elapsed_ticks` 字段为两次调用的差值。
+/// 未设置时退出事件中不包含耗时。可以多次设置，以最后一次为准。
+///
+/// # 示例
+/// ```rust,ignore
+/// fn ticks() -> u64 {
+///     cortex_m::peripheral::DWT::cycle_count() as u64
+/// }
+///
+/// tracing_fn::set_tick_source(ticks);
+/// ```
+pub fn set_tick_source(ticks: fn() -> u64) {
+    TICK_SOURCE.store(ticks as *mut (), Ordering::Release);
+}
+
+/// 调用计时函数，未设置时返回 `None`
+#[doc(hidden)]
+pub fn ticks() -> Option<u64> {
+    let ptr = TICK_SOURCE.load(Ordering::Acquire);
+    if ptr.is_null() {
+        return None;
+    }
+    // SAFETY: 非空的指针只能由 `set_tick_source` 从 `fn() -> u64` 转换而来
+    let ticks = unsafe { core::mem::transmute::<*mut (), fn() -> u64>(ptr) };
+    Some(ticks())
+}
@@ -0,0 +1,252 @@
+//! 以包装类型追踪动态分发的调用
+//!
+//! 属性宏只能追踪标注的函数，`Box<dyn Storage>` 等经由 trait 对象调用的实现需要逐个标注其 impl 块。
+//! [`Traced`] 包装任意值，为 `io::Read`、`io::Write`、`io::Seek`、`io::BufRead` 与 `Iterator` 转发调用，
+//! 每次转发的调用输出一条包含返回值与耗时的事件；自定义的 trait 可以通过 [`Traced::call`] 与 [`Traced::call_mut`]
+//! 实现转发，或为 `Traced<T>` 的 impl 块标注 [`macro@crate::tracing_impl`]。
+
+use std::borrow::Cow;
+use std::fmt::{self, Debug};
+use std::io::{self, BufRead, IoSlice, IoSliceMut, Read, Seek, SeekFrom, Write};
+use std::time::Duration;
+
+use tracing::Level;
+
+use crate::InvocationId;
+use crate::runtime::Instant;
+
+/// 输出每次转发的调用的包装类型
+///
+/// 事件的格式与 `paired = true` 的退出事件相同，例如 `<<< [Storage::get] #3 #Ret: Some(..), duration: 1.2µs`，
+/// 默认等级为 trace，可以通过 [`Traced::with_level`] 修改。事件不会被记录时只增加一次等级检查。
+///
+/// # 示例
+/// ```rust
+/// use std::io::Read;
+/// use tracing_fn::Traced;
+///
+/// let mut reader = Traced::named("config", std::io::Cursor::new(b"key=value".to_vec()));
+/// let mut text = String::new();
+/// // <<< [config::read_to_string] #1 #Ret: Ok(9), duration: 2.1µs
+/// reader.read_to_string(&mut text).unwrap();
+/// assert_eq!(text, "key=value");
+/// ```
+pub struct Traced<T> {
+    name: Cow<'static, str>,
+    level: Level,
+    inner: T,
+}
+
+impl<T> Traced<T> {
+    /// 包装 `inner`，事件中以其类型名标识
+    pub fn new(inner: T) -> Self {
+        Self::named(std::any::type_name::<T>(), inner)
+    }
+
+    /// 包装 `inner`，事件中以 `name` 标识
+    pub fn named(name: impl Into<Cow<'static, str>>, inner: T) -> Self {
+        Self {
+            name: name.into(),
+            level: Level::TRACE,
+            inner,
+        }
+    }
+
+    /// 设置事件的日志等级
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// 事件中标识被包装的值的名称
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 被包装的值，经由该引用的调用不输出事件
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// 被包装的值，经由该引用的调用不输出事件
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// 转发一次调用并输出事件，用于为自定义的 trait 实现转发
+    ///
+    /// # 示例
+    /// ```rust
+    /// use tracing_fn::Traced;
+    ///
+    /// trait Storage {
+    ///     fn get(&self, key: &str) -> Option<Vec<u8>>;
+    /// }
+    ///
+    /// impl Storage for Traced<Box<dyn Storage>> {
+    ///     fn get(&self, key: &str) -> Option<Vec<u8>> {
+    ///         self.call("get", |storage| storage.get(key))
+    ///     }
+    /// }
+    /// ```
+    pub fn call<R: Debug>(&self, method: &str, f: impl FnOnce(&T) -> R) -> R {
+        if !enabled(self.level) {
+            return f(&self.inner);
+        }
+        let start = Instant::now();
+        let ret = f(&self.inner);
+        emit(self.level, &self.name, method, &ret, start.elapsed());
+        ret
+    }
+
+    /// 同 [`Traced::call`]，用于接收 `&mut self` 的方法
+    pub fn call_mut<R: Debug>(&mut self, method: &str, f: impl FnOnce(&mut T) -> R) -> R {
+        if !enabled(self.level) {
+            return f(&mut self.inner);
+        }
+        let start = Instant::now();
+        let ret = f(&mut self.inner);
+        emit(self.level, &self.name, method, &ret, start.elapsed());
+        ret
+    }
+}
+
+impl<T: Debug> Debug for Traced<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// 隐藏迭代器的元素, 元素不要求实现 Debug
+struct Item(bool);
+
+impl Debug for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0 { "Some(..)" } else { "None" })
+    }
+}
+
+impl<T: Read> Read for Traced<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.call_mut("read", |inner| inner.read(buf))
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        self.call_mut("read_vectored", |inner| inner.read_vectored(bufs))
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        self.call_mut("read_to_end", |inner| inner.read_to_end(buf))
+    }
+
+    fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+        self.call_mut("read_to_string", |inner| inner.read_to_string(buf))
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.call_mut("read_exact", |inner| inner.read_exact(buf))
+    }
+}
+
+impl<T: Write> Write for Traced<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.call_mut("write", |inner| inner.write(buf))
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.call_mut("write_vectored", |inner| inner.write_vectored(bufs))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.call_mut("flush", |inner| inner.flush())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.call_mut("write_all", |inner| inner.write_all(buf))
+    }
+}
+
+impl<T: Seek> Seek for Traced<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.call_mut("seek", |inner| inner.seek(pos))
+    }
+}
+
+impl<T: BufRead> BufRead for Traced<T> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        // 返回的切片借用了被包装的值, 不能经由 call_mut 转发, 返回值输出为切片的长度
+        if !enabled(self.level) {
+            return self.inner.fill_buf();
+        }
+        let start = Instant::now();
+        let ret = self.inner.fill_buf();
+        let len = ret.as_ref().map(|buf| buf.len());
+        emit(self.level, &self.name, "fill_buf", &len, start.elapsed());
+        ret
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.call_mut("consume", |inner| inner.consume(amt))
+    }
+}
+
+impl<T: Iterator> Iterator for Traced<T> {
+    type Item = T::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !enabled(self.level) {
+            return self.inner.next();
+        }
+        let start = Instant::now();
+        let ret = self.inner.next();
+        emit(
+            self.level,
+            &self.name,
+            "next",
+            &Item(ret.is_some()),
+            start.elapsed(),
+        );
+        ret
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// tracing 的事件宏要求等级为常量
+macro_rules! with_level {
+    ($level:expr, $event:ident!($($args:tt)*)) => {
+        match $level {
+            Level::ERROR => tracing::$event!(Level::ERROR, $($args)*),
+            Level::WARN => tracing::$event!(Level::WARN, $($args)*),
+            Level::INFO => tracing::$event!(Level::INFO, $($args)*),
+            Level::DEBUG => tracing::$event!(Level::DEBUG, $($args)*),
+            _ => tracing::$event!(Level::TRACE, $($args)*),
+        }
+    };
+}
+
+fn enabled(level: Level) -> bool {
+    with_level!(level, enabled!())
+}
+
+fn emit(level: Level, name: &str, method: &str, ret: &dyn Debug, duration: Duration) {
+    let id = InvocationId::next();
+    with_level!(
+        level,
+        event!(
+            duration_us = duration.as_micros() as u64,
+            "<<< [{}::{}] #{} #Ret: {:?}, duration: {:?}",
+            name,
+            method,
+            id,
+            ret,
+            duration
+        )
+    );
+}
@@ -0,0 +1,27 @@
+//! 运行时详细程度
+//!
+//! 使用 `verbosity_tiers` 参数的函数根据 [`set_verbosity`] 设置的全局详细程度决定事件中包含的内容，
+//! 无需重新部署即可在排查问题时临时输出更多细节。
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(1);
+
+/// 设置全局的详细程度，默认为 1
+///
+/// 对于使用 `verbosity_tiers(args = 2, ret = 3)` 的函数，详细程度为 1 时只输出函数名与耗时，
+/// 为 2 时追加参数，为 3 时再追加返回值。设置立即对之后开始的调用生效。
+///
+/// # 示例
+/// ```rust
+/// // 排查问题期间输出参数与返回值
+/// tracing_fn::set_verbosity(3);
+/// ```
+pub fn set_verbosity(verbosity: u8) {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+}
+
+/// 当前的全局详细程度
+pub fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
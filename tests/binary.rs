@@ -0,0 +1,79 @@
+//! `binary = true` 写入的记录文件的解码测试, 需要启用 binary feature:
+//!
+//! ```sh
+//! cargo test --features binary --test binary
+//! ```
+
+#![cfg(feature = "binary")]
+
+use std::path::Path;
+
+use tracing_fn::binary::{self, RecordKind};
+use tracing_fn::tracing_fn;
+
+#[tracing_fn(binary = true)]
+fn square(x: u64) -> u64 {
+    x * x
+}
+
+#[tracing_fn(binary = true)]
+fn parse(s: &str) -> Result<u64, std::num::ParseIntError> {
+    let value = s.parse()?;
+    Ok(value)
+}
+
+#[test]
+fn records_round_trip_through_the_mapped_file() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("binary_test.bin");
+    // 打开之前的调用不写入
+    square(1);
+    // 两个函数名各占一个槽位, 三次调用共六条记录, 最后一次调用的两条记录被丢弃
+    binary::open(&path, 6).unwrap();
+    square(2);
+    parse("x").unwrap_err();
+    parse("3").unwrap();
+    binary::flush().unwrap();
+
+    let log = binary::decode(&path).unwrap();
+    assert_eq!(log.dropped, 2);
+    let records: Vec<_> = log
+        .records
+        .iter()
+        .map(|record| {
+            let function = record.function.rsplit("::").next().unwrap();
+            let failed = match record.kind {
+                RecordKind::Entry => None,
+                RecordKind::Exit { failed, .. } => Some(failed),
+            };
+            (function, record.call, failed)
+        })
+        .collect();
+    assert_eq!(
+        records,
+        [
+            ("square", 0, None),
+            ("square", 0, Some(false)),
+            ("parse", 1, None),
+            ("parse", 1, Some(true)),
+        ]
+    );
+    assert!(log.records[0].thread.is_some());
+    assert!(log.records[1].timestamp_ns >= log.records[0].timestamp_ns);
+}
+
+#[test]
+fn corrupt_function_index_is_rejected() {
+    let mut bytes = vec![0; 64 + 32];
+    bytes[..8].copy_from_slice(b"TFNBIN01");
+    bytes[8..12].copy_from_slice(&1u32.to_le_bytes());
+    bytes[12..16].copy_from_slice(&32u32.to_le_bytes());
+    bytes[16..24].copy_from_slice(&1u64.to_le_bytes());
+    // 函数名记录中的函数编号远超槽位数量
+    bytes[64] = 3;
+    bytes[64 + 2..64 + 4].copy_from_slice(&4u16.to_le_bytes());
+    bytes[64 + 4..64 + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+    bytes[64 + 8..64 + 12].copy_from_slice(b"main");
+
+    let err = binary::decode_bytes(&bytes).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
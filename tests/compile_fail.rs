@@ -0,0 +1,17 @@
+//! 宏参数的编译期诊断, 每个 `tests/ui/*.rs` 都应编译失败并输出同目录下 `.stderr` 中的错误:
+//!
+//! ```sh
+//! cargo test --test compile_fail
+//! # 错误消息变化后更新 .stderr
+//! TRYBUILD=overwrite cargo test --test compile_fail
+//! ```
+//!
+//! 以 `--features max_level_info` 或 `--features disabled` 运行时, 被移除的函数同样应报告相同的错误。
+
+#![cfg(feature = "std")]
+
+#[test]
+fn invalid_arguments_are_rejected() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,56 @@
+//! `span = true` 生成的 span 与 tracing-flame 的兼容性测试
+
+use std::path::Path;
+
+#[allow(dead_code)]
+#[path = "../examples/flame.rs"]
+mod flame;
+
+#[test]
+fn folded_stacks_follow_the_call_tree() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let folded = dir.join("flame_test.folded");
+    let svg = dir.join("flame_test.svg");
+
+    flame::record(&folded);
+    let stacks: Vec<Vec<String>> = std::fs::read_to_string(&folded)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let (stack, _samples) = line.rsplit_once(' ').unwrap();
+            // 去掉线程名, 每个 span 只保留函数名, 不含模块路径与源码位置
+            stack
+                .split("; ")
+                .skip(1)
+                .map(|frame| {
+                    let name = frame.rsplitn(3, ':').last().unwrap();
+                    name.rsplit("::").next().unwrap().to_string()
+                })
+                .collect()
+        })
+        .collect();
+
+    let expected = [
+        vec!["handle_request"],
+        vec!["handle_request", "query"],
+        vec!["handle_request", "render_page"],
+    ];
+    for stack in &expected {
+        assert!(
+            stacks.iter().any(|s| s == stack),
+            "missing stack {stack:?} in {stacks:?}"
+        );
+    }
+    // 嵌套调用必须以外层函数的 span 为父 span, 不能出现在栈底
+    assert!(
+        stacks
+            .iter()
+            .all(|s| s.is_empty() || s[0] == "handle_request"),
+        "unexpected root span in {stacks:?}"
+    );
+
+    flame::render(&folded, &svg);
+    let svg = std::fs::read_to_string(&svg).unwrap();
+    assert!(svg.starts_with("<?xml"));
+    assert!(svg.contains("query"));
+}
@@ -0,0 +1,86 @@
+//! 快照测试工具的测试, 需要启用 golden feature:
+//!
+//! ```sh
+//! cargo test --features golden --test golden
+//! ```
+
+#![cfg(feature = "golden")]
+
+use std::path::Path;
+
+use tracing_fn::golden::{self, Capture};
+use tracing_fn::{assert_golden, tracing_fn};
+
+#[tracing_fn(location = "none")]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[tracing_fn(location = "none", level = "info")]
+fn parse(s: &str) -> Result<i32, std::num::ParseIntError> {
+    let value = s.parse()?;
+    Ok(add(value, 1))
+}
+
+#[test]
+fn output_matches_snapshot() {
+    let output = golden::capture(|| {
+        parse("1").unwrap();
+        parse("x").unwrap_err();
+    });
+    assert_golden!("parse", output);
+}
+
+#[test]
+fn capture_is_deterministic() {
+    let run = || {
+        golden::capture(|| {
+            add(1, 2);
+        })
+    };
+    let first = run();
+    add(3, 4);
+    // 调用 ID 每次收集时重新编号, 耗时被冻结
+    assert_eq!(first, run());
+    assert_eq!(
+        first.lines(),
+        [
+            "TRACE >>> [add] #1 #Args: a=1, b=2",
+            "TRACE <<< [add] #1 #Ret: 3, duration: 0ns duration_us=0",
+        ]
+    );
+}
+
+#[test]
+fn mismatch_writes_pending_snapshot() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    let _ = std::fs::remove_dir_all(&dir);
+    let manifest_dir = dir.to_str().unwrap();
+    let capture = golden::capture(|| {
+        add(1, 2);
+    });
+
+    // 快照不存在时写入 .snap.new 并失败
+    let missing = std::panic::catch_unwind(|| {
+        golden::assert_snapshot(manifest_dir, "add.rs", "add", "capture", &capture)
+    });
+    assert!(missing.is_err());
+    let pending = dir.join("snapshots/add__add.snap.new");
+    let contents = std::fs::read_to_string(&pending).unwrap();
+    assert!(contents.starts_with("---\nsource: add.rs\nexpression: capture\n---\n"));
+
+    // 接受之后输出一致时通过, 不一致时失败
+    std::fs::rename(&pending, dir.join("snapshots/add__add.snap")).unwrap();
+    golden::assert_snapshot(manifest_dir, "add.rs", "add", "capture", &capture);
+    let changed = std::panic::catch_unwind(|| {
+        golden::assert_snapshot(
+            manifest_dir,
+            "add.rs",
+            "add",
+            "capture",
+            &Capture::default(),
+        )
+    });
+    assert!(changed.is_err());
+    assert!(pending.exists());
+}
@@ -0,0 +1,177 @@
+//! 生成的代码只使用绝对路径, 在 `#[no_implicit_prelude]` 模块中同样可以展开
+
+#![cfg(feature = "std")]
+
+#[no_implicit_prelude]
+mod without_prelude {
+    use ::tracing_fn::{trace_block, trace_expr, traced_extern, tracing_closure, tracing_fn};
+
+    #[tracing_fn]
+    pub fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    #[tracing_fn(level = "debug", skip(password), max_len = 16)]
+    pub fn login(user: &str, password: &str) -> bool {
+        user == password
+    }
+
+    #[tracing_fn(err_level = "warn", err = true)]
+    pub fn parse(text: &str) -> ::std::result::Result<u8, ::std::num::ParseIntError> {
+        let value = text.parse::<u8>()?;
+        ::std::result::Result::Ok(value)
+    }
+
+    #[tracing_fn(paired = true, once_per(key = "id"), first = 8, dedup = true)]
+    pub fn lookup(id: u32) -> ::std::option::Option<u32> {
+        id.checked_sub(1)
+    }
+
+    #[tracing_fn(exit_only = true, skip_ret = true, thread = true)]
+    pub fn touch(values: &mut ::std::vec::Vec<u8>) {
+        values.push(0);
+    }
+
+    #[tracing_fn(
+        redact = "sha256",
+        skip(token),
+        summarize(items),
+        runtime_skip = true,
+        compress_prefix(len = 1),
+        condition = "items.len() < 100",
+        span = true,
+        caller = true,
+        caller_fn = true,
+        fanout = true,
+        tenant = true,
+        abort_safe = true,
+        fields(subsystem = "io"),
+        priority = "high",
+        duration_unit = "us",
+        location = "both",
+        entry_on_slow = "1s"
+    )]
+    pub fn send(token: &str, items: &[u8]) -> usize {
+        token.len() + items.len()
+    }
+
+    pub fn code(err: &::std::num::ParseIntError) -> u32 {
+        ::std::string::ToString::to_string(err).len() as u32
+    }
+
+    #[tracing_fn(err_code = "code(err)", ret_with = "describe", audit = true)]
+    pub fn checked(text: &str) -> ::std::result::Result<u8, ::std::num::ParseIntError> {
+        text.parse::<u8>()
+    }
+
+    pub fn describe(ret: &::std::result::Result<u8, ::std::num::ParseIntError>) -> bool {
+        ret.is_ok()
+    }
+
+    pub fn level_for() -> ::tracing::Level {
+        ::tracing::Level::INFO
+    }
+
+    pub fn context() -> ::std::vec::Vec<(&'static str, ::std::string::String)> {
+        ::std::vec![("request_id", ::std::borrow::ToOwned::to_owned("abc"))]
+    }
+
+    pub fn now() -> u64 {
+        0
+    }
+
+    pub fn size(body: &[u8]) -> ::std::string::String {
+        ::std::format!("<{} bytes>", body.len())
+    }
+
+    #[tracing_fn(
+        level_fn = "level_for",
+        with = "context",
+        clock = "now",
+        format(body = "size"),
+        log(url, body),
+        strict_args = true,
+        verbosity_tiers(args = 2, ret = 3),
+        pressure = "drop",
+        dedup = true,
+        qualified = true,
+        generics = true,
+        skip_empty_args = true
+    )]
+    pub fn post<T: ::std::fmt::Debug>(url: T, body: &[u8]) -> usize {
+        body.len()
+    }
+
+    #[tracing_fn(err_only = true, batch(items, chunk = 2))]
+    pub fn process(items: &[u8]) -> ::std::result::Result<usize, ()> {
+        for _ in items {
+            ::tracing_fn::batch_tick!();
+        }
+        ::std::result::Result::Ok(items.len())
+    }
+
+    #[::tracing_fn::tracing_main]
+    pub fn entry() -> u8 {
+        add(1, 2) as u8
+    }
+
+    #[::tracing_fn::tracing_mod]
+    pub mod nested {
+        pub fn exit_early(code: i32) -> i32 {
+            if code > 0 {
+                ::std::process::exit(code);
+            }
+            code
+        }
+    }
+
+    #[tracing_fn]
+    pub async fn fetch(id: u32) -> u32 {
+        id
+    }
+
+    #[tracing_fn(slow_poll = "50ms", child_span = "fetching")]
+    pub async fn fetch_slow(id: u32) -> ::std::result::Result<u32, ::std::string::String> {
+        ::std::result::Result::Ok(id)
+    }
+
+    pub struct Counter(pub u32);
+
+    #[::tracing_fn::tracing_impl(level = "info")]
+    impl Counter {
+        pub fn bump(&mut self) -> &mut u32 {
+            self.0 += 1;
+            &mut self.0
+        }
+    }
+
+    pub fn functional(x: i32) -> i32 {
+        let double = tracing_closure!(|x: i32| x * 2);
+        let y = trace_block!("double", { double(x) });
+        let z = trace_expr!(y + 1);
+        traced_extern!(::core::cmp::Ord::max(z, 0))
+    }
+}
+
+#[test]
+fn expands_without_the_prelude() {
+    assert_eq!(without_prelude::add(1, 2), 3);
+    assert!(without_prelude::login("a", "a"));
+    assert_eq!(without_prelude::parse("7"), Ok(7));
+    assert!(without_prelude::parse("x").is_err());
+    assert_eq!(without_prelude::lookup(3), Some(2));
+    let mut values = Vec::new();
+    without_prelude::touch(&mut values);
+    assert_eq!(values, [0]);
+    let mut counter = without_prelude::Counter(0);
+    assert_eq!(*counter.bump(), 1);
+    assert_eq!(without_prelude::functional(2), 5);
+    assert_eq!(without_prelude::send("t", &[1, 2]), 3);
+    assert!(without_prelude::checked("x").is_err());
+    assert_eq!(without_prelude::post("/upload", &[1, 2]), 2);
+    assert_eq!(without_prelude::process(&[1, 2, 3]), Ok(3));
+    assert_eq!(without_prelude::entry(), 3);
+    assert_eq!(without_prelude::nested::exit_early(0), 0);
+    drop(without_prelude::fetch(1));
+    drop(without_prelude::fetch_slow(1));
+}
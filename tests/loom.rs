@@ -0,0 +1,208 @@
+//! 运行时状态的并发正确性测试
+//!
+//! 以 loom 穷举线程的交错执行，需要以 `--cfg loom` 编译：
+//!
+//! ```sh
+//! RUSTFLAGS="--cfg loom" cargo test --release --test loom
+//! ```
+
+#![cfg(loom)]
+
+use std::time::Duration;
+
+use loom::sync::Arc;
+use loom::thread;
+
+use tracing_fn::InvocationId;
+use tracing_fn::redaction::Registry;
+use tracing_fn::runtime::{
+    AuditChain, Dedup, FirstDecision, FirstN, FnStats, OncePer, PollTracker, PrefixCache,
+    RecentCalls, StatsRegistry,
+};
+
+#[test]
+fn first_n_admits_exactly_limit_calls() {
+    loom::model(|| {
+        let first = Arc::new(FirstN::new(1));
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let first = first.clone();
+                thread::spawn(move || first.admit())
+            })
+            .collect();
+        let mut decisions: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        decisions.push(first.admit());
+
+        let count = |decision| decisions.iter().filter(|d| **d == decision).count();
+        assert_eq!(count(FirstDecision::Emit), 1);
+        assert_eq!(count(FirstDecision::Notice), 1);
+        assert_eq!(count(FirstDecision::Suppress), 1);
+    });
+}
+
+#[test]
+fn dedup_counts_every_repeated_call() {
+    loom::model(|| {
+        let dedup = Arc::new(Dedup::new());
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let dedup = dedup.clone();
+                thread::spawn(move || dedup.observe("x=1".to_string()).emit)
+            })
+            .collect();
+        let emitted = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(|emit| *emit)
+            .count();
+        // 相同参数的并发调用只输出一次, 另一次在参数变化时计入汇总
+        assert_eq!(emitted, 1);
+        let decision = dedup.observe("x=2".to_string());
+        assert!(decision.emit);
+        assert_eq!(decision.repeated, Some(1));
+    });
+}
+
+#[test]
+fn audit_chain_links_concurrent_records() {
+    loom::model(|| {
+        let chain = Arc::new(AuditChain::new());
+        let handles: Vec<_> = ["a", "b"]
+            .into_iter()
+            .map(|message| {
+                let chain = chain.clone();
+                thread::spawn(move || chain.append(message))
+            })
+            .collect();
+        let mut records: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        records.sort_by_key(|record| record.seq);
+
+        // 序号连续, 每条记录的 prev 为上一条记录的 hash
+        assert_eq!(records[0].seq, 1);
+        assert_eq!(records[0].prev, 0);
+        assert_eq!(records[1].seq, 2);
+        assert_eq!(records[1].prev, records[0].hash);
+    });
+}
+
+#[test]
+fn redaction_readers_see_whole_settings() {
+    loom::model(|| {
+        let registry = Arc::new(Registry::new());
+        let writer = {
+            let registry = registry.clone();
+            thread::spawn(move || registry.set("app::login", &["password", "otp"]))
+        };
+        // 与 set 同时进行的读取看到修改前或修改后的完整设置
+        let redacted = registry.current("app::login");
+        assert_eq!(redacted.contains("password"), redacted.contains("otp"));
+        writer.join().unwrap();
+
+        let redacted = registry.current("app::login");
+        assert!(redacted.contains("password") && redacted.contains("otp"));
+    });
+}
+
+#[test]
+fn once_per_emits_each_key_once() {
+    loom::model(|| {
+        let once = Arc::new(OncePer::new(8, Duration::from_secs(60)));
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let once = once.clone();
+                thread::spawn(move || once.observe("user-1").emit)
+            })
+            .collect();
+        let emitted = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(|emit| *emit)
+            .count();
+        assert_eq!(emitted, 1);
+    });
+}
+
+#[test]
+fn poll_tracker_starts_waiting_once() {
+    loom::model(|| {
+        let tracker = Arc::new(PollTracker::new());
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let tracker = tracker.clone();
+                thread::spawn(move || tracker.pending("task-1"))
+            })
+            .collect();
+        let started = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(|started| *started)
+            .count();
+        // 两次 Pending 只有一次使实例开始等待, 就绪时的 poll 次数包含全部三次
+        assert_eq!(started, 1);
+        assert_eq!(tracker.ready("task-1").polls, 3);
+    });
+}
+
+#[test]
+fn prefix_cache_defines_prefix_once() {
+    loom::model(|| {
+        let cache = Arc::new(PrefixCache::new(Duration::from_secs(60)));
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let cache = cache.clone();
+                thread::spawn(move || cache.compress("tenant=acme".to_string()))
+            })
+            .collect();
+        let outputs: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        let defined = outputs.iter().filter(|out| out.contains(": ")).count();
+        assert_eq!(defined, 1);
+    });
+}
+
+#[test]
+fn fn_stats_register_once_and_count_every_call() {
+    loom::model(|| {
+        let registry = Arc::new(StatsRegistry::new());
+        registry.enable();
+        let stats: &'static FnStats = Box::leak(Box::new(FnStats::new("app::handle")));
+        let handles: Vec<_> = [true, false]
+            .into_iter()
+            .map(|failed| {
+                let registry = registry.clone();
+                thread::spawn(move || stats.record_in(&registry, Duration::from_millis(1), failed))
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let summary = registry.summary();
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0].calls, 2);
+        assert_eq!(summary[0].failed, 1);
+        assert_eq!(summary[0].total, Duration::from_millis(2));
+    });
+}
+
+#[test]
+fn recent_calls_keep_the_latest_records() {
+    loom::model(|| {
+        let recent = Arc::new(RecentCalls::new(2));
+        let handles: Vec<_> = ["a", "b"]
+            .into_iter()
+            .map(|name| {
+                let recent = recent.clone();
+                thread::spawn(move || recent.record(InvocationId::next(), name, String::new()))
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        recent.record(InvocationId::next(), "c", String::new());
+
+        // 容量为 2 时丢弃最早写入的记录
+        let names = recent.names();
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[1], "c");
+        assert!(names[0] == "a" || names[0] == "b");
+    });
+}
@@ -0,0 +1,48 @@
+//! `#[tracing_main]` 的退出统计测试, 需要启用 test-util feature, 每个函数的统计需要启用 main-stats feature:
+//!
+//! ```sh
+//! cargo test --features "test-util main-stats" --test main_stats
+//! ```
+
+#![cfg(feature = "test-util")]
+
+use tracing_fn::test_util::{self, EventKind};
+use tracing_fn::{tracing_fn, tracing_main};
+
+#[tracing_fn]
+fn parse(text: &str) -> Result<u8, String> {
+    text.parse().map_err(|_| format!("invalid: {text}"))
+}
+
+#[tracing_main]
+fn run() -> usize {
+    ["1", "2", "x"]
+        .into_iter()
+        .filter_map(|text| parse(text).ok())
+        .count()
+}
+
+#[test]
+fn exit_summary_reports_traced_functions() {
+    let _capture = test_util::capture();
+    assert_eq!(run(), 2);
+    let events = test_util::events_of("run");
+    assert_eq!(events.len(), 1 + usize::from(cfg!(feature = "main-stats")));
+    assert!(events.iter().all(|event| event.kind() == EventKind::Other));
+    assert!(
+        events[0]
+            .message()
+            .starts_with("=== [run] #Exit: duration: ")
+    );
+    if cfg!(feature = "main-stats") {
+        assert!(events[0].message().ends_with(", functions: 1"));
+        assert!(
+            events[1]
+                .message()
+                .contains("#Stats: main_stats::parse calls: 3, failed: 1")
+        );
+    } else {
+        // 未启用 main-stats 时不统计被追踪的函数
+        assert!(!events[0].message().contains("functions"));
+    }
+}
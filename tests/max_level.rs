@@ -0,0 +1,42 @@
+//! 静态最高等级的测试, 需要启用 max_level_info 与 test-util feature:
+//!
+//! ```sh
+//! cargo test --features "max_level_info test-util" --test max_level
+//! ```
+
+#![cfg(all(feature = "max_level_info", feature = "test-util"))]
+
+use tracing_fn::test_util::{self, EventKind};
+use tracing_fn::tracing_fn;
+
+#[tracing_fn(level = "trace", err_level = "error")]
+fn parse(text: &str) -> Result<u8, String> {
+    text.parse().map_err(|_| format!("invalid: {text}"))
+}
+
+#[tracing_fn(level = "trace")]
+fn stripped(x: u8) -> u8 {
+    x
+}
+
+#[test]
+fn err_level_keeps_the_function_instrumented() {
+    let _capture = test_util::capture();
+    assert_eq!(parse("7"), Ok(7));
+    // TRACE 的入口与退出事件被 tracing 移除
+    assert!(test_util::events_of("parse").is_empty());
+
+    assert!(parse("x").is_err());
+    let events = test_util::events_of("parse");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind(), EventKind::Exit);
+    assert_eq!(events[0].level(), tracing::Level::ERROR);
+    assert!(events[0].message().contains(r#"#Ret: Err("invalid: x")"#));
+}
+
+#[test]
+fn stripped_levels_emit_nothing() {
+    let _capture = test_util::capture();
+    assert_eq!(stripped(1), 1);
+    assert!(test_util::events().is_empty());
+}
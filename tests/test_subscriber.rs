@@ -0,0 +1,30 @@
+//! `test_subscriber = true` 的测试, 需要启用 test-subscriber feature:
+//!
+//! ```sh
+//! cargo test --features test-subscriber --test test_subscriber
+//! ```
+
+#![cfg(feature = "test-subscriber")]
+
+use tracing_fn::tracing_fn;
+
+#[tracing_fn]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[test]
+#[tracing_fn(test_subscriber = true)]
+fn subscriber_is_installed() {
+    assert!(tracing::dispatcher::has_been_set());
+    assert!(tracing::enabled!(tracing::Level::TRACE));
+    assert_eq!(add(1, 2), 3);
+}
+
+#[tracing_fn(test_subscriber = true)]
+#[test]
+fn works_below_test_attribute() -> Result<(), String> {
+    assert!(tracing::dispatcher::has_been_set());
+    assert_eq!(add(2, 2), 4);
+    Ok(())
+}
@@ -0,0 +1,676 @@
+//! `test_util` 的收集与断言测试, 需要启用 test-util feature:
+//!
+//! ```sh
+//! cargo test --features test-util --test test_util
+//! ```
+
+#![cfg(feature = "test-util")]
+
+use std::time::Duration;
+
+use tracing_fn::test_util::{self, EventKind};
+use tracing_fn::{assert_duration_under, assert_traced, tracing_fn};
+
+#[tracing_fn(skip(password))]
+fn login(user: &str, password: &str) -> bool {
+    user == "admin" && password == "hunter2"
+}
+
+#[tracing_fn(paired = true)]
+fn lookup(id: u32) -> Option<u32> {
+    id.checked_sub(1)
+}
+
+#[tracing_fn(generics = true)]
+fn size_of<T>() -> usize {
+    std::mem::size_of::<T>()
+}
+
+/// 与 `anyhow::bail!` 相同, 展开为 `return`
+macro_rules! bail {
+    ($msg:literal) => {
+        return Err($msg.to_string())
+    };
+}
+
+#[tracing_fn(err_level = "warn")]
+fn checked(x: u8) -> Result<u8, String> {
+    if x == 0 {
+        bail!("zero");
+    }
+    Ok(x)
+}
+
+#[tracing_fn]
+fn first_mut(values: &mut Vec<u8>) -> Result<&mut u8, String> {
+    if values.is_empty() {
+        bail!("empty");
+    }
+    Ok(&mut values[0])
+}
+
+/// 与 `anyhow::ensure!` 相同, 条件不成立时展开为 `return`
+macro_rules! ensure {
+    ($cond:expr, $msg:literal) => {
+        if !$cond {
+            return Err($msg.to_string());
+        }
+    };
+}
+
+struct Store {
+    items: Vec<u8>,
+}
+
+impl Store {
+    // 函数体中的宏可能返回, 函数体在 async 块中执行, 返回值借用 self
+    #[tracing_fn(err_level = "warn")]
+    async fn first_mut(&mut self) -> Result<&mut u8, String> {
+        ensure!(!self.items.is_empty(), "empty");
+        Ok(&mut self.items[0])
+    }
+}
+
+/// 在当前线程上轮询 future 直到完成, 测试中的 future 不会返回 `Pending`
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    match future.as_mut().poll(&mut cx) {
+        std::task::Poll::Ready(output) => output,
+        std::task::Poll::Pending => unreachable!(),
+    }
+}
+
+#[tracing_fn]
+fn find(grid: &[Vec<u8>], target: u8) -> Option<(usize, usize)> {
+    let mut skipped = 0;
+    'rows: for (i, row) in grid.iter().enumerate() {
+        for (j, &value) in row.iter().enumerate() {
+            if value == 0 {
+                skipped += 1;
+                continue 'rows;
+            }
+            if value == target {
+                return Some((i, j));
+            }
+        }
+    }
+    let mut attempts = 0;
+    'search: loop {
+        attempts += 1;
+        if skipped > 0 {
+            break 'search None;
+        }
+        if attempts == 2 {
+            break 'search Some((usize::MAX, attempts));
+        }
+    }
+}
+
+#[tracing_fn]
+fn positives(values: &[&str]) -> Result<Vec<u8>, String> {
+    // 闭包中的 `return` 与 `?` 属于闭包自身, 不是函数的返回
+    let parse = |text: &str| -> Result<u8, String> {
+        if text.is_empty() {
+            return Err("empty".to_string());
+        }
+        let value = text.parse::<u8>().map_err(|err| err.to_string())?;
+        Ok(value)
+    };
+    let keep = |value: &u8| {
+        if *value == 0 {
+            return false;
+        }
+        true
+    };
+    let parsed: Vec<u8> = values.iter().filter_map(|text| parse(text).ok()).collect();
+    Ok(parsed.into_iter().filter(keep).collect())
+}
+
+#[derive(Debug, PartialEq)]
+enum AppError {
+    Parse(std::num::ParseIntError),
+}
+
+impl From<std::num::ParseIntError> for AppError {
+    fn from(err: std::num::ParseIntError) -> Self {
+        AppError::Parse(err)
+    }
+}
+
+impl AppError {
+    fn code(&self) -> u16 {
+        match self {
+            AppError::Parse(_) => 400,
+        }
+    }
+}
+
+#[tracing_fn]
+fn parse_app(text: &str) -> Result<u8, AppError> {
+    let value: u8 = text.parse()?;
+    Ok(value)
+}
+
+// err_code 需要转换之后的错误, `?` 展开为先经 `From` 转换再记录的 `match`
+#[tracing_fn(err_code = "err.code()")]
+fn parse_coded(text: &str) -> Result<u8, AppError> {
+    let value: u8 = text.parse()?;
+    Ok(value)
+}
+
+#[tracing_fn(err = true)]
+fn parse_port(text: &str) -> Result<u16, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(text.parse()?)
+}
+
+#[derive(Debug)]
+struct ConfigError(std::io::Error);
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("failed to load config")
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+#[tracing_fn(err = true)]
+fn open_config() -> Result<(), Box<dyn std::error::Error>> {
+    let err = std::io::Error::other("config.toml not found");
+    Err(Box::new(ConfigError(err)))
+}
+
+/// 按路径识别, 与 `anyhow::Error` 一样 `Debug` 输出跨越多行
+mod anyhow {
+    pub struct Error;
+
+    impl std::fmt::Debug for Error {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("outer\n\nCaused by:\n    inner")
+        }
+    }
+
+    impl std::fmt::Display for Error {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            if f.alternate() {
+                f.write_str("outer: inner")
+            } else {
+                f.write_str("outer")
+            }
+        }
+    }
+}
+
+#[tracing_fn]
+fn flush() -> Result<(), anyhow::Error> {
+    Err(anyhow::Error)
+}
+
+#[tracing_fn]
+fn label() -> Result<&'static str, anyhow::Error> {
+    Err(anyhow::Error)
+}
+
+#[tracing_fn(once_per(key = "user"))]
+fn greet(user: &str) -> usize {
+    user.len()
+}
+
+#[test]
+fn once_per_ignores_disabled_calls() {
+    // 没有 subscriber 时等级未启用, 该调用不占用 key
+    greet("alice");
+    let _capture = test_util::capture();
+    greet("alice");
+    greet("alice");
+    greet("bob");
+    let entries: Vec<_> = test_util::events_of("greet")
+        .into_iter()
+        .filter(|event| event.kind() == EventKind::Entry)
+        .collect();
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].message().contains(r#"user="alice""#));
+    assert!(entries[1].message().contains(r#"user="bob""#));
+}
+
+#[test]
+fn counts_calls() {
+    let _capture = test_util::capture();
+    login("admin", "hunter2");
+    login("guest", "guest");
+    assert_traced!("login", times = 2);
+    assert_eq!(test_util::calls("lookup"), 0);
+
+    // paired 只输出退出事件
+    lookup(3);
+    assert_traced!("lookup");
+    assert_traced!("lookup", times = 1);
+
+    size_of::<u64>();
+    assert_traced!("size_of", times = 1);
+}
+
+#[test]
+fn redacted_arguments_are_not_logged() {
+    let _capture = test_util::capture();
+    login("admin", "hunter2");
+    let events = test_util::events_of("login");
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].kind(), EventKind::Entry);
+    assert_eq!(events[1].kind(), EventKind::Exit);
+    assert!(
+        events
+            .iter()
+            .all(|event| !event.message().contains("hunter2"))
+    );
+    assert!(events[1].duration().is_some());
+}
+
+#[tracing_fn(skip(token), redact = "sha256")]
+fn authorize(user: &str, token: &str) -> bool {
+    !user.is_empty() && token.len() > 8
+}
+
+/// 取出消息中 `token=sha256:` 之后的哈希前缀
+fn token_digest(message: &str) -> &str {
+    let start = message.find("token=sha256:").expect("token 未按哈希输出") + "token=sha256:".len();
+    &message[start..start + 16]
+}
+
+#[test]
+fn sha256_redacted_arguments_are_not_logged() {
+    let _capture = test_util::capture();
+    authorize("alice", "s3cr3t-token");
+    authorize("bob", "s3cr3t-token");
+    authorize("carol", "other-token");
+    let events = test_util::events_of("authorize");
+    assert!(events.iter().all(
+        |event| !event.message().contains("s3cr3t") && !event.message().contains("other-token")
+    ));
+    let entries: Vec<_> = events
+        .iter()
+        .filter(|event| event.kind() == EventKind::Entry)
+        .collect();
+    assert_eq!(entries.len(), 3);
+    // 相同的值得到相同的前缀, 不同的值得到不同的前缀
+    let digests: Vec<_> = entries
+        .iter()
+        .map(|event| token_digest(event.message()))
+        .collect();
+    assert!(digests[0].bytes().all(|byte| byte.is_ascii_hexdigit()));
+    assert_eq!(digests[0], digests[1]);
+    assert_ne!(digests[0], digests[2]);
+}
+
+#[tracing_fn(skip(password), redacted_as = "<redacted>")]
+fn sign_in(user: &str, password: &str) -> bool {
+    user == "admin" && password == "hunter2"
+}
+
+#[tracing_fn(skip(password), redact = "omit")]
+fn register(user: &str, password: &str) -> bool {
+    !user.is_empty() && !password.is_empty()
+}
+
+#[test]
+fn placeholder_and_omitted_arguments_are_not_logged() {
+    let _capture = test_util::capture();
+    sign_in("admin", "hunter2");
+    register("admin", "hunter2");
+    assert!(
+        test_util::events()
+            .iter()
+            .all(|event| !event.message().contains("hunter2"))
+    );
+
+    let signed_in = test_util::events_of("sign_in");
+    assert_eq!(signed_in[0].kind(), EventKind::Entry);
+    assert!(signed_in[0].message().contains("password=<redacted>"));
+
+    // omit 连同参数名一起省略
+    let registered = test_util::events_of("register");
+    assert_eq!(registered[0].kind(), EventKind::Entry);
+    assert!(registered[0].message().contains(r#"user="admin""#));
+    assert!(!registered[0].message().contains("password"));
+}
+
+/// Debug 输出原值, 未被跳过时会出现在日志中
+#[derive(Debug)]
+struct Password(&'static str);
+
+#[tracing_fn(skip_types = "Password")]
+fn connect(url: &str, credentials: &Password, fallback: Option<Password>) -> bool {
+    !url.is_empty() && !credentials.0.is_empty() && fallback.is_some()
+}
+
+#[test]
+fn skipped_types_are_not_logged() {
+    let _capture = test_util::capture();
+    connect("db://host", &Password("hunter2"), Some(Password("letmein")));
+    let events = test_util::events_of("connect");
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|event| {
+        !event.message().contains("hunter2") && !event.message().contains("letmein")
+    }));
+    assert!(
+        events[0]
+            .message()
+            .contains(r#"url="db://host", credentials=***, fallback=***"#)
+    );
+}
+
+#[tracing_fn(runtime_skip = true)]
+fn change_password(user: &str, password: &str) -> bool {
+    !user.is_empty() && !password.is_empty()
+}
+
+#[test]
+fn runtime_skipped_arguments_are_not_logged() {
+    let _capture = test_util::capture();
+    tracing_fn::redaction::set("test_util::change_password", &["password"]);
+    change_password("admin", "hunter2");
+    let events = test_util::events_of("change_password");
+    assert_eq!(events.len(), 2);
+    assert!(
+        events
+            .iter()
+            .all(|event| !event.message().contains("hunter2"))
+    );
+    assert!(
+        events[0]
+            .message()
+            .contains(r#"user="admin", password=***"#)
+    );
+
+    // 清除后恢复输出
+    tracing_fn::redaction::clear("test_util::change_password");
+    change_password("admin", "hunter2");
+    let events = test_util::events_of("change_password");
+    assert!(events[2].message().contains(r#"password="hunter2""#));
+}
+
+#[test]
+fn durations_are_bounded() {
+    let _capture = test_util::capture();
+    login("admin", "hunter2");
+    assert_duration_under!("login", 10s);
+    assert_duration_under!("login", Duration::from_secs(10));
+    assert_eq!(test_util::parse_limit("1.5ms"), Duration::from_micros(1500));
+}
+
+#[test]
+#[should_panic(expected = "traced 1 times")]
+fn wrong_count_fails() {
+    let _capture = test_util::capture();
+    login("admin", "hunter2");
+    assert_traced!("login", times = 2);
+}
+
+#[test]
+#[should_panic(expected = "to take less than")]
+fn slow_call_fails() {
+    let _capture = test_util::capture();
+    login("admin", "hunter2");
+    assert_duration_under!("login", 0ns);
+}
+
+#[test]
+fn macro_returns_are_recorded() {
+    let _capture = test_util::capture();
+    assert_eq!(checked(0), Err("zero".to_string()));
+    assert_eq!(checked(1), Ok(1));
+    let events = test_util::events_of("checked");
+    assert_eq!(events[1].level(), tracing::Level::WARN);
+    assert!(events[1].message().contains(r#"#Ret: Err("zero")"#));
+    assert_eq!(events[3].level(), tracing::Level::TRACE);
+    assert!(events[3].message().contains("#Ret: Ok(1)"));
+
+    let mut values = vec![1];
+    *first_mut(&mut values).unwrap() = 2;
+    assert_eq!(values, [2]);
+    assert!(first_mut(&mut Vec::new()).is_err());
+    assert!(
+        test_util::events_of("first_mut")[3]
+            .message()
+            .contains(r#"Err("empty")"#)
+    );
+}
+
+#[test]
+fn boxed_errors_render_their_chain() {
+    let _capture = test_util::capture();
+    assert!(parse_port("http").is_err());
+    assert!(
+        test_util::events_of("parse_port")[1]
+            .message()
+            .contains("#Ret: Err(invalid digit found in string)")
+    );
+    assert!(parse_port("80").is_ok());
+    assert!(
+        test_util::events_of("parse_port")[3]
+            .message()
+            .contains("#Ret: Ok(80)")
+    );
+
+    assert!(open_config().is_err());
+    assert!(
+        test_util::events_of("open_config")[1]
+            .message()
+            .contains("#Ret: Err(failed to load config: config.toml not found)")
+    );
+}
+
+#[test]
+fn report_errors_render_on_one_line() {
+    let _capture = test_util::capture();
+    assert!(flush().is_err());
+    assert!(label().is_err());
+    assert!(
+        test_util::events_of("flush")[1]
+            .message()
+            .contains("#Ret: Err(outer: inner)")
+    );
+    assert!(
+        test_util::events_of("label")[1]
+            .message()
+            .contains("#Ret: Err(outer: inner)")
+    );
+}
+
+#[test]
+fn async_macro_returns_borrow_self() {
+    let _capture = test_util::capture();
+    let mut store = Store { items: vec![1] };
+    *block_on(store.first_mut()).unwrap() = 2;
+    assert_eq!(store.items, [2]);
+    let mut empty = Store { items: Vec::new() };
+    assert_eq!(block_on(empty.first_mut()), Err("empty".to_string()));
+
+    let events = test_util::events_of("first_mut");
+    assert_eq!(events.len(), 4);
+    assert!(events[1].message().contains("#Ret: Ok(1)"));
+    assert_eq!(events[3].level(), tracing::Level::WARN);
+    assert!(events[3].message().contains(r#"#Ret: Err("empty")"#));
+}
+
+#[test]
+fn labeled_loops_keep_their_targets() {
+    let _capture = test_util::capture();
+    let grid = vec![vec![1, 0, 5], vec![3, 4]];
+    assert_eq!(find(&grid, 4), Some((1, 1)));
+    // 第一行遇到 0 时跳到下一行, 5 不会被找到
+    assert_eq!(find(&grid, 5), None);
+    assert_eq!(find(&[vec![1]], 9), Some((usize::MAX, 2)));
+
+    let exits: Vec<_> = test_util::events_of("find")
+        .into_iter()
+        .filter(|event| event.kind() == EventKind::Exit)
+        .collect();
+    assert!(exits[0].message().contains("#Ret: Some((1, 1))"));
+    assert!(exits[1].message().contains("#Ret: None"));
+    assert!(exits[2].message().contains("#Ret: Some("));
+}
+
+#[test]
+fn closures_keep_their_own_returns() {
+    let _capture = test_util::capture();
+    assert_eq!(positives(&["3", "", "x", "0", "7"]), Ok(vec![3, 7]));
+    let events = test_util::events_of("positives");
+    assert_eq!(events.len(), 2);
+    assert!(events[1].message().contains("#Ret: Ok([3, 7])"));
+}
+
+#[test]
+fn question_mark_records_the_error() {
+    let _capture = test_util::capture();
+    // 默认输出 `?` 的操作数中导致返回的部分, 即转换之前的错误
+    assert!(matches!(parse_app("x"), Err(AppError::Parse(_))));
+    assert_eq!(parse_app("5"), Ok(5));
+    let events = test_util::events_of("parse_app");
+    assert!(
+        events[1]
+            .message()
+            .contains("#Ret: Err(ParseIntError { kind: InvalidDigit })")
+    );
+    assert!(events[3].message().contains("#Ret: Ok(5)"));
+
+    // try_record: 输出经 `From` 转换为函数的错误类型之后的错误
+    assert!(matches!(parse_coded("x"), Err(AppError::Parse(_))));
+    assert_eq!(parse_coded("5"), Ok(5));
+    let events = test_util::events_of("parse_coded");
+    assert!(
+        events[1]
+            .message()
+            .contains("#Ret: Err(Parse(ParseIntError { kind: InvalidDigit }))")
+    );
+    assert_eq!(events[1].field("error.code"), Some("400"));
+    assert!(events[3].message().contains("#Ret: Ok(5)"));
+    assert_eq!(events[3].field("error.code"), None);
+}
+
+#[tracing_fn(level = "warn", err_only = true)]
+fn handle(id: u32) -> Result<u32, String> {
+    if id == 0 {
+        return Err("missing id".to_string());
+    }
+    Ok(id)
+}
+
+#[test]
+fn err_only_emits_a_combined_event_on_failure() {
+    let _capture = test_util::capture();
+    assert_eq!(handle(1), Ok(1));
+    assert_eq!(handle(2), Ok(2));
+    assert!(test_util::events_of("handle").is_empty());
+
+    assert!(handle(0).is_err());
+    let events = test_util::events_of("handle");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind(), EventKind::Exit);
+    assert_eq!(events[0].level(), tracing::Level::WARN);
+    let message = events[0].message();
+    assert!(message.contains("#Args: id=0 | #Ret: Err(\"missing id\")"));
+    assert!(message.contains("outcome: err"));
+    assert!(events[0].duration().is_some());
+}
+
+#[tracing_fn(first = 3)]
+fn resolve(name: &str) -> usize {
+    name.len()
+}
+
+#[test]
+fn first_suppresses_calls_after_the_limit() {
+    let _capture = test_util::capture();
+    for name in ["a", "bb", "ccc", "dddd", "eeeee", "ffffff"] {
+        resolve(name);
+    }
+    assert_eq!(test_util::calls("resolve"), 3);
+    let events = test_util::events_of("resolve");
+    // 前 3 次调用的入口与退出事件, 以及第 4 次调用输出的一条提示
+    assert_eq!(events.len(), 7);
+    let notices: Vec<_> = events
+        .iter()
+        .filter(|event| event.kind() == EventKind::Other)
+        .collect();
+    assert_eq!(notices.len(), 1);
+    assert!(
+        notices[0]
+            .message()
+            .contains("=== [resolve] #First: further events suppressed after 3 calls")
+    );
+    assert!(
+        events
+            .iter()
+            .all(|event| !event.message().contains("dddd") && !event.message().contains("ffffff"))
+    );
+}
+
+#[tracing_fn(dedup = true)]
+fn poll_status(job: u32) -> bool {
+    job.is_multiple_of(2)
+}
+
+#[test]
+fn dedup_summarizes_repeated_calls() {
+    let _capture = test_util::capture();
+    for job in [7, 7, 7, 7, 8, 8] {
+        poll_status(job);
+    }
+    let events = test_util::events_of("poll_status");
+    let messages: Vec<_> = events.iter().map(|event| event.message()).collect();
+    // 参数变化时先输出被省略次数的汇总, 参数一直不变的 job=8 不输出汇总
+    assert_eq!(events.len(), 5);
+    assert_eq!(events[0].kind(), EventKind::Entry);
+    assert!(messages[0].contains("#Args: job=7"));
+    assert_eq!(events[1].kind(), EventKind::Exit);
+    assert_eq!(events[2].kind(), EventKind::Other);
+    assert!(messages[2].contains("=== [poll_status] #Dedup: repeated x3"));
+    assert_eq!(events[3].kind(), EventKind::Entry);
+    assert!(messages[3].contains("#Args: job=8"));
+    assert_eq!(events[4].kind(), EventKind::Exit);
+    assert_eq!(test_util::calls("poll_status"), 2);
+}
+
+#[tracing_fn(entry_only = true)]
+fn publish(topic: &str, payload: &[u8]) -> usize {
+    topic.len() + payload.len()
+}
+
+#[tracing_fn(exit_only = true)]
+fn drain(buffer: &mut Vec<u8>) -> usize {
+    buffer.drain(..).count()
+}
+
+#[test]
+fn entry_only_and_exit_only_emit_a_single_event() {
+    let _capture = test_util::capture();
+    publish("orders", &[1, 2, 3]);
+    publish("orders", &[4]);
+    let published = test_util::events_of("publish");
+    assert_eq!(published.len(), 2);
+    assert!(
+        published
+            .iter()
+            .all(|event| event.kind() == EventKind::Entry)
+    );
+    assert!(
+        published[0]
+            .message()
+            .contains(r#"#Args: topic="orders", payload=[1, 2, 3]"#)
+    );
+    assert_eq!(test_util::calls("publish"), 2);
+
+    drain(&mut vec![1, 2]);
+    let flushed = test_util::events_of("drain");
+    assert_eq!(flushed.len(), 1);
+    assert_eq!(flushed[0].kind(), EventKind::Exit);
+    assert!(flushed[0].message().contains("#Ret: 2"));
+    assert!(!flushed[0].message().contains("#Args"));
+    assert!(flushed[0].duration().is_some());
+}
@@ -0,0 +1,9 @@
+// dedup 省略整个调用, 与 err_only 同时使用时可能丢失失败的调用
+use tracing_fn::tracing_fn;
+
+#[tracing_fn(dedup = true, err_only = true)]
+fn fetch(id: u32) -> Result<u32, ()> {
+    Ok(id)
+}
+
+fn main() {}
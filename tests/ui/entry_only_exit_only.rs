@@ -0,0 +1,24 @@
+// entry_only 与 exit_only 互斥, 也不能与合并事件的参数同时使用
+use tracing_fn::tracing_fn;
+
+#[tracing_fn(entry_only = true, exit_only = true)]
+fn both(x: i32) -> i32 {
+    x
+}
+
+#[tracing_fn(entry_only = true, paired = true)]
+fn entry_paired(x: i32) -> i32 {
+    x
+}
+
+#[tracing_fn(exit_only = true, err_only = true)]
+fn exit_err_only(x: i32) -> Result<i32, ()> {
+    Ok(x)
+}
+
+#[tracing_fn(entry_only = true, err_level = "warn")]
+fn entry_err_level(x: i32) -> Result<i32, ()> {
+    Ok(x)
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+// 参数名与宏生成的局部变量冲突
+use tracing_fn::tracing_fn;
+
+#[tracing_fn]
+fn add(__tracing_fn_ret: i32, b: i32) -> i32 {
+    __tracing_fn_ret + b
+}
+
+fn main() {}
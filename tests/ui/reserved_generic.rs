@@ -0,0 +1,14 @@
+// 泛型参数与宏生成的类型冲突, 解构的参数同样检查
+use tracing_fn::tracing_fn;
+
+#[tracing_fn]
+fn first<__TracingFnGuard: Copy>(items: &[__TracingFnGuard]) -> Option<__TracingFnGuard> {
+    items.first().copied()
+}
+
+#[tracing_fn]
+fn sum((a, __TRACING_FN_STATS): (i32, i32)) -> i32 {
+    a + __TRACING_FN_STATS
+}
+
+fn main() {}
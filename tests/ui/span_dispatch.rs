@@ -0,0 +1,19 @@
+// 函数的 span 创建于默认 subscriber, 与 dispatch 指定的 Dispatch 无法形成父子关系
+use std::sync::LazyLock;
+
+use tracing::Dispatch;
+use tracing_fn::tracing_fn;
+
+static AUDIT: LazyLock<Dispatch> = LazyLock::new(Dispatch::none);
+
+#[tracing_fn(span = true, dispatch = AUDIT)]
+fn transfer(amount: u64) -> u64 {
+    amount
+}
+
+#[tracing_fn(span_id = true, dispatch = AUDIT)]
+fn refund(amount: u64) -> u64 {
+    amount
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+// strict_args 要求每个参数都出现在 log、format、summarize、json、valuable 或 skip 中
+use tracing_fn::tracing_fn;
+
+#[tracing_fn(strict_args = true, log(user), skip(password))]
+fn login(user: &str, password: &str, remember: bool) -> bool {
+    remember && user == password
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+// max_level_info 移除 TRACE 等级的函数时同样校验参数
+use tracing_fn::tracing_fn;
+
+#[tracing_fn(level = "trace", strict_args = true, log(user))]
+fn login(user: &str, password: &str) -> bool {
+    user == password
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+//! 没有文档的函数不追加 `# Instrumentation`, 仍被 `missing_docs` 报告
+
+#![deny(missing_docs)]
+
+use tracing_fn::tracing_fn;
+
+/// 有文档的函数
+#[tracing_fn]
+pub fn documented() {}
+
+#[tracing_fn]
+pub fn undocumented() {}
+
+fn main() {}
@@ -0,0 +1,148 @@
+//! 编译期白名单
+//!
+//! 设置了 `TRACING_FN_ALLOW` 或 `TRACING_FN_ALLOW_FILE` 时，只有全限定名匹配白名单的函数才会添加 tracing，
+//! 其余被标注的函数展开为原函数。
+
+use std::path::{Component, Path, PathBuf};
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+
+/// 白名单读取的环境变量
+pub(crate) const ENV_VARS: [&str; 2] = ["TRACING_FN_ALLOW", "TRACING_FN_ALLOW_FILE"];
+
+/// 判断全限定名为 `qualified_name` 的函数是否在白名单中, 未设置白名单时所有函数都允许
+pub(crate) fn is_allowed(qualified_name: &str) -> syn::Result<bool> {
+    let Some(patterns) = patterns()? else {
+        return Ok(true);
+    };
+    Ok(patterns
+        .iter()
+        .any(|pattern| glob_match(pattern, qualified_name)))
+}
+
+/// 读取白名单, 两个环境变量同时设置时合并
+fn patterns() -> syn::Result<Option<Vec<String>>> {
+    let mut patterns = None;
+    if let Ok(list) = std::env::var("TRACING_FN_ALLOW") {
+        patterns.get_or_insert_with(Vec::new).extend(
+            list.split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from),
+        );
+    }
+    if let Some(path) = allow_file() {
+        let content = std::fs::read_to_string(&path).map_err(|err| {
+            syn::Error::new(
+                Span::call_site(),
+                format!("无法读取 TRACING_FN_ALLOW_FILE ({}): {err}", path.display()),
+            )
+        })?;
+        // 每行一个模式, `#` 之后为注释
+        patterns.get_or_insert_with(Vec::new).extend(
+            content
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or_default().trim())
+                .filter(|line| !line.is_empty())
+                .map(String::from),
+        );
+    }
+    Ok(patterns)
+}
+
+/// `TRACING_FN_ALLOW_FILE` 指定的文件, 相对路径相对于 crate 的根目录
+fn allow_file() -> Option<PathBuf> {
+    let file = std::env::var("TRACING_FN_ALLOW_FILE").ok()?;
+    let mut path = PathBuf::from(&file);
+    if path.is_relative()
+        && let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR")
+    {
+        path = Path::new(&manifest_dir).join(path);
+    }
+    Some(path)
+}
+
+/// 以 `include_bytes!` 引用白名单文件, 使 rustc 在文件修改后重新编译使用方的 crate
+pub(crate) fn file_dependency() -> Option<TokenStream> {
+    let path = allow_file().filter(|path| path.is_file())?;
+    let path = path.to_str()?;
+    Some(quote!(
+        const _: &[u8] = ::core::include_bytes!(#path);
+    ))
+}
+
+/// 函数的全限定名: crate 名 + 源文件对应的模块路径 + `scope` + 函数名
+///
+/// 源文件对应的模块路径按 cargo 的默认目录结构推断, 例如 `src/auth/login.rs` 对应 `auth::login`。
+pub(crate) fn qualified_name(scope: &[String], fn_name: &str) -> String {
+    let mut segments = Vec::new();
+    if let Ok(crate_name) = std::env::var("CARGO_CRATE_NAME") {
+        segments.push(crate_name);
+    }
+    segments.extend(file_module_path());
+    segments.extend(scope.iter().cloned());
+    segments.push(fn_name.to_string());
+    segments.join("::")
+}
+
+fn file_module_path() -> Vec<String> {
+    let Some(file) = proc_macro::Span::call_site().local_file() else {
+        return Vec::new();
+    };
+    let relative = match std::env::var("CARGO_MANIFEST_DIR") {
+        Ok(manifest_dir) => file
+            .strip_prefix(&manifest_dir)
+            .map(Path::to_path_buf)
+            .unwrap_or(file),
+        Err(_) => file,
+    };
+    let mut components: Vec<String> = relative
+        .with_extension("")
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+
+    // src/bin/<name>、examples/<name>、tests/<name>、benches/<name> 均为独立 crate 的根
+    let root_len = match components.first().map(String::as_str) {
+        Some("src") if components.get(1).map(String::as_str) == Some("bin") => 3,
+        Some("src") => 1,
+        Some("examples" | "tests" | "benches") => 2,
+        _ => 0,
+    };
+    components.drain(..root_len.min(components.len()));
+    if components.last().map(String::as_str) == Some("mod") {
+        components.pop();
+    }
+    if matches!(components.as_slice(), [root] if root == "lib" || root == "main") {
+        components.clear();
+    }
+    components
+}
+
+/// 简单的通配符匹配, `*` 匹配任意长度的任意字符 (包括 `::`)
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let name = name.as_bytes();
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, n));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == name[n] {
+            p += 1;
+            n += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
@@ -0,0 +1,1501 @@
+use std::time::Duration;
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+use syn::{Attribute, Expr, Ident, LitBool, LitInt, LitStr, Meta, Path};
+
+use crate::allow;
+
+/// `#[tracing_fn]`、`#[tracing_impl]`、`#[tracing_mod]` 共用的参数
+///
+/// 除 `skip` 外的参数均为可选值，未设置时使用上层属性或默认值。
+#[derive(Clone, Default)]
+pub(crate) struct Args {
+    pub(crate) level: Option<String>,
+    /// 在调用时返回 `tracing::Level` 的函数路径或表达式, 代替 `level` 决定入口与退出事件的等级
+    pub(crate) level_fn: Option<Expr>,
+    pub(crate) skip: Vec<String>,
+    /// 按类型跳过参数, 每项为以 `::` 连接的类型路径, 与参数类型中出现的类型路径的末尾几段比较
+    pub(crate) skip_types: Vec<String>,
+    /// 只输出集合类型与长度的参数
+    pub(crate) summarize: Vec<String>,
+    /// 通过 serde_json 序列化为结构化字段的参数
+    pub(crate) json: Vec<String>,
+    /// 通过 `valuable::Valuable` 作为结构化值记录的参数
+    pub(crate) valuable: Vec<String>,
+    /// 明确需要输出的参数, 供 `strict_args` 检查
+    pub(crate) log: Vec<String>,
+    /// 是否要求每个参数都明确出现在输出或跳过的参数列表中
+    pub(crate) strict_args: Option<bool>,
+    pub(crate) redact: Option<Redact>,
+    /// 是否在运行时查询通过 `tracing_fn::redaction::set` 设置的跳过的参数
+    pub(crate) runtime_skip: Option<bool>,
+    /// `redact = "mask"` 时代替跳过的参数输出的占位符
+    pub(crate) redacted_as: Option<String>,
+    /// 按参数名指定的格式化函数, 接收 `&参数类型`, 返回实现了 `Display` 的值
+    pub(crate) format: Vec<(Ident, Path)>,
+    pub(crate) force: Option<bool>,
+    pub(crate) cfg: Option<Meta>,
+    /// 运行时判断是否输出事件的表达式, 可以引用函数的参数
+    pub(crate) condition: Option<Expr>,
+    pub(crate) once_per: Option<OncePer>,
+    /// 只输出前 N 次调用的事件
+    pub(crate) first: Option<u64>,
+    /// 是否省略参数与上一次调用相同的调用的事件
+    pub(crate) dedup: Option<bool>,
+    pub(crate) skip_ret: Option<bool>,
+    /// 没有参数时是否省略入口事件中的 `#Args: ()`
+    pub(crate) skip_empty_args: Option<bool>,
+    /// 格式化返回值的函数, 接收 `&返回值类型`, 返回实现了 `Display` 的值
+    pub(crate) ret_with: Option<Path>,
+    /// 日志管道压力较高时的输出策略
+    pub(crate) pressure: Option<Shedding>,
+    /// 函数的优先级, 决定负载较高时优先保留哪些函数的日志
+    pub(crate) priority: Option<Priority>,
+    /// 每个参数与返回值格式化结果的最大字节数
+    pub(crate) max_len: Option<usize>,
+    /// 是否在函数的文档末尾追加 `# Instrumentation` 一节
+    pub(crate) doc: Option<bool>,
+    /// 返回 `Err` 时错误的输出方式
+    pub(crate) err: Option<ErrMode>,
+    /// 返回 `Err` 时退出事件使用的日志等级
+    pub(crate) err_level: Option<String>,
+    /// 从错误中取得错误码的表达式, 其中以 `err` 引用函数返回的错误
+    pub(crate) err_code: Option<Expr>,
+    pub(crate) fanout: Option<bool>,
+    pub(crate) abort_safe: Option<bool>,
+    pub(crate) tenant: Option<bool>,
+    pub(crate) compress_prefix: Option<CompressPrefix>,
+    pub(crate) slow_poll: Option<Duration>,
+    pub(crate) depth: Option<bool>,
+    pub(crate) paired: Option<bool>,
+    /// 是否只在返回 `Err` 或 panic 时输出一条合并的事件
+    pub(crate) err_only: Option<bool>,
+    /// 是否在函数体中的 `process::exit` 与 `process::abort` 之前输出退出事件, 默认为 true
+    pub(crate) process_exit: Option<bool>,
+    /// 入口事件推迟到退出时, 只在耗时超过该阈值时补发
+    pub(crate) entry_on_slow: Option<Duration>,
+    /// 事件中是否以包含模块路径的全限定名标识函数
+    pub(crate) qualified: Option<bool>,
+    /// 事件中是否在函数名之后附带类型参数的类型名
+    pub(crate) generics: Option<bool>,
+    /// 事件中方法名之前附带的类型名
+    pub(crate) self_type: Option<SelfType>,
+    /// 是否以 `#[track_caller]` 标注函数并输出调用方的位置
+    pub(crate) caller: Option<bool>,
+    /// 是否输出最近的外层被追踪调用的函数名
+    pub(crate) caller_fn: Option<bool>,
+    /// 是否只输出入口事件
+    pub(crate) entry_only: Option<bool>,
+    /// 是否只输出退出事件
+    pub(crate) exit_only: Option<bool>,
+    pub(crate) thread: Option<bool>,
+    /// 是否在事件中附加当前 span 的 ID
+    pub(crate) span_id: Option<bool>,
+    pub(crate) child_span: Option<LitStr>,
+    pub(crate) span: Option<FnSpan>,
+    /// 输出事件使用的 `tracing::Dispatch` 的路径, 通常为 `LazyLock<Dispatch>` 类型的静态变量
+    pub(crate) dispatch: Option<Path>,
+    pub(crate) backend: Option<Backend>,
+    pub(crate) audit: Option<bool>,
+    /// 在入口处调用的上下文提供函数, 返回附加到事件中的键值对
+    pub(crate) with: Option<Path>,
+    /// 代替 `Instant::now()` 的计时函数, 返回以微秒为单位的时间戳
+    pub(crate) clock: Option<Path>,
+    pub(crate) measure: Option<Measure>,
+    /// 在哪些事件的消息中输出源码位置
+    pub(crate) location: Option<Location>,
+    /// 退出事件中耗时的输出单位, 未设置时使用 `Duration` 的 Debug 输出
+    pub(crate) duration_unit: Option<DurationUnit>,
+    pub(crate) allocs: Option<bool>,
+    pub(crate) record: Option<bool>,
+    pub(crate) replay: Option<bool>,
+    /// 是否在入口与退出处写入二进制调用记录
+    pub(crate) binary: Option<bool>,
+    /// 是否在测试函数的开头初始化输出到测试输出的 subscriber
+    pub(crate) test_subscriber: Option<bool>,
+    /// 生成的代码引用 tracing 的路径, 用于经由其他 crate 重新导出的 tracing
+    pub(crate) tracing_crate: Option<Path>,
+    pub(crate) verbosity_tiers: Option<VerbosityTiers>,
+    /// 附加到入口与退出事件的固定字段, 按名称追加或覆盖外层的字段
+    pub(crate) fields: Vec<(Ident, Expr)>,
+    pub(crate) poll: Option<PollMode>,
+    pub(crate) batch: Option<Batch>,
+    /// `opts!` 定义的预设参数, 由预设的 macro_rules 以 `use = (...)` 的形式传入, 被同一属性中的其他参数覆盖
+    pub(crate) preset: Option<Box<Args>>,
+    /// 函数所在的内联模块与类型路径, 由 `#[tracing_mod]`、`#[tracing_impl]` 填充, 不能通过参数设置
+    pub(crate) scope: Vec<String>,
+    /// 函数所在的 impl 块, 由 `#[tracing_impl]` 填充, 不能通过参数设置
+    pub(crate) impl_block: Option<ImplBlock>,
+}
+
+/// 函数所在的 impl 块的种类
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImplBlock {
+    /// `impl Type { ... }`
+    Inherent,
+    /// `impl Trait for Type { ... }`
+    Trait,
+}
+
+/// 输出事件使用的日志库
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Backend {
+    /// 使用 `tracing::event!` 输出, 默认值
+    Tracing,
+    /// 使用 `log::log!` 输出, 结构化字段不输出
+    Log,
+    /// 使用 `eprintln!` 输出到标准错误, 不依赖任何日志库, 结构化字段不输出
+    Stderr,
+    /// 通过 TraceLogging 输出为 ETW 事件, 需要启用 win-etw feature, 结构化字段不输出
+    Etw,
+}
+
+impl Backend {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Backend::Tracing => "tracing",
+            Backend::Log => "log",
+            Backend::Stderr => "stderr",
+            Backend::Etw => "etw",
+        }
+    }
+}
+
+/// 校验日志后端
+fn parse_backend(backend: &str, span: Span) -> syn::Result<Backend> {
+    match backend.trim().to_lowercase().as_str() {
+        "tracing" => Ok(Backend::Tracing),
+        "log" => Ok(Backend::Log),
+        "stderr" => Ok(Backend::Stderr),
+        "etw" => Ok(Backend::Etw),
+        _ => Err(syn::Error::new(
+            span,
+            "未知的日志后端, 可选值: tracing, log, stderr, etw",
+        )),
+    }
+}
+
+/// 日志管道压力较高时的输出策略
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Shedding {
+    /// 不受压力影响
+    Keep,
+    /// 压力为 High 时只输出函数名与耗时, 为 Critical 时不输出, 默认值
+    Downgrade,
+    /// 压力为 High 及以上时不输出
+    Drop,
+}
+
+impl Shedding {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Shedding::Keep => "keep",
+            Shedding::Downgrade => "downgrade",
+            Shedding::Drop => "drop",
+        }
+    }
+}
+
+/// `err` 参数, 返回 `Err` 时错误的输出方式
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ErrMode {
+    /// 使用返回值的默认输出方式, `err = false`
+    Off,
+    /// 以 `Display` 输出错误及其 `source()` 链, `err = true` 或 `err = "chain"`
+    Chain,
+    /// 以 `{:#}` 输出错误, 适用于 `anyhow::Error` 与 `eyre::Report`, `err = "alternate"`
+    Alternate,
+}
+
+/// 函数的优先级
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Priority {
+    /// 业务关键的函数, 压力较高时仍完整输出
+    High,
+    /// 默认值
+    Normal,
+    /// 频繁调用的辅助函数, 压力较高时最先丢弃
+    Low,
+}
+
+impl Priority {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Normal => "normal",
+            Priority::Low => "low",
+        }
+    }
+
+    /// 未指定 `pressure` 时采用的压力策略
+    pub(crate) fn shedding(self) -> Shedding {
+        match self {
+            Priority::High => Shedding::Keep,
+            Priority::Normal => Shedding::Downgrade,
+            Priority::Low => Shedding::Drop,
+        }
+    }
+}
+
+/// 校验函数的优先级
+fn parse_priority(priority: &str, span: Span) -> syn::Result<Priority> {
+    match priority.trim().to_lowercase().as_str() {
+        "high" => Ok(Priority::High),
+        "normal" => Ok(Priority::Normal),
+        "low" => Ok(Priority::Low),
+        _ => Err(syn::Error::new(
+            span,
+            "未知的优先级, 可选值: high, normal, low",
+        )),
+    }
+}
+
+/// 校验压力下的输出策略
+fn parse_shedding(shedding: &str, span: Span) -> syn::Result<Shedding> {
+    match shedding.trim().to_lowercase().as_str() {
+        "keep" => Ok(Shedding::Keep),
+        "downgrade" => Ok(Shedding::Downgrade),
+        "drop" => Ok(Shedding::Drop),
+        _ => Err(syn::Error::new(
+            span,
+            "未知的压力策略, 可选值: keep, downgrade, drop",
+        )),
+    }
+}
+
+/// `skip` 中的参数的输出方式
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Redact {
+    /// 输出为 `***`, 默认值
+    Mask,
+    /// 输出 `Debug` 输出的 SHA-256 哈希前缀
+    Sha256,
+    /// 不输出参数名与值
+    Omit,
+}
+
+/// 校验参数的隐藏方式
+fn parse_redact(redact: &str, span: Span) -> syn::Result<Redact> {
+    match redact.trim().to_lowercase().as_str() {
+        "mask" => Ok(Redact::Mask),
+        "sha256" => Ok(Redact::Sha256),
+        "omit" => Ok(Redact::Omit),
+        _ => Err(syn::Error::new(
+            span,
+            "未知的隐藏方式, 可选值: mask, sha256, omit",
+        )),
+    }
+}
+
+/// 退出事件中输出的耗时
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Measure {
+    /// 墙上时间, 默认值
+    Wall,
+    /// 当前线程的 CPU 时间
+    Cpu,
+    /// 同时输出墙上时间与 CPU 时间
+    Both,
+}
+
+impl Measure {
+    pub(crate) fn cpu(self) -> bool {
+        matches!(self, Measure::Cpu | Measure::Both)
+    }
+
+    pub(crate) fn wall(self) -> bool {
+        matches!(self, Measure::Wall | Measure::Both)
+    }
+}
+
+/// `location` 参数
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Location {
+    /// 只在入口事件中输出, 默认值
+    Entry,
+    Exit,
+    Both,
+    /// 不在消息中输出, 依赖 tracing 事件元数据中的位置
+    None,
+}
+
+impl Location {
+    pub(crate) fn entry(self) -> bool {
+        matches!(self, Location::Entry | Location::Both)
+    }
+
+    pub(crate) fn exit(self) -> bool {
+        matches!(self, Location::Exit | Location::Both)
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Location::Entry => "entry",
+            Location::Exit => "exit",
+            Location::Both => "both",
+            Location::None => "none",
+        }
+    }
+}
+
+/// `self_type` 参数
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) enum SelfType {
+    /// `self_type = true`: 使用 `#[tracing_impl]` 所在 impl 块的类型名
+    Detect,
+    /// `self_type = "UserService"`
+    Named(String),
+    /// `self_type = false`: 覆盖预设或外层的设置
+    Off,
+}
+
+/// `duration_unit` 参数
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DurationUnit {
+    Ns,
+    Us,
+    Ms,
+    S,
+}
+
+impl DurationUnit {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            DurationUnit::Ns => "ns",
+            DurationUnit::Us => "us",
+            DurationUnit::Ms => "ms",
+            DurationUnit::S => "s",
+        }
+    }
+
+    /// 运行时 `DurationUnit` 中对应的变体名
+    pub(crate) fn variant(self) -> &'static str {
+        match self {
+            DurationUnit::Ns => "Ns",
+            DurationUnit::Us => "Us",
+            DurationUnit::Ms => "Ms",
+            DurationUnit::S => "S",
+        }
+    }
+}
+
+/// `once_per(key = "...", capacity = 1024, period = "60s")` 参数
+#[derive(Clone)]
+pub(crate) struct OncePer {
+    /// 区分调用的 key 表达式, 通常为参数名
+    pub(crate) key: Expr,
+    /// LRU 中最多记录的 key 数量
+    pub(crate) capacity: usize,
+    /// 汇总输出被抑制调用的周期
+    pub(crate) period: Duration,
+}
+
+/// `poll(id = "...")` 参数
+#[derive(Clone)]
+pub(crate) struct PollMode {
+    /// 区分 future 实例的 id 表达式, 在函数体之前求值
+    pub(crate) id: Expr,
+}
+
+impl PollMode {
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        let mut id = None;
+        meta.parse_nested_meta(|nested| {
+            if nested.path.is_ident("id") {
+                let value: LitStr = nested.value()?.parse()?;
+                id = Some(value.parse()?);
+            } else {
+                return Err(nested.error("未知参数, 可选参数: id"));
+            }
+            Ok(())
+        })?;
+        let id = id.ok_or_else(|| meta.error("poll 缺少 id 参数"))?;
+        Ok(Self { id })
+    }
+}
+
+/// `batch(items, chunk = 100)` 参数
+#[derive(Clone)]
+pub(crate) struct Batch {
+    /// 被处理的集合参数, 用于在入口处取得条目总数
+    pub(crate) items: Option<Ident>,
+    /// 每处理多少个条目输出一次进度事件
+    pub(crate) chunk: u64,
+}
+
+impl Batch {
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        let mut items = None;
+        let mut chunk = 100;
+        meta.parse_nested_meta(|nested| {
+            if nested.path.is_ident("chunk") {
+                let value: LitInt = nested.value()?.parse()?;
+                chunk = value.base10_parse()?;
+                if chunk == 0 {
+                    return Err(syn::Error::new(value.span(), "chunk 必须大于 0"));
+                }
+            } else if nested.input.is_empty() || nested.input.peek(syn::Token![,]) {
+                if items.is_some() {
+                    return Err(nested.error("batch 只能指定一个集合参数"));
+                }
+                items = Some(nested.path.require_ident()?.clone());
+            } else {
+                return Err(nested.error("未知参数, 可选参数: chunk"));
+            }
+            Ok(())
+        })?;
+        Ok(Self { items, chunk })
+    }
+}
+
+/// `compress_prefix(len = 1, window = "60s")` 参数
+#[derive(Clone)]
+pub(crate) struct CompressPrefix {
+    /// 作为前缀的参数个数
+    pub(crate) len: usize,
+    /// 完整前缀的重新输出周期
+    pub(crate) window: Duration,
+}
+
+/// `span = true` 或 `span(handle = "span", record(rows, bytes))` 参数
+#[derive(Clone)]
+pub(crate) struct FnSpan {
+    /// `span = false` 时为 false, 用于覆盖外层属性
+    pub(crate) enabled: bool,
+    /// 函数体中访问该 span 使用的变量名
+    pub(crate) handle: Ident,
+    /// 创建时声明为 `tracing::field::Empty`、由函数体通过 `record` 填充的字段
+    pub(crate) record: Vec<Ident>,
+}
+
+impl FnSpan {
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        let mut span = Self {
+            enabled: true,
+            handle: Ident::new("__tracing_fn_span", Span::call_site()),
+            record: Vec::new(),
+        };
+        if meta.input.peek(syn::Token![=]) {
+            let value: LitBool = meta.value()?.parse()?;
+            span.enabled = value.value;
+            return Ok(span);
+        }
+        meta.parse_nested_meta(|nested| {
+            if nested.path.is_ident("handle") {
+                let value: LitStr = nested.value()?.parse()?;
+                // 解析出的标识符带有字面量的 span, 函数体因此可以访问该变量
+                span.handle = value.parse()?;
+            } else if nested.path.is_ident("record") {
+                nested.parse_nested_meta(|field| {
+                    span.record.push(field.path.require_ident()?.clone());
+                    Ok(())
+                })?;
+            } else {
+                return Err(nested.error("未知参数, 可选参数: handle, record"));
+            }
+            Ok(())
+        })?;
+        Ok(span)
+    }
+}
+
+/// `verbosity_tiers(args = 2, ret = 3)` 参数
+#[derive(Clone)]
+pub(crate) struct VerbosityTiers {
+    /// 输出参数所需的最低详细程度
+    pub(crate) args: u8,
+    /// 输出返回值所需的最低详细程度
+    pub(crate) ret: u8,
+}
+
+impl VerbosityTiers {
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        let mut args = 2;
+        let mut ret = 3;
+        // 允许省略括号, 全部使用默认值
+        if meta.input.peek(syn::token::Paren) {
+            meta.parse_nested_meta(|nested| {
+                if nested.path.is_ident("args") {
+                    let value: LitInt = nested.value()?.parse()?;
+                    args = value.base10_parse()?;
+                } else if nested.path.is_ident("ret") {
+                    let value: LitInt = nested.value()?.parse()?;
+                    ret = value.base10_parse()?;
+                } else {
+                    return Err(nested.error("未知参数, 可选参数: args, ret"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(Self { args, ret })
+    }
+}
+
+impl CompressPrefix {
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        let mut len = 1;
+        let mut window = Duration::from_secs(60);
+        // 允许省略括号, 全部使用默认值
+        if meta.input.peek(syn::token::Paren) {
+            meta.parse_nested_meta(|nested| {
+                if nested.path.is_ident("len") {
+                    let value: LitInt = nested.value()?.parse()?;
+                    len = value.base10_parse()?;
+                } else if nested.path.is_ident("window") {
+                    let value: LitStr = nested.value()?.parse()?;
+                    window = parse_duration(&value)?;
+                } else {
+                    return Err(nested.error("未知参数, 可选参数: len, window"));
+                }
+                Ok(())
+            })?;
+        }
+        if len == 0 {
+            return Err(meta.error("compress_prefix 的 len 必须大于 0"));
+        }
+        Ok(Self { len, window })
+    }
+}
+
+impl OncePer {
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        let mut key = None;
+        let mut capacity = 1024;
+        let mut period = Duration::from_secs(60);
+        meta.parse_nested_meta(|nested| {
+            if nested.path.is_ident("key") {
+                let value: LitStr = nested.value()?.parse()?;
+                key = Some(value.parse()?);
+            } else if nested.path.is_ident("capacity") {
+                let value: LitInt = nested.value()?.parse()?;
+                capacity = value.base10_parse()?;
+            } else if nested.path.is_ident("period") {
+                let value: LitStr = nested.value()?.parse()?;
+                period = parse_duration(&value)?;
+            } else {
+                return Err(nested.error("未知参数, 可选参数: key, capacity, period"));
+            }
+            Ok(())
+        })?;
+        let key = key.ok_or_else(|| meta.error("once_per 缺少 key 参数"))?;
+        Ok(Self {
+            key,
+            capacity,
+            period,
+        })
+    }
+}
+
+/// 校验日志等级, 返回小写形式
+fn parse_level(level: &str, span: Span) -> syn::Result<String> {
+    let level = level.trim().to_lowercase();
+    if !matches!(
+        level.as_str(),
+        "trace" | "debug" | "info" | "warn" | "error"
+    ) {
+        return Err(syn::Error::new(
+            span,
+            "未知的日志等级, 可选值: trace, debug, info, warn, error",
+        ));
+    }
+    Ok(level)
+}
+
+/// [`Args::from_env`] 与调用报告读取的环境变量
+const ENV_VARS: [&str; 10] = [
+    "TRACING_FN_LEVEL",
+    "TRACING_FN_FORCE",
+    "TRACING_FN_DEFAULT_SKIP_RET",
+    "TRACING_FN_DEFAULT_SKIP_EMPTY_ARGS",
+    "TRACING_FN_SKIP_TYPES",
+    "TRACING_FN_REDACT",
+    "TRACING_FN_REDACTED_AS",
+    "TRACING_FN_BACKEND",
+    "TRACING_FN_CRATE",
+    "TRACING_FN_REPORT",
+];
+
+/// 宏读取的环境变量与白名单文件的依赖声明
+///
+/// 过程宏中通过 `std::env::var` 读取的环境变量不会被 rustc 记录为依赖，修改后使用方的 crate 不会重新编译。
+/// 展开中的 `option_env!` 与 `include_bytes!` 使 rustc 记录这些依赖，本身不产生任何运行时代码。
+pub(crate) fn env_dependencies() -> TokenStream {
+    let vars = ENV_VARS.iter().chain(allow::ENV_VARS.iter());
+    let file = allow::file_dependency();
+    quote! {
+        #(const _: ::core::option::Option<&str> = ::core::option_env!(#vars);)*
+        #file
+    }
+}
+
+/// 读取非空的环境变量
+pub(crate) fn env_var(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+pub(crate) fn parse_env_bool(name: &str, value: &str) -> syn::Result<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(syn::Error::new(
+            Span::call_site(),
+            format!("环境变量 {name} 的值无效, 可选值: true, false, 1, 0"),
+        )),
+    }
+}
+
+/// 解析 `"SecretString, secrecy::SecretBox"` 形式的类型路径列表
+fn parse_type_list(value: &str, span: Span) -> syn::Result<Vec<String>> {
+    let mut types = Vec::new();
+    for ty in value.split(',').map(str::trim).filter(|ty| !ty.is_empty()) {
+        let path: Path = syn::parse_str(ty)
+            .map_err(|_| syn::Error::new(span, format!("无效的类型路径: {ty}")))?;
+        push_unique(&mut types, path_string(&path));
+    }
+    Ok(types)
+}
+
+/// 以 `::` 连接路径中各段的名称, 忽略泛型参数与开头的 `::`
+fn path_string(path: &Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn push_unique(list: &mut Vec<String>, value: String) {
+    if !list.contains(&value) {
+        list.push(value);
+    }
+}
+
+/// 解析 `"100ms"`、`"60s"` 形式的时长, 支持 ns、us、ms、s、m、h 单位
+pub(crate) fn parse_duration(lit: &LitStr) -> syn::Result<Duration> {
+    let value = lit.value();
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| syn::Error::new(lit.span(), "无效的时长, 示例: \"100ms\", \"60s\""))?;
+    let duration = match unit.trim() {
+        "ns" => Duration::from_nanos(number),
+        "us" | "µs" => Duration::from_micros(number),
+        "ms" => Duration::from_millis(number),
+        "s" => Duration::from_secs(number),
+        "m" => Duration::from_secs(number * 60),
+        "h" => Duration::from_secs(number * 3600),
+        _ => {
+            return Err(syn::Error::new(
+                lit.span(),
+                "未知的时长单位, 可选单位: ns, us, ms, s, m, h",
+            ));
+        }
+    };
+    Ok(duration)
+}
+
+impl Args {
+    /// 解析属性参数, 例如 `level = "info", skip(password, token)`
+    pub(crate) fn parse(input: proc_macro2::TokenStream) -> syn::Result<Self> {
+        let mut args = Args::default();
+        let parser = syn::meta::parser(|meta| args.parse_meta(meta));
+        syn::parse::Parser::parse2(parser, input)?;
+        if let Some(preset) = args.preset.take() {
+            args = preset.merge(&args);
+        }
+        args.validate()?;
+        Ok(args)
+    }
+
+    /// 生成的代码引用 tracing 的路径, 未设置 `crate` 时为 `::tracing`
+    pub(crate) fn tracing_path(&self) -> proc_macro2::TokenStream {
+        match &self.tracing_crate {
+            Some(path) => quote::quote!(#path),
+            None => quote::quote!(::tracing),
+        }
+    }
+
+    /// 解析 `traced_extern!` 的参数, 只支持与函数签名无关的参数
+    pub(crate) fn parse_call_options(input: proc_macro2::TokenStream) -> syn::Result<Self> {
+        const SUPPORTED: [&str; 7] = [
+            "level", "force", "cfg", "backend", "skip_ret", "max_len", "crate",
+        ];
+        let mut args = Args::default();
+        let parser = syn::meta::parser(|meta| {
+            if SUPPORTED.iter().any(|name| meta.path.is_ident(name)) {
+                args.parse_meta(meta)
+            } else {
+                Err(meta.error(format!("未知参数, 可选参数: {}", SUPPORTED.join(", "))))
+            }
+        });
+        syn::parse::Parser::parse2(parser, input)?;
+        args.validate()?;
+        Ok(args)
+    }
+
+    /// 从函数上的属性解析参数, 属性可以不带参数
+    pub(crate) fn from_attr(attr: &Attribute) -> syn::Result<Self> {
+        match &attr.meta {
+            Meta::Path(_) => Ok(Args::default()),
+            Meta::List(list) => Args::parse(list.tokens.clone()),
+            Meta::NameValue(nv) => Err(syn::Error::new_spanned(
+                nv,
+                "参数格式应为 `#[tracing_fn(...)]`",
+            )),
+        }
+    }
+
+    fn parse_meta(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("level") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.level = Some(parse_level(&value.value(), value.span())?);
+        } else if meta.path.is_ident("level_fn") {
+            let value: LitStr = meta.value()?.parse()?;
+            // 路径表示无参数的函数, 其余表达式 (例如 `level_for(user_id)`) 原样求值
+            self.level_fn = Some(match value.parse()? {
+                Expr::Path(path) => syn::parse_quote!(#path()),
+                expr => expr,
+            });
+        } else if meta.path.is_ident("skip") {
+            if meta.input.peek(syn::Token![=]) {
+                // skip = "a, b"
+                let value: LitStr = meta.value()?.parse()?;
+                self.skip.extend(
+                    value
+                        .value()
+                        .split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty()),
+                );
+            } else {
+                // skip(a, b)
+                meta.parse_nested_meta(|nested| {
+                    let ident = nested.path.require_ident()?;
+                    self.skip.push(ident.to_string());
+                    Ok(())
+                })?;
+            }
+        } else if meta.path.is_ident("skip_types") {
+            if meta.input.peek(syn::Token![=]) {
+                // skip_types = "SecretString, secrecy::SecretBox"
+                let value: LitStr = meta.value()?.parse()?;
+                for ty in parse_type_list(&value.value(), value.span())? {
+                    push_unique(&mut self.skip_types, ty);
+                }
+            } else {
+                // skip_types(SecretString, secrecy::SecretBox)
+                meta.parse_nested_meta(|nested| {
+                    push_unique(&mut self.skip_types, path_string(&nested.path));
+                    Ok(())
+                })?;
+            }
+        } else if meta.path.is_ident("summarize") {
+            if meta.input.peek(syn::Token![=]) {
+                // summarize = "items, buffer"
+                let value: LitStr = meta.value()?.parse()?;
+                for name in value.value().split(',').map(str::trim) {
+                    if !name.is_empty() {
+                        push_unique(&mut self.summarize, name.to_string());
+                    }
+                }
+            } else {
+                // summarize(items, buffer)
+                meta.parse_nested_meta(|nested| {
+                    let ident = nested.path.require_ident()?;
+                    push_unique(&mut self.summarize, ident.to_string());
+                    Ok(())
+                })?;
+            }
+        } else if meta.path.is_ident("json") {
+            if meta.input.peek(syn::Token![=]) {
+                // json = "request, body"
+                let value: LitStr = meta.value()?.parse()?;
+                for name in value.value().split(',').map(str::trim) {
+                    if !name.is_empty() {
+                        push_unique(&mut self.json, name.to_string());
+                    }
+                }
+            } else {
+                // json(request, body)
+                meta.parse_nested_meta(|nested| {
+                    let ident = nested.path.require_ident()?;
+                    push_unique(&mut self.json, ident.to_string());
+                    Ok(())
+                })?;
+            }
+        } else if meta.path.is_ident("valuable") {
+            if meta.input.peek(syn::Token![=]) {
+                // valuable = "order, user"
+                let value: LitStr = meta.value()?.parse()?;
+                for name in value.value().split(',').map(str::trim) {
+                    if !name.is_empty() {
+                        push_unique(&mut self.valuable, name.to_string());
+                    }
+                }
+            } else {
+                // valuable(order, user)
+                meta.parse_nested_meta(|nested| {
+                    let ident = nested.path.require_ident()?;
+                    push_unique(&mut self.valuable, ident.to_string());
+                    Ok(())
+                })?;
+            }
+        } else if meta.path.is_ident("log") {
+            if meta.input.peek(syn::Token![=]) {
+                // log = "user, amount"
+                let value: LitStr = meta.value()?.parse()?;
+                for name in value.value().split(',').map(str::trim) {
+                    if !name.is_empty() {
+                        push_unique(&mut self.log, name.to_string());
+                    }
+                }
+            } else {
+                // log(user, amount)
+                meta.parse_nested_meta(|nested| {
+                    let ident = nested.path.require_ident()?;
+                    push_unique(&mut self.log, ident.to_string());
+                    Ok(())
+                })?;
+            }
+        } else if meta.path.is_ident("strict_args") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.strict_args = Some(value.value);
+        } else if meta.path.is_ident("force") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.force = Some(value.value);
+        } else if meta.path.is_ident("cfg") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.cfg = Some(value.parse()?);
+        } else if meta.path.is_ident("condition") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.condition = Some(value.parse()?);
+        } else if meta.path.is_ident("first") {
+            let value: LitInt = meta.value()?.parse()?;
+            let first = value.base10_parse()?;
+            if first == 0 {
+                return Err(syn::Error::new(value.span(), "first 必须大于 0"));
+            }
+            self.first = Some(first);
+        } else if meta.path.is_ident("dedup") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.dedup = Some(value.value);
+        } else if meta.path.is_ident("once_per") {
+            self.once_per = Some(OncePer::parse(&meta)?);
+        } else if meta.path.is_ident("skip_empty_args") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.skip_empty_args = Some(value.value);
+        } else if meta.path.is_ident("pressure") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.pressure = Some(parse_shedding(&value.value(), value.span())?);
+        } else if meta.path.is_ident("priority") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.priority = Some(parse_priority(&value.value(), value.span())?);
+        } else if meta.path.is_ident("max_len") {
+            let value: LitInt = meta.value()?.parse()?;
+            let max_len = value.base10_parse()?;
+            if max_len == 0 {
+                return Err(syn::Error::new(value.span(), "max_len 必须大于 0"));
+            }
+            self.max_len = Some(max_len);
+        } else if meta.path.is_ident("doc") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.doc = Some(value.value);
+        } else if meta.path.is_ident("skip_ret") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.skip_ret = Some(value.value);
+        } else if meta.path.is_ident("fanout") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.fanout = Some(value.value);
+        } else if meta.path.is_ident("abort_safe") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.abort_safe = Some(value.value);
+        } else if meta.path.is_ident("tenant") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.tenant = Some(value.value);
+        } else if meta.path.is_ident("compress_prefix") {
+            self.compress_prefix = Some(CompressPrefix::parse(&meta)?);
+        } else if meta.path.is_ident("slow_poll") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.slow_poll = Some(parse_duration(&value)?);
+        } else if meta.path.is_ident("depth") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.depth = Some(value.value);
+        } else if meta.path.is_ident("paired") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.paired = Some(value.value);
+        } else if meta.path.is_ident("process_exit") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.process_exit = Some(value.value);
+        } else if meta.path.is_ident("entry_on_slow") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.entry_on_slow = Some(parse_duration(&value)?);
+        } else if meta.path.is_ident("qualified") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.qualified = Some(value.value);
+        } else if meta.path.is_ident("generics") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.generics = Some(value.value);
+        } else if meta.path.is_ident("self_type") {
+            let value = meta.value()?;
+            self.self_type = Some(if value.peek(LitStr) {
+                let name: LitStr = value.parse()?;
+                let ty: syn::Path = name.parse().map_err(|_| {
+                    syn::Error::new(name.span(), "`self_type` 应为类型名, 例如 \"UserService\"")
+                })?;
+                SelfType::Named(quote::quote!(#ty).to_string().replace(' ', ""))
+            } else {
+                let value: LitBool = value.parse()?;
+                if value.value {
+                    SelfType::Detect
+                } else {
+                    SelfType::Off
+                }
+            });
+        } else if meta.path.is_ident("caller") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.caller = Some(value.value);
+        } else if meta.path.is_ident("caller_fn") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.caller_fn = Some(value.value);
+        } else if meta.path.is_ident("entry_only") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.entry_only = Some(value.value);
+        } else if meta.path.is_ident("exit_only") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.exit_only = Some(value.value);
+        } else if meta.path.is_ident("err_only") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.err_only = Some(value.value);
+        } else if meta.path.is_ident("thread") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.thread = Some(value.value);
+        } else if meta.path.is_ident("span_id") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.span_id = Some(value.value);
+        } else if meta.path.is_ident("span") {
+            self.span = Some(FnSpan::parse(&meta)?);
+        } else if meta.path.is_ident("child_span") {
+            let value: LitStr = meta.value()?.parse()?;
+            if value.value().is_empty() {
+                return Err(syn::Error::new(value.span(), "child_span 的名称不能为空"));
+            }
+            self.child_span = Some(value);
+        } else if meta.path.is_ident("dispatch") {
+            self.dispatch = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("audit") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.audit = Some(value.value);
+        } else if meta.path.is_ident("ret_with") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.ret_with = Some(value.parse()?);
+        } else if meta.path.is_ident("err") {
+            let value = meta.value()?;
+            self.err = Some(if value.peek(LitStr) {
+                let value: LitStr = value.parse()?;
+                match value.value().trim().to_lowercase().as_str() {
+                    "chain" => ErrMode::Chain,
+                    "alternate" => ErrMode::Alternate,
+                    _ => {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "未知的错误输出方式, 可选值: true, false, chain, alternate",
+                        ));
+                    }
+                }
+            } else {
+                let value: LitBool = value.parse()?;
+                if value.value {
+                    ErrMode::Chain
+                } else {
+                    ErrMode::Off
+                }
+            });
+        } else if meta.path.is_ident("err_level") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.err_level = Some(parse_level(&value.value(), value.span())?);
+        } else if meta.path.is_ident("err_code") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.err_code = Some(value.parse()?);
+        } else if meta.path.is_ident("with") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.with = Some(value.parse()?);
+        } else if meta.path.is_ident("clock") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.clock = Some(value.parse()?);
+        } else if meta.path.is_ident("measure") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.measure = Some(match value.value().trim() {
+                "wall" => Measure::Wall,
+                "cpu" => Measure::Cpu,
+                "both" => Measure::Both,
+                _ => {
+                    return Err(syn::Error::new(
+                        value.span(),
+                        "未知的耗时类型, 可选值: wall, cpu, both",
+                    ));
+                }
+            });
+        } else if meta.path.is_ident("location") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.location = Some(match value.value().trim() {
+                "entry" => Location::Entry,
+                "exit" => Location::Exit,
+                "both" => Location::Both,
+                "none" => Location::None,
+                _ => {
+                    return Err(syn::Error::new(
+                        value.span(),
+                        "未知的位置输出方式, 可选值: entry, exit, both, none",
+                    ));
+                }
+            });
+        } else if meta.path.is_ident("duration_unit") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.duration_unit = Some(match value.value().trim() {
+                "ns" => DurationUnit::Ns,
+                "us" => DurationUnit::Us,
+                "ms" => DurationUnit::Ms,
+                "s" => DurationUnit::S,
+                _ => {
+                    return Err(syn::Error::new(
+                        value.span(),
+                        "未知的耗时单位, 可选值: ns, us, ms, s",
+                    ));
+                }
+            });
+        } else if meta.path.is_ident("allocs") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.allocs = Some(value.value);
+        } else if meta.path.is_ident("record") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.record = Some(value.value);
+        } else if meta.path.is_ident("replay") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.replay = Some(value.value);
+        } else if meta.path.is_ident("binary") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.binary = Some(value.value);
+        } else if meta.path.is_ident("crate") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.tracing_crate = Some(value.parse()?);
+        } else if meta.path.is_ident("test_subscriber") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.test_subscriber = Some(value.value);
+        } else if meta.path.is_ident("poll") {
+            self.poll = Some(PollMode::parse(&meta)?);
+        } else if meta.path.is_ident("batch") {
+            self.batch = Some(Batch::parse(&meta)?);
+        } else if meta.path.is_ident("verbosity_tiers") {
+            self.verbosity_tiers = Some(VerbosityTiers::parse(&meta)?);
+        } else if meta.path.is_ident("fields") {
+            meta.parse_nested_meta(|nested| {
+                let name = nested.path.require_ident()?.clone();
+                let value: Expr = nested.value()?.parse()?;
+                if let Some(field) = self.fields.iter_mut().find(|(n, _)| *n == name) {
+                    field.1 = value;
+                } else {
+                    self.fields.push((name, value));
+                }
+                Ok(())
+            })?;
+        } else if meta.path.is_ident("redact") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.redact = Some(parse_redact(&value.value(), value.span())?);
+        } else if meta.path.is_ident("runtime_skip") {
+            let value: LitBool = meta.value()?.parse()?;
+            self.runtime_skip = Some(value.value);
+        } else if meta.path.is_ident("redacted_as") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.redacted_as = Some(value.value());
+        } else if meta.path.is_ident("format") {
+            meta.parse_nested_meta(|nested| {
+                let name = nested.path.require_ident()?.clone();
+                let value: LitStr = nested.value()?.parse()?;
+                let value: Path = value.parse()?;
+                if let Some(format) = self.format.iter_mut().find(|(n, _)| *n == name) {
+                    format.1 = value;
+                } else {
+                    self.format.push((name, value));
+                }
+                Ok(())
+            })?;
+        } else if meta.path.is_ident("backend") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.backend = Some(parse_backend(&value.value(), value.span())?);
+        } else if meta.path.is_ident("use") {
+            // `use = 路径` 在解析参数之前由 `preset::forward` 转交给预设的 macro_rules,
+            // 预设展开后以 `use = (...)` 的形式传入预设的参数
+            let value = meta.value()?;
+            if !value.peek(syn::token::Paren) {
+                return Err(meta.error(
+                    "`use = 路径` 只能用于 `#[tracing_fn]`、`#[tracing_impl]` 或 `#[tracing_mod]` 自身, 不能用于 `opts!` 或 `#[tracing_impl]`、`#[tracing_mod]` 内部的属性",
+                ));
+            }
+            if self.preset.is_some() {
+                return Err(meta.error("`use` 只能设置一次"));
+            }
+            let content;
+            syn::parenthesized!(content in value);
+            self.preset = Some(Box::new(Args::parse(content.parse()?)?));
+        } else {
+            return Err(meta.error(
+                "未知参数, 可选参数: level, level_fn, skip, skip_types, summarize, json, valuable, log, strict_args, redact, runtime_skip, redacted_as, format, force, cfg, condition, once_per, first, dedup, skip_empty_args, skip_ret, ret_with, max_len, doc, err, err_level, err_code, fanout, abort_safe, tenant, compress_prefix, slow_poll, depth, paired, err_only, process_exit, entry_on_slow, qualified, self_type, generics, caller, caller_fn, entry_only, exit_only, thread, span_id, span, child_span, dispatch, backend, audit, with, clock, measure, duration_unit, location, allocs, verbosity_tiers, pressure, priority, fields, poll, batch, record, replay, binary, test_subscriber, crate, use",
+            ));
+        }
+        Ok(())
+    }
+
+    /// 从环境变量读取项目级的默认参数
+    ///
+    /// - `TRACING_FN_LEVEL`: 默认日志等级
+    /// - `TRACING_FN_FORCE`: 是否默认强制启用
+    /// - `TRACING_FN_DEFAULT_SKIP_RET`: 是否默认不输出返回值
+    /// - `TRACING_FN_DEFAULT_SKIP_EMPTY_ARGS`: 没有参数时是否默认省略 `#Args: ()`
+    /// - `TRACING_FN_SKIP_TYPES`: 总是跳过的参数类型
+    /// - `TRACING_FN_REDACT`: 跳过的参数的默认输出方式
+    /// - `TRACING_FN_REDACTED_AS`: 跳过的参数的默认占位符
+    /// - `TRACING_FN_BACKEND`: 默认日志后端
+    /// - `TRACING_FN_CRATE`: 引用 tracing 的路径
+    pub(crate) fn from_env() -> syn::Result<Self> {
+        let mut args = Args::default();
+        if let Some(level) = env_var("TRACING_FN_LEVEL") {
+            args.level = Some(parse_level(&level, Span::call_site()).map_err(|err| {
+                syn::Error::new(err.span(), format!("环境变量 TRACING_FN_LEVEL: {err}"))
+            })?);
+        }
+        if let Some(force) = env_var("TRACING_FN_FORCE") {
+            args.force = Some(parse_env_bool("TRACING_FN_FORCE", &force)?);
+        }
+        if let Some(skip_ret) = env_var("TRACING_FN_DEFAULT_SKIP_RET") {
+            args.skip_ret = Some(parse_env_bool("TRACING_FN_DEFAULT_SKIP_RET", &skip_ret)?);
+        }
+        if let Some(skip_empty_args) = env_var("TRACING_FN_DEFAULT_SKIP_EMPTY_ARGS") {
+            args.skip_empty_args = Some(parse_env_bool(
+                "TRACING_FN_DEFAULT_SKIP_EMPTY_ARGS",
+                &skip_empty_args,
+            )?);
+        }
+        if let Some(skip_types) = env_var("TRACING_FN_SKIP_TYPES") {
+            args.skip_types = parse_type_list(&skip_types, Span::call_site()).map_err(|err| {
+                syn::Error::new(err.span(), format!("环境变量 TRACING_FN_SKIP_TYPES: {err}"))
+            })?;
+        }
+        if let Some(redact) = env_var("TRACING_FN_REDACT") {
+            args.redact = Some(parse_redact(&redact, Span::call_site()).map_err(|err| {
+                syn::Error::new(err.span(), format!("环境变量 TRACING_FN_REDACT: {err}"))
+            })?);
+        }
+        if let Some(redacted_as) = env_var("TRACING_FN_REDACTED_AS") {
+            args.redacted_as = Some(redacted_as);
+        }
+        if let Some(backend) = env_var("TRACING_FN_BACKEND") {
+            args.backend = Some(parse_backend(&backend, Span::call_site()).map_err(|err| {
+                syn::Error::new(err.span(), format!("环境变量 TRACING_FN_BACKEND: {err}"))
+            })?);
+        }
+        if let Some(path) = env_var("TRACING_FN_CRATE") {
+            args.tracing_crate = Some(syn::parse_str(&path).map_err(|err| {
+                syn::Error::new(err.span(), format!("环境变量 TRACING_FN_CRATE: {err}"))
+            })?);
+        }
+        Ok(args)
+    }
+
+    fn validate(&self) -> syn::Result<()> {
+        if self.force == Some(true) && self.cfg.is_some() {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "`force` 与 `cfg` 不能同时使用",
+            ));
+        }
+        Ok(())
+    }
+
+    /// `location` 参数, 默认只在入口事件中输出
+    pub(crate) fn location(&self) -> Location {
+        self.location.unwrap_or(Location::Entry)
+    }
+
+    /// 所在 `#[tracing_impl]` 的 impl 块的类型名
+    pub(crate) fn impl_type(&self) -> Option<&str> {
+        self.impl_block.and(self.scope.last()).map(String::as_str)
+    }
+
+    /// `self_type` 参数指定或检测到的类型名
+    pub(crate) fn self_type_name(&self) -> Option<&str> {
+        match &self.self_type {
+            Some(SelfType::Named(name)) => Some(name),
+            Some(SelfType::Detect) => self.impl_type(),
+            Some(SelfType::Off) | None => None,
+        }
+    }
+
+    /// 启用时返回 `span` 参数
+    pub(crate) fn enabled_span(&self) -> Option<&FnSpan> {
+        self.span.as_ref().filter(|span| span.enabled)
+    }
+
+    /// 用内层属性的参数覆盖当前参数
+    ///
+    /// `skip`、`skip_types`、`summarize`、`json`、`valuable` 与 `log` 为追加关系, `format` 与 `fields` 按名称追加或覆盖, 其余参数由内层覆盖外层。`force` 与 `cfg` 共同决定启用条件,
+    /// 内层只要设置了其中之一, 就会整体替换外层的启用条件。
+    pub(crate) fn merge(&self, inner: &Args) -> Args {
+        let mut merged = self.clone();
+        if inner.level.is_some() {
+            merged.level.clone_from(&inner.level);
+        }
+        if inner.level_fn.is_some() {
+            merged.level_fn.clone_from(&inner.level_fn);
+        }
+        for name in &inner.skip {
+            if !merged.skip.contains(name) {
+                merged.skip.push(name.clone());
+            }
+        }
+        for ty in &inner.skip_types {
+            push_unique(&mut merged.skip_types, ty.clone());
+        }
+        for name in &inner.summarize {
+            push_unique(&mut merged.summarize, name.clone());
+        }
+        for name in &inner.json {
+            push_unique(&mut merged.json, name.clone());
+        }
+        for name in &inner.valuable {
+            push_unique(&mut merged.valuable, name.clone());
+        }
+        for name in &inner.log {
+            push_unique(&mut merged.log, name.clone());
+        }
+        if inner.strict_args.is_some() {
+            merged.strict_args = inner.strict_args;
+        }
+        if inner.redact.is_some() {
+            merged.redact = inner.redact;
+        }
+        if inner.runtime_skip.is_some() {
+            merged.runtime_skip = inner.runtime_skip;
+        }
+        if inner.redacted_as.is_some() {
+            merged.redacted_as.clone_from(&inner.redacted_as);
+        }
+        for (name, format) in &inner.format {
+            match merged.format.iter_mut().find(|(n, _)| n == name) {
+                Some(entry) => entry.1 = format.clone(),
+                None => merged.format.push((name.clone(), format.clone())),
+            }
+        }
+        if inner.force.is_some() || inner.cfg.is_some() {
+            merged.force = inner.force;
+            merged.cfg.clone_from(&inner.cfg);
+        }
+        if inner.condition.is_some() {
+            merged.condition.clone_from(&inner.condition);
+        }
+        if inner.first.is_some() {
+            merged.first = inner.first;
+        }
+        if inner.dedup.is_some() {
+            merged.dedup = inner.dedup;
+        }
+        if inner.once_per.is_some() {
+            merged.once_per.clone_from(&inner.once_per);
+        }
+        if inner.skip_empty_args.is_some() {
+            merged.skip_empty_args = inner.skip_empty_args;
+        }
+        if inner.skip_ret.is_some() {
+            merged.skip_ret = inner.skip_ret;
+        }
+        if inner.ret_with.is_some() {
+            merged.ret_with.clone_from(&inner.ret_with);
+        }
+        if inner.pressure.is_some() {
+            merged.pressure = inner.pressure;
+        }
+        if inner.priority.is_some() {
+            merged.priority = inner.priority;
+        }
+        if inner.max_len.is_some() {
+            merged.max_len = inner.max_len;
+        }
+        if inner.doc.is_some() {
+            merged.doc = inner.doc;
+        }
+        if inner.err.is_some() {
+            merged.err = inner.err;
+        }
+        if inner.err_level.is_some() {
+            merged.err_level.clone_from(&inner.err_level);
+        }
+        if inner.err_code.is_some() {
+            merged.err_code.clone_from(&inner.err_code);
+        }
+        if inner.fanout.is_some() {
+            merged.fanout = inner.fanout;
+        }
+        if inner.abort_safe.is_some() {
+            merged.abort_safe = inner.abort_safe;
+        }
+        if inner.tenant.is_some() {
+            merged.tenant = inner.tenant;
+        }
+        if inner.compress_prefix.is_some() {
+            merged.compress_prefix.clone_from(&inner.compress_prefix);
+        }
+        if inner.slow_poll.is_some() {
+            merged.slow_poll = inner.slow_poll;
+        }
+        if inner.depth.is_some() {
+            merged.depth = inner.depth;
+        }
+        if inner.paired.is_some() {
+            merged.paired = inner.paired;
+        }
+        if inner.process_exit.is_some() {
+            merged.process_exit = inner.process_exit;
+        }
+        if inner.entry_on_slow.is_some() {
+            merged.entry_on_slow = inner.entry_on_slow;
+        }
+        if inner.qualified.is_some() {
+            merged.qualified = inner.qualified;
+        }
+        if inner.generics.is_some() {
+            merged.generics = inner.generics;
+        }
+        if inner.self_type.is_some() {
+            merged.self_type.clone_from(&inner.self_type);
+        }
+        if inner.caller.is_some() {
+            merged.caller = inner.caller;
+        }
+        if inner.caller_fn.is_some() {
+            merged.caller_fn = inner.caller_fn;
+        }
+        if inner.err_only.is_some() {
+            merged.err_only = inner.err_only;
+        }
+        if inner.entry_only.is_some() {
+            merged.entry_only = inner.entry_only;
+        }
+        if inner.exit_only.is_some() {
+            merged.exit_only = inner.exit_only;
+        }
+        if inner.thread.is_some() {
+            merged.thread = inner.thread;
+        }
+        if inner.span_id.is_some() {
+            merged.span_id = inner.span_id;
+        }
+        if inner.span.is_some() {
+            merged.span.clone_from(&inner.span);
+        }
+        if inner.child_span.is_some() {
+            merged.child_span.clone_from(&inner.child_span);
+        }
+        if inner.dispatch.is_some() {
+            merged.dispatch.clone_from(&inner.dispatch);
+        }
+        if inner.backend.is_some() {
+            merged.backend = inner.backend;
+        }
+        if inner.audit.is_some() {
+            merged.audit = inner.audit;
+        }
+        if inner.with.is_some() {
+            merged.with.clone_from(&inner.with);
+        }
+        if inner.clock.is_some() {
+            merged.clock.clone_from(&inner.clock);
+        }
+        if inner.measure.is_some() {
+            merged.measure = inner.measure;
+        }
+        if inner.location.is_some() {
+            merged.location = inner.location;
+        }
+        if inner.duration_unit.is_some() {
+            merged.duration_unit = inner.duration_unit;
+        }
+        if inner.allocs.is_some() {
+            merged.allocs = inner.allocs;
+        }
+        if inner.record.is_some() {
+            merged.record = inner.record;
+        }
+        if inner.replay.is_some() {
+            merged.replay = inner.replay;
+        }
+        if inner.binary.is_some() {
+            merged.binary = inner.binary;
+        }
+        if inner.test_subscriber.is_some() {
+            merged.test_subscriber = inner.test_subscriber;
+        }
+        if inner.tracing_crate.is_some() {
+            merged.tracing_crate.clone_from(&inner.tracing_crate);
+        }
+        if inner.poll.is_some() {
+            merged.poll.clone_from(&inner.poll);
+        }
+        if inner.batch.is_some() {
+            merged.batch.clone_from(&inner.batch);
+        }
+        if inner.verbosity_tiers.is_some() {
+            merged.verbosity_tiers.clone_from(&inner.verbosity_tiers);
+        }
+        if !inner.scope.is_empty() {
+            merged.scope.clone_from(&inner.scope);
+        }
+        if inner.impl_block.is_some() {
+            merged.impl_block = inner.impl_block;
+        }
+        for (name, value) in &inner.fields {
+            match merged.fields.iter_mut().find(|(n, _)| n == name) {
+                Some(field) => field.1 = value.clone(),
+                None => merged.fields.push((name.clone(), value.clone())),
+            }
+        }
+        merged
+    }
+}
+
+/// 判断属性是否为指定名称的本库属性, 兼容 `#[tracing_fn]` 与 `#[tracing_fn::tracing_fn]` 写法
+pub(crate) fn is_attr(attr: &Attribute, name: &str) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == name)
+}
+
+/// 从属性列表中取出指定名称的本库属性并解析其参数
+pub(crate) fn take_attr(attrs: &mut Vec<Attribute>, name: &str) -> syn::Result<Option<Args>> {
+    let mut found = None;
+    let mut result = Ok(());
+    attrs.retain(|attr| {
+        if !is_attr(attr, name) {
+            return true;
+        }
+        match Args::from_attr(attr) {
+            Ok(args) => {
+                found = Some(
+                    found
+                        .as_ref()
+                        .map_or(args.clone(), |prev: &Args| prev.merge(&args)),
+                )
+            }
+            Err(err) => result = Err(err),
+        }
+        false
+    });
+    result.map(|()| found)
+}
@@ -0,0 +1,151 @@
+//! 代码块的 tracing
+//!
+//! `trace_block!(level = "debug", "loading config", { ... })` 在代码块执行前输出入口事件，
+//! 执行后输出代码块的值与耗时，用于只关心大函数中某一段代码的情况。代码块原地展开，
+//! 其中的 `?`、`return`、`break` 与 `.await` 的含义不变，经由它们离开代码块或 panic 时退出事件的值输出为 `<unknown>` 或 `<panicked>`。
+
+use proc_macro2::TokenStream;
+use quote::{ToTokens, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Block, LitStr, MetaNameValue, Token};
+
+use crate::args::{Args, env_dependencies};
+use crate::call::{gate, parse_options};
+use crate::expand::{
+    EventKind, StaticLevel, autoref_imports, debug_value, enabled, event, static_level, truncated,
+};
+
+/// `trace_block!` 的输入: 逗号分隔的参数、代码块的名称与代码块
+pub(crate) struct TracedBlock {
+    options: Punctuated<MetaNameValue, Token![,]>,
+    name: LitStr,
+    block: Block,
+}
+
+impl Parse for TracedBlock {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let options = parse_options(input)?;
+        let name = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let block = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        if !input.is_empty() {
+            return Err(input.error("代码块之后不能有其他内容"));
+        }
+        Ok(Self {
+            options,
+            name,
+            block,
+        })
+    }
+}
+
+/// 展开 `trace_block!`
+pub(crate) fn expand(input: TracedBlock) -> syn::Result<TokenStream> {
+    let TracedBlock {
+        options,
+        name,
+        block,
+    } = input;
+    let args = Args::parse_call_options(options.to_token_stream())?;
+    // 环境变量提供项目级默认值, 宏参数优先
+    let args = Args::from_env()?.merge(&args);
+
+    let level = args.level.as_deref().unwrap_or("trace");
+    // 启用 disabled feature 或该等级被 tracing 的 max_level_* feature 移除时原样展开代码块
+    let static_level = static_level(&args, level);
+    if cfg!(feature = "disabled") || static_level == StaticLevel::Disabled {
+        return Ok(block.to_token_stream());
+    }
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &name,
+            "`trace_block!` 需要启用 tracing-fn 的 std feature",
+        ));
+    }
+
+    let gate = gate(&args, static_level);
+    let enabled = enabled(&args, level);
+    let entry = event(
+        &args,
+        EventKind::Entry,
+        level,
+        quote!(),
+        quote!(">>> [{}] --- {}:{}", #name, ::core::file!(), ::core::line!()),
+    );
+    let (exit, exit_unknown) = if args.skip_ret == Some(true) {
+        let exit = event(
+            &args,
+            EventKind::Exit,
+            level,
+            quote!(),
+            quote!("<<< [{}] duration: {:?}", #name, __tracing_fn_duration),
+        );
+        (exit.clone(), exit)
+    } else {
+        let ret = truncated(&args, debug_value(quote!(__tracing_fn_ret)));
+        (
+            event(
+                &args,
+                EventKind::Exit,
+                level,
+                quote!(),
+                quote!("<<< [{}] #Ret: {:?}, duration: {:?}", #name, #ret, __tracing_fn_duration),
+            ),
+            event(
+                &args,
+                EventKind::Exit,
+                level,
+                quote!(),
+                quote!("<<< [{}] #Ret: {}, duration: {:?}", #name, __tracing_fn_ret, __tracing_fn_duration),
+            ),
+        )
+    };
+
+    let imports = autoref_imports();
+    let env_dependencies = env_dependencies();
+    Ok(quote! {
+        {
+            #env_dependencies
+            #imports
+            let __tracing_fn_enabled = #gate && #enabled;
+            if __tracing_fn_enabled {
+                #entry
+            }
+
+            // 经由 `?`、`return`、`break` 离开代码块或 panic 时由守卫输出退出事件
+            struct __TracingFnBlockGuard(
+                ::std::option::Option<::tracing_fn::runtime::Instant>,
+            );
+
+            impl ::std::ops::Drop for __TracingFnBlockGuard {
+                fn drop(&mut self) {
+                    if let ::std::option::Option::Some(start) = self.0.take() {
+                        #[allow(unused_variables)]
+                        let __tracing_fn_ret = if ::std::thread::panicking() {
+                            "<panicked>"
+                        } else {
+                            "<unknown>"
+                        };
+                        let __tracing_fn_duration = start.elapsed();
+                        #exit_unknown
+                    }
+                }
+            }
+
+            let mut __tracing_fn_guard = __TracingFnBlockGuard(
+                __tracing_fn_enabled.then(::tracing_fn::runtime::Instant::now),
+            );
+            #[allow(unreachable_code)]
+            let __tracing_fn_ret = #block;
+            #[allow(unreachable_code)]
+            if let ::std::option::Option::Some(start) = __tracing_fn_guard.0.take() {
+                let __tracing_fn_duration = start.elapsed();
+                #exit
+            }
+            #[allow(unreachable_code)]
+            __tracing_fn_ret
+        }
+    })
+}
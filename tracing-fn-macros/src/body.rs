@@ -0,0 +1,357 @@
+//! 函数体改写
+//!
+//! 函数体保持原样展开在函数中，仅把 `return expr` 改写为先记录返回值再返回，
+//! 把 `expr?` 改写为先观察 `expr` 再执行 `?`，从而保证 `return`、`?`、`.await` 以及借用的语义与原函数完全一致。
+//! 函数体中有可能提前返回的宏调用 (例如 `anyhow::bail!`) 时，函数体改为在 `FnOnce` 闭包或 async 块中执行。
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::visit::{self, Visit};
+use syn::visit_mut::{self, VisitMut};
+use syn::{Block, Expr, Item, Macro, Stmt, Type, parse_quote};
+
+/// 改写函数体中属于该函数本身的 `return` 与 `?`
+///
+/// 闭包、async 块、const 块、try 块以及嵌套的函数等条目有各自的返回目标，不会被改写。
+/// 宏调用中的 token 无法解析，其中的 `return` 与 `?` (包括 `anyhow::bail!` 等宏展开出的 `return`) 无法改写，
+/// 函数体中有可能提前返回的宏调用时改为隔离执行函数体，参见 [`Body::eval`]。
+///
+/// 设置了 `try_record` 时 (仅用于返回 `Result` 的函数)，`expr?` 展开为与其等价的 `match`，
+/// 提前返回的错误先经 `From` 转换为函数的错误类型，再执行 `try_record` 后返回。
+///
+/// `process_exit` 为 true 时，路径以 `process::exit` 或 `process::abort` 结尾的函数调用改写为先由守卫输出退出事件再调用。
+pub(crate) fn rewrite_body(
+    block: &Block,
+    ret_ty: Option<&Type>,
+    record: &TokenStream,
+    try_record: Option<&TokenStream>,
+    process_exit: bool,
+) -> Body {
+    let isolated = macro_may_return(block);
+    let mut block = block.clone();
+    // 隔离执行时返回值在函数体结束处统一记录, `return` 与 `?` 不再需要记录
+    let empty = TokenStream::new();
+    Rewriter {
+        ret_ty,
+        record: if isolated { &empty } else { record },
+        try_record: try_record.filter(|_| !isolated),
+        process_exit,
+    }
+    .visit_block_mut(&mut block);
+    Body { block, isolated }
+}
+
+/// 改写后的函数体
+pub(crate) struct Body {
+    pub(crate) block: Block,
+    /// 函数体中有可能提前返回的宏调用
+    isolated: bool,
+}
+
+impl Body {
+    /// 求值函数体并以 `record` 记录返回值
+    ///
+    /// 函数体中有可能提前返回的宏调用时，函数体在 `FnOnce` 闭包 (async fn 为 async 块) 中执行，
+    /// 宏展开出的 `return` 只能返回到闭包，返回值因此总能被记录。闭包以 `FnOnce` 调用，
+    /// 函数体仍可以移动参数或返回借用自参数 (包括 `&mut self`) 的引用。
+    pub(crate) fn eval(
+        &self,
+        ret_ty: Option<&Type>,
+        is_async: bool,
+        record: &TokenStream,
+    ) -> TokenStream {
+        let block = &self.block;
+        let value = match (self.isolated, is_async) {
+            (false, _) => quote!(#block),
+            // async 块不能标注返回值类型, 其中 `?` 的错误类型由 async_body 的约束推断
+            (true, true) => match ret_ty {
+                Some(ty) => quote!(::tracing_fn::runtime::async_body::<#ty, _>(async #block).await),
+                None => quote!(async #block.await),
+            },
+            (true, false) => {
+                let ty = ret_ty.map(|ty| quote!(-> #ty));
+                quote!(::tracing_fn::runtime::call_once(|| #ty #block))
+            }
+        };
+        let ty = ret_ty.map(|ty| quote!(: #ty));
+        quote! {
+            let __tracing_fn_ret #ty = #value;
+            #record
+            __tracing_fn_ret
+        }
+    }
+
+    /// 永不返回的函数的函数体, 语句直接展开以免产生多余的花括号
+    pub(crate) fn never(&self) -> TokenStream {
+        let stmts = &self.block.stmts;
+        quote!(#(#stmts)*)
+    }
+}
+
+/// 已知不会使调用处提前返回的宏, 参数中包含 `return` 或 `?` 时除外
+const TRANSPARENT_MACROS: &[&str] = &[
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "batch_tick",
+    "cfg",
+    "column",
+    "concat",
+    "dbg",
+    "debug",
+    "debug_assert",
+    "debug_assert_eq",
+    "debug_assert_ne",
+    "env",
+    "eprint",
+    "eprintln",
+    "error",
+    "event",
+    "file",
+    "format",
+    "format_args",
+    "include",
+    "include_bytes",
+    "include_str",
+    "info",
+    "line",
+    "matches",
+    "module_path",
+    "option_env",
+    "panic",
+    "print",
+    "println",
+    "stringify",
+    "todo",
+    "trace",
+    "unimplemented",
+    "unreachable",
+    "vec",
+    "warn",
+    "write",
+    "writeln",
+];
+
+/// 函数体中是否有可能使函数提前返回的宏调用
+///
+/// 除 [`TRANSPARENT_MACROS`] 外的宏调用 (例如 `anyhow::bail!`、`ensure!`) 均视为可能提前返回。
+/// 与改写 `return` 相同，闭包、async 块等有各自返回目标的表达式以及嵌套的条目中的宏调用不计在内。
+fn macro_may_return(block: &Block) -> bool {
+    let mut finder = MacroReturns { found: false };
+    finder.visit_block(block);
+    finder.found
+}
+
+struct MacroReturns {
+    found: bool,
+}
+
+impl<'ast> Visit<'ast> for MacroReturns {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        if matches!(
+            expr,
+            Expr::Closure(_) | Expr::Async(_) | Expr::Const(_) | Expr::TryBlock(_)
+        ) {
+            return;
+        }
+        visit::visit_expr(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        let transparent = mac
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| TRANSPARENT_MACROS.iter().any(|name| segment.ident == name));
+        if !transparent || may_return(mac.tokens.clone()) {
+            self.found = true;
+        }
+    }
+
+    fn visit_item(&mut self, _item: &'ast Item) {}
+}
+
+/// 宏参数中是否包含 `return` 或 `?`
+fn may_return(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == "return",
+        TokenTree::Punct(punct) => punct.as_char() == '?',
+        TokenTree::Group(group) => may_return(group.stream()),
+        TokenTree::Literal(_) => false,
+    })
+}
+
+struct Rewriter<'a> {
+    ret_ty: Option<&'a Type>,
+    record: &'a TokenStream,
+    try_record: Option<&'a TokenStream>,
+    process_exit: bool,
+}
+
+impl VisitMut for Rewriter<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if matches!(
+            expr,
+            Expr::Closure(_) | Expr::Async(_) | Expr::Const(_) | Expr::TryBlock(_)
+        ) {
+            return;
+        }
+        visit_mut::visit_expr_mut(self, expr);
+
+        match expr {
+            Expr::Return(ret) => {
+                let value = ret
+                    .expr
+                    .take()
+                    .map_or_else(|| quote!(()), |value| quote!(#value));
+                let ty = self.ret_ty.map(|ty| quote!(: #ty));
+                let record = self.record;
+                ret.expr = Some(parse_quote! {
+                    {
+                        let __tracing_fn_ret #ty = #value;
+                        #record
+                        __tracing_fn_ret
+                    }
+                });
+            }
+            Expr::Try(try_expr) => match self.try_record {
+                Some(record) => {
+                    let inner = &try_expr.expr;
+                    let ty = self.ret_ty.map(|ty| quote!(: #ty));
+                    *expr = parse_quote! {
+                        match #inner {
+                            ::std::result::Result::Ok(__tracing_fn_value) => __tracing_fn_value,
+                            ::std::result::Result::Err(__tracing_fn_err) => {
+                                let __tracing_fn_ret #ty = ::std::result::Result::Err(
+                                    ::std::convert::From::from(__tracing_fn_err),
+                                );
+                                #record
+                                return __tracing_fn_ret;
+                            }
+                        }
+                    };
+                }
+                None => {
+                    let inner = &try_expr.expr;
+                    // 导致提前返回的部分在调用处按具体类型选择输出方式, 未实现 `Debug` 时只输出类型名
+                    try_expr.expr = parse_quote! {
+                        __tracing_fn_guard.observe(#inner, |value| {
+                            (&&::tracing_fn::runtime::DebugWrap(value)).__tracing_fn_residual()
+                        })
+                    };
+                }
+            },
+            Expr::Call(call) if self.process_exit => match process_call(&call.func) {
+                Some(ProcessCall::Exit) if call.args.len() == 1 => {
+                    let func = &call.func;
+                    let code = &call.args[0];
+                    *expr = parse_quote! {
+                        {
+                            let __tracing_fn_code: i32 = #code;
+                            __tracing_fn_guard.process_exit(
+                                ::std::option::Option::Some(__tracing_fn_code),
+                            );
+                            #func(__tracing_fn_code)
+                        }
+                    };
+                }
+                Some(ProcessCall::Abort) if call.args.is_empty() => {
+                    let func = &call.func;
+                    *expr = parse_quote! {
+                        {
+                            __tracing_fn_guard.process_exit(::std::option::Option::None);
+                            #func()
+                        }
+                    };
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn visit_item_mut(&mut self, _item: &mut Item) {}
+}
+
+enum ProcessCall {
+    Exit,
+    Abort,
+}
+
+/// 按路径识别 `std::process::exit` 与 `std::process::abort`, 只导入函数名的调用无法识别
+fn process_call(func: &Expr) -> Option<ProcessCall> {
+    let Expr::Path(path) = func else {
+        return None;
+    };
+    let mut segments = path.path.segments.iter().rev();
+    let last = segments.next()?;
+    if segments.next()?.ident != "process" {
+        return None;
+    }
+    if last.ident == "exit" {
+        Some(ProcessCall::Exit)
+    } else if last.ident == "abort" {
+        Some(ProcessCall::Abort)
+    } else {
+        None
+    }
+}
+
+/// 把函数体中的 `batch_tick!()` 与 `batch_tick!(n)` 替换为 `tick`
+///
+/// 替换后仍经由原路径的 `batch_tick!(@tracing_fn { ... })` 展开, 宏的导入因此不会被视为未使用。
+/// `tick` 中以 `__tracing_fn_count` 引用本次记录的条目数。与 `return` 不同，闭包与 async 块中的调用同样会被替换，
+/// 只有嵌套的函数等条目以及其他宏中的调用不会被替换，此时由 `tracing_fn::batch_tick!` 本身报错。
+pub(crate) fn rewrite_batch_ticks(block: &mut Block, tick: &TokenStream) {
+    BatchTicks { tick }.visit_block_mut(block);
+}
+
+struct BatchTicks<'a> {
+    tick: &'a TokenStream,
+}
+
+impl BatchTicks<'_> {
+    fn replace(&self, mac: &mut Macro) {
+        let is_tick = mac
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "batch_tick");
+        if !is_tick {
+            return;
+        }
+        let count = if mac.tokens.is_empty() {
+            quote!(1)
+        } else {
+            mac.tokens.clone()
+        };
+        let tick = self.tick;
+        mac.tokens = quote! {
+            @tracing_fn {
+                let __tracing_fn_count =
+                    ::std::convert::TryFrom::try_from(#count).unwrap_or(0u64);
+                #tick
+            }
+        };
+    }
+}
+
+impl VisitMut for BatchTicks<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Macro(mac) = expr {
+            self.replace(&mut mac.mac);
+            return;
+        }
+        visit_mut::visit_expr_mut(self, expr);
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        if let Stmt::Macro(mac) = stmt {
+            self.replace(&mut mac.mac);
+            return;
+        }
+        visit_mut::visit_stmt_mut(self, stmt);
+    }
+
+    fn visit_item_mut(&mut self, _item: &mut Item) {}
+}
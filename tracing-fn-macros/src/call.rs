@@ -0,0 +1,205 @@
+//! 调用处的 tracing
+//!
+//! `traced_extern!(level = "debug", some_lib::parse(input, flags))` 先求值所有参数并输出入口事件，
+//! 再以求值后的参数调用函数并输出返回值与耗时，用于无法添加 `#[tracing_fn]` 的第三方函数。
+
+use proc_macro2::TokenStream;
+use quote::{ToTokens, format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Expr, Ident, MetaNameValue, Token};
+
+use crate::args::{Args, env_dependencies};
+use crate::expand::{
+    EventKind, StaticLevel, autoref_imports, debug_value, enabled, event, static_level, truncated,
+};
+
+/// `traced_extern!` 的输入: 逗号分隔的参数与一个函数调用或方法调用表达式
+pub(crate) struct TracedCall {
+    options: Punctuated<MetaNameValue, Token![,]>,
+    call: Expr,
+}
+
+impl Parse for TracedCall {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // 调用表达式不会以 `ident =` 开头
+        let options = parse_options(input)?;
+        let call = input.parse()?;
+        if !input.is_empty() {
+            return Err(input.error("调用表达式之后不能有其他内容"));
+        }
+        Ok(Self { options, call })
+    }
+}
+
+/// 解析宏开头逗号分隔的 `name = value` 形式的参数, `crate` 为关键字需要单独判断
+pub(crate) fn parse_options(
+    input: ParseStream,
+) -> syn::Result<Punctuated<MetaNameValue, Token![,]>> {
+    let mut options = Punctuated::new();
+    while (input.peek(Ident) || input.peek(Token![crate]))
+        && input.peek2(Token![=])
+        && !input.peek2(Token![==])
+    {
+        options.push_value(input.parse()?);
+        options.push_punct(input.parse()?);
+    }
+    Ok(options)
+}
+
+/// 展开 `traced_extern!`
+pub(crate) fn expand(input: TracedCall) -> syn::Result<TokenStream> {
+    let TracedCall { options, call } = input;
+    let args = Args::parse_call_options(options.to_token_stream())?;
+    // 环境变量提供项目级默认值, 宏参数优先
+    let args = Args::from_env()?.merge(&args);
+
+    // 参数按原顺序求值并绑定到局部变量, 调用时使用绑定后的值
+    let bindings = |count: usize| {
+        (0..count)
+            .map(|index| format_ident!("__tracing_fn_arg{}", index))
+            .collect::<Vec<_>>()
+    };
+    let (name, inputs, bindings, invoke) = match &call {
+        Expr::Call(call) => {
+            let func = &call.func;
+            let bindings = bindings(call.args.len());
+            let invoke = quote!(#func(#(#bindings),*));
+            (compact(func), &call.args, bindings, invoke)
+        }
+        Expr::MethodCall(call) => {
+            let receiver = &call.receiver;
+            let method = &call.method;
+            let turbofish = &call.turbofish;
+            let bindings = bindings(call.args.len());
+            let invoke = quote!(#receiver.#method #turbofish(#(#bindings),*));
+            let name = format!("{}.{method}", compact(receiver));
+            (name, &call.args, bindings, invoke)
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &call,
+                "`traced_extern!` 的最后一个参数应为函数调用或方法调用",
+            ));
+        }
+    };
+    let inputs = inputs.iter().collect::<Vec<_>>();
+
+    let level = args.level.as_deref().unwrap_or("trace");
+    // 启用 disabled feature 或该等级被 tracing 的 max_level_* feature 移除时原样调用
+    let static_level = static_level(&args, level);
+    if cfg!(feature = "disabled") || static_level == StaticLevel::Disabled {
+        return Ok(call.to_token_stream());
+    }
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &call,
+            "`traced_extern!` 需要启用 tracing-fn 的 std feature",
+        ));
+    }
+
+    let gate = gate(&args, static_level);
+    let enabled = enabled(&args, level);
+
+    let args_format = inputs
+        .iter()
+        .map(|input| {
+            format!(
+                "{}={{:?}}",
+                compact(input).replace('{', "{{").replace('}', "}}")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let arg_values = bindings
+        .iter()
+        .map(|binding| truncated(&args, debug_value(quote!(#binding))));
+    let entry_format = format!(">>> [{{}}] #Args: {args_format} --- {{}}:{{}}");
+    let entry = event(
+        &args,
+        EventKind::Entry,
+        level,
+        quote!(),
+        quote!(#entry_format, #name, #(#arg_values,)* ::core::file!(), ::core::line!()),
+    );
+
+    let exit = if args.skip_ret == Some(true) {
+        event(
+            &args,
+            EventKind::Exit,
+            level,
+            quote!(),
+            quote!("<<< [{}] duration: {:?}", #name, __tracing_fn_duration),
+        )
+    } else {
+        let ret = truncated(&args, debug_value(quote!(__tracing_fn_ret)));
+        event(
+            &args,
+            EventKind::Exit,
+            level,
+            quote!(),
+            quote!("<<< [{}] #Ret: {:?}, duration: {:?}", #name, #ret, __tracing_fn_duration),
+        )
+    };
+
+    let imports = autoref_imports();
+    let env_dependencies = env_dependencies();
+    Ok(quote! {
+        match (#(#inputs,)*) {
+            (#(#bindings,)*) => {
+                #env_dependencies
+                #imports
+                let __tracing_fn_enabled = #gate && #enabled;
+                if __tracing_fn_enabled {
+                    #entry
+                }
+                let __tracing_fn_start = __tracing_fn_enabled.then(::std::time::Instant::now);
+                let __tracing_fn_ret = #invoke;
+                if let ::std::option::Option::Some(__tracing_fn_start) = __tracing_fn_start {
+                    let __tracing_fn_duration = __tracing_fn_start.elapsed();
+                    #exit
+                }
+                __tracing_fn_ret
+            }
+        }
+    })
+}
+
+/// 调用处的启用条件, 与 `#[tracing_fn]` 相同默认只在 debug 模式下启用
+pub(crate) fn gate(args: &Args, static_level: StaticLevel) -> TokenStream {
+    match (args.force, &args.cfg) {
+        _ if static_level == StaticLevel::DebugOnly => match &args.cfg {
+            Some(cfg) => quote!(::core::cfg!(all(#cfg, debug_assertions))),
+            None => quote!(::core::cfg!(debug_assertions)),
+        },
+        (Some(true), _) => quote!(true),
+        (_, Some(cfg)) => quote!(::core::cfg!(#cfg)),
+        _ => quote!(::core::cfg!(debug_assertions)),
+    }
+}
+
+/// 表达式的源码, 去掉 token 之间多余的空格, 例如 `some_lib :: parse` 输出为 `some_lib::parse`
+///
+/// 只保留两个标识符或字面量之间与逗号之后的空格, 字符串字面量中的内容不变。
+pub(crate) fn compact(tokens: &impl ToTokens) -> String {
+    let text = tokens.to_token_stream().to_string();
+    let chars = text.chars().collect::<Vec<_>>();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '"' || c == '\'';
+    let mut compact = String::with_capacity(text.len());
+    let mut in_string = false;
+    for (index, &c) in chars.iter().enumerate() {
+        if c == '"' && (index == 0 || chars[index - 1] != '\\') {
+            in_string = !in_string;
+        }
+        let keep = in_string
+            || c != ' '
+            || (index > 0 && chars[index - 1] == ',')
+            || (index > 0
+                && is_word(chars[index - 1])
+                && chars.get(index + 1).copied().is_some_and(is_word));
+        if keep {
+            compact.push(c);
+        }
+    }
+    compact
+}
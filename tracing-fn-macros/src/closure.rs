@@ -0,0 +1,187 @@
+//! 闭包的 tracing
+//!
+//! `tracing_closure!(level = "info", |x: i32| x * 2)` 展开为参数相同的闭包，每次调用时输出参数，
+//! 调用结束后输出返回值与耗时，用于迭代器适配器与回调等无法添加 `#[tracing_fn]` 的地方。
+
+use proc_macro2::TokenStream;
+use quote::{ToTokens, format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{ExprClosure, LitStr, MetaNameValue, Pat, ReturnType, Token};
+
+use crate::args::{Args, env_dependencies};
+use crate::call::{compact, gate, parse_options};
+use crate::expand::{
+    EventKind, StaticLevel, autoref_imports, debug_value, enabled, event, static_level, truncated,
+};
+
+/// `tracing_closure!` 的输入: 逗号分隔的参数、可选的名称与闭包
+pub(crate) struct TracedClosure {
+    options: Punctuated<MetaNameValue, Token![,]>,
+    name: Option<LitStr>,
+    closure: ExprClosure,
+}
+
+impl Parse for TracedClosure {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let options = parse_options(input)?;
+        let name = if input.peek(LitStr) {
+            let name = input.parse()?;
+            input.parse::<Token![,]>()?;
+            Some(name)
+        } else {
+            None
+        };
+        let closure = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        if !input.is_empty() {
+            return Err(input.error("闭包之后不能有其他内容"));
+        }
+        Ok(Self {
+            options,
+            name,
+            closure,
+        })
+    }
+}
+
+/// 展开 `tracing_closure!`
+pub(crate) fn expand(input: TracedClosure) -> syn::Result<TokenStream> {
+    let TracedClosure {
+        options,
+        name,
+        closure,
+    } = input;
+    let args = Args::parse_call_options(options.to_token_stream())?;
+    // 环境变量提供项目级默认值, 宏参数优先
+    let args = Args::from_env()?.merge(&args);
+
+    let level = args.level.as_deref().unwrap_or("trace");
+    // 启用 disabled feature 或该等级被 tracing 的 max_level_* feature 移除时原样展开闭包
+    let static_level = static_level(&args, level);
+    if cfg!(feature = "disabled") || static_level == StaticLevel::Disabled {
+        return Ok(closure.to_token_stream());
+    }
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &closure,
+            "`tracing_closure!` 需要启用 tracing-fn 的 std feature",
+        ));
+    }
+    if let Some(asyncness) = &closure.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "`tracing_closure!` 不支持 async 闭包",
+        ));
+    }
+    let name = name.map_or_else(|| "closure".to_string(), |name| name.value());
+
+    // 参数绑定到局部变量, 输出后在函数体中按原来的模式解构
+    let mut params = Vec::new();
+    let mut patterns = Vec::new();
+    let mut bindings = Vec::new();
+    for (index, input) in closure.inputs.iter().enumerate() {
+        let binding = format_ident!("__tracing_fn_arg{}", index);
+        match input {
+            Pat::Type(typed) => {
+                let ty = &typed.ty;
+                params.push(quote!(#binding: #ty));
+                patterns.push(typed.pat.as_ref().clone());
+            }
+            pat => {
+                params.push(quote!(#binding));
+                patterns.push(pat.clone());
+            }
+        }
+        bindings.push(binding);
+    }
+
+    let gate = gate(&args, static_level);
+    let enabled = enabled(&args, level);
+
+    // 参数名为模式的源码, 去掉 `mut`
+    let args_format = patterns
+        .iter()
+        .map(|pat| {
+            let pat = match pat {
+                Pat::Ident(ident) if ident.subpat.is_none() => ident.ident.to_string(),
+                pat => compact(pat),
+            };
+            format!("{}={{:?}}", pat.replace('{', "{{").replace('}', "}}"))
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let arg_values = bindings
+        .iter()
+        .map(|binding| truncated(&args, debug_value(quote!(#binding))));
+    let entry_format = if patterns.is_empty() {
+        ">>> [{}] --- {}:{}".to_string()
+    } else {
+        format!(">>> [{{}}] #Args: {args_format} --- {{}}:{{}}")
+    };
+    let entry = event(
+        &args,
+        EventKind::Entry,
+        level,
+        quote!(),
+        quote!(#entry_format, #name, #(#arg_values,)* ::core::file!(), ::core::line!()),
+    );
+
+    let exit = if args.skip_ret == Some(true) {
+        event(
+            &args,
+            EventKind::Exit,
+            level,
+            quote!(),
+            quote!("<<< [{}] duration: {:?}", #name, __tracing_fn_duration),
+        )
+    } else {
+        let ret = truncated(&args, debug_value(quote!(__tracing_fn_ret)));
+        event(
+            &args,
+            EventKind::Exit,
+            level,
+            quote!(),
+            quote!("<<< [{}] #Ret: {:?}, duration: {:?}", #name, #ret, __tracing_fn_duration),
+        )
+    };
+
+    let ExprClosure {
+        attrs,
+        lifetimes,
+        constness,
+        movability,
+        capture,
+        output,
+        body,
+        ..
+    } = &closure;
+    // 函数体放入立即调用的内层闭包, 其中的 `return` 与 `?` 的含义不变
+    let output_ty = match output {
+        ReturnType::Default => quote!(),
+        ReturnType::Type(arrow, ty) => quote!(#arrow #ty),
+    };
+    let imports = autoref_imports();
+    let env_dependencies = env_dependencies();
+    Ok(quote! {
+        #(#attrs)* #lifetimes #constness #movability #capture |#(#params),*| #output_ty {
+            #env_dependencies
+            #imports
+            let __tracing_fn_enabled = #gate && #enabled;
+            if __tracing_fn_enabled {
+                #entry
+            }
+            let __tracing_fn_start = __tracing_fn_enabled.then(::tracing_fn::runtime::Instant::now);
+            #[allow(clippy::redundant_closure_call)]
+            let __tracing_fn_ret = (|| #output_ty {
+                #(let #patterns = #bindings;)*
+                #body
+            })();
+            if let ::std::option::Option::Some(__tracing_fn_start) = __tracing_fn_start {
+                let __tracing_fn_duration = __tracing_fn_start.elapsed();
+                #exit
+            }
+            __tracing_fn_ret
+        }
+    })
+}
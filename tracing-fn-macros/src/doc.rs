@@ -0,0 +1,201 @@
+//! 文档注入
+//!
+//! 在添加了 tracing 的函数的文档末尾追加 `# Instrumentation` 一节，说明日志等级、跳过的参数、阈值与启用条件，
+//! 使 rustdoc 与运行时的日志行为保持一致。参数在合并环境变量之后生成，描述的是最终生效的配置。
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use syn::{Attribute, Signature};
+
+use crate::args::{Args, Backend, ErrMode, Location, Priority, Redact};
+use crate::expand::{DYNAMIC_LEVEL, StaticLevel, err_mode, static_level};
+
+/// 生成追加到函数文档的 `#[doc]` 属性, `doc = false` 或函数没有文档时为空
+///
+/// 没有文档的函数不追加, 以免 `missing_docs` 把只有生成内容的函数视为已有文档。
+pub(crate) fn instrumentation_doc(
+    args: &Args,
+    attrs: &[&Attribute],
+    sig: &Signature,
+) -> TokenStream {
+    let documented = attrs.iter().any(|attr| attr.path().is_ident("doc"));
+    if args.doc == Some(false) || !documented {
+        return TokenStream::new();
+    }
+
+    let mut lines = vec![
+        String::new(),
+        "# Instrumentation".to_string(),
+        String::new(),
+        "该函数由 `#[tracing_fn]` 添加了 tracing：".to_string(),
+        String::new(),
+    ];
+    let level = args.level.as_deref().unwrap_or("trace");
+    match &args.level_fn {
+        Some(level_fn) => lines.push(format!("- 日志等级: 调用时由 `{}` 决定", quote!(#level_fn))),
+        None => lines.push(format!("- 日志等级: `{level}`")),
+    }
+    lines.push(match (&args.force, &args.cfg) {
+        (Some(true), _) => "- 启用条件: 总是启用 (`force = true`)".to_string(),
+        (_, Some(cfg)) => format!("- 启用条件: `cfg({})`", quote!(#cfg)),
+        _ => "- 启用条件: debug 模式 (`debug_assertions`)，Release 模式下不输出".to_string(),
+    });
+    if let Some(condition) = &args.condition {
+        lines.push(format!("- 运行时条件: `{}`", quote!(#condition)));
+    }
+    let static_level = match args.level_fn {
+        Some(_) => static_level(args, DYNAMIC_LEVEL),
+        None => static_level(args, level),
+    };
+    if static_level == StaticLevel::DebugOnly {
+        lines.push(
+            "- tracing 的 `release_max_level_*` feature 移除了该等级，Release 模式下不输出"
+                .to_string(),
+        );
+    }
+    if let Some(backend) = args.backend.filter(|backend| *backend != Backend::Tracing) {
+        lines.push(format!("- 日志后端: `{}`", backend.name()));
+    }
+    if !args.skip.is_empty() {
+        let redact = match args.redact {
+            Some(Redact::Sha256) => "输出为 SHA-256 哈希前缀".to_string(),
+            Some(Redact::Omit) => "不输出".to_string(),
+            Some(Redact::Mask) | None => {
+                format!("输出为 `{}`", args.redacted_as.as_deref().unwrap_or("***"))
+            }
+        };
+        lines.push(format!("- 跳过的参数: {} ({redact})", names(&args.skip)));
+    }
+    if args.runtime_skip == Some(true) {
+        lines.push("- 运行时可以通过 `tracing_fn::redaction::set` 跳过参数".to_string());
+    }
+    if !args.summarize.is_empty() {
+        lines.push(format!(
+            "- 只输出类型与长度的参数: {}",
+            names(&args.summarize)
+        ));
+    }
+    if !args.json.is_empty() {
+        lines.push(format!("- 以 JSON 输出的参数: {}", names(&args.json)));
+    }
+    if !args.valuable.is_empty() {
+        lines.push(format!(
+            "- 以 `valuable` 结构化值输出的参数: {}",
+            names(&args.valuable)
+        ));
+    }
+    if args.skip_ret == Some(true) {
+        lines.push("- 不输出返回值".to_string());
+    }
+    match err_mode(args, sig) {
+        ErrMode::Off => {}
+        ErrMode::Chain => lines.push("- 返回 `Err` 时输出错误及其完整的 `source()` 链".to_string()),
+        ErrMode::Alternate => lines.push("- 返回 `Err` 时以 `{:#}` 输出错误及其上下文".to_string()),
+    }
+    if args.err_only == Some(true) {
+        lines.push(
+            "- 调用成功时不输出，返回 `Err` 或 panic 时输出一条包含参数、错误与耗时的事件"
+                .to_string(),
+        );
+    }
+    if args.entry_only == Some(true) {
+        lines.push("- 只输出入口事件".to_string());
+    }
+    if args.exit_only == Some(true) {
+        lines.push("- 只输出退出事件".to_string());
+    }
+    if args.qualified == Some(true) {
+        lines.push("- 事件中以全限定名标识函数".to_string());
+    }
+    if args.generics == Some(true) {
+        lines.push("- 事件中的函数名附带类型参数".to_string());
+    }
+    if let Some(ty) = args.self_type_name() {
+        lines.push(format!("- 事件中的方法名附带类型名 `{ty}`"));
+    }
+    if args.caller == Some(true) {
+        lines.push("- 输出调用方的源码位置".to_string());
+    }
+    if args.caller_fn == Some(true) {
+        lines.push("- 输出外层被追踪调用的函数名".to_string());
+    }
+    if let Some(err_level) = &args.err_level {
+        lines.push(format!("- 返回 `Err` 时退出事件的日志等级: `{err_level}`"));
+    }
+    if let Some(location) = args
+        .location
+        .filter(|location| *location != Location::Entry)
+    {
+        lines.push(format!("- 消息中的源码位置: `{}`", location.name()));
+    }
+    if let Some(unit) = args.duration_unit {
+        lines.push(format!("- 耗时以 `{}` 为单位输出", unit.name()));
+    }
+    if let Some(max_len) = args.max_len {
+        lines.push(format!("- 每个参数与返回值最多输出 {max_len} 字节"));
+    }
+    if let Some(tiers) = &args.verbosity_tiers {
+        lines.push(format!(
+            "- 全局详细程度不低于 {} 时输出参数，不低于 {} 时输出返回值",
+            tiers.args, tiers.ret
+        ));
+    }
+    if let Some(priority) = args.priority {
+        lines.push(format!("- 优先级: `{}`", priority.name()));
+    }
+    if let Some(shedding) = args.pressure.or(args.priority.map(Priority::shedding)) {
+        lines.push(format!("- 日志管道压力较高时的策略: `{}`", shedding.name()));
+    }
+    if let Some(once_per) = &args.once_per {
+        let key = &once_per.key;
+        lines.push(format!(
+            "- 同一 `{}` 只在首次调用时输出，之后的调用每 {:?} 汇总一次",
+            quote!(#key),
+            once_per.period
+        ));
+    }
+    if let Some(first) = args.first {
+        lines.push(format!("- 只输出前 {first} 次调用"));
+    }
+    if args.dedup == Some(true) {
+        lines.push("- 参数与上一次调用相同时不输出".to_string());
+    }
+    if let Some(threshold) = args.entry_on_slow {
+        lines.push(format!("- 只输出耗时超过 {threshold:?} 的调用"));
+    }
+    if let Some(threshold) = args.slow_poll {
+        lines.push(format!(
+            "- 单次 poll 超过 {threshold:?} 时以 `warn` 等级输出"
+        ));
+    }
+    if args.binary == Some(true) {
+        lines.push("- 入口与退出写入 `tracing_fn::binary` 的二进制记录文件".to_string());
+    }
+    if args.test_subscriber == Some(true) {
+        lines.push("- 执行前初始化输出到测试输出的 subscriber".to_string());
+    }
+    if let Some(batch) = &args.batch {
+        lines.push(format!("- 每处理 {} 个条目输出一次进度", batch.chunk));
+    }
+
+    let lines = lines.iter().map(|line| {
+        if line.is_empty() {
+            String::new()
+        } else {
+            format!(" {line}")
+        }
+    });
+    quote! {
+        #(#[doc = #lines])*
+    }
+}
+
+/// 以 `` `a`, `b` `` 的形式列出参数名
+fn names(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|name| format!("`{name}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,2523 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::visit::{self, Visit};
+use syn::{AttrStyle, Attribute, Block, Ident, Pat, ReturnType, Signature, Type};
+
+use crate::allow;
+use crate::args::{
+    Args, Backend, ErrMode, Location, Measure, Priority, Redact, SelfType, Shedding,
+    env_dependencies,
+};
+use crate::body::{rewrite_batch_ticks, rewrite_body};
+use crate::doc;
+use crate::no_std;
+use crate::poll;
+use crate::replay;
+use crate::report;
+
+/// 为单个函数生成带 tracing 的实现
+///
+/// `vis` 为函数签名之前的所有修饰 (可见性、`default` 等)。
+pub(crate) fn expand_fn(
+    args: &Args,
+    attrs: &[Attribute],
+    vis: TokenStream,
+    sig: &Signature,
+    block: &Block,
+) -> TokenStream {
+    // 函数体内的 `#![...]` 属性需要保留在函数体内
+    let (inner_attrs, outer_attrs): (Vec<_>, Vec<_>) = attrs
+        .iter()
+        .partition(|attr| matches!(attr.style, AttrStyle::Inner(_)));
+    // batch: 保留原函数的展开 (包括不满足 cfg 条件时) 中 `batch_tick!()` 只对条目数求值
+    let plain = args.batch.as_ref().map(|_| {
+        let mut plain = block.clone();
+        rewrite_batch_ticks(&mut plain, &quote!());
+        plain
+    });
+    let stmts = &plain.as_ref().unwrap_or(block).stmts;
+    // 环境变量决定了是否以及如何添加 tracing, 保留原函数时同样需要记录依赖
+    let env_dependencies = env_dependencies();
+    let original = quote! {
+        #(#outer_attrs)*
+        #vis #sig {
+            #(#inner_attrs)*
+            #env_dependencies
+            #(#stmts)*
+        }
+    };
+
+    // 环境变量提供项目级默认值, 属性参数优先
+    let mut args = match Args::from_env() {
+        Ok(env) => env.merge(args),
+        Err(err) => return err.to_compile_error(),
+    };
+    // skip_types: 类型匹配的参数与按名称跳过的参数一样处理
+    for name in skipped_by_type(&args, sig) {
+        if !args.skip.contains(&name) {
+            args.skip.push(name);
+        }
+    }
+    // 参数在所有提前返回之前校验, 同一个属性不会因启用的 feature 或白名单不同而时而报错时而通过
+    if let Err(err) = validate(&args, sig) {
+        return err.to_compile_error();
+    }
+
+    // 启用 disabled feature 时保留原函数
+    if cfg!(feature = "disabled") {
+        return original;
+    }
+
+    // 设置了编译期白名单时, 不在白名单中的函数保留原函数
+    let qualified_name = allow::qualified_name(&args.scope, &sig.ident.to_string());
+    match allow::is_allowed(&qualified_name) {
+        Ok(true) => {}
+        Ok(false) => return original,
+        Err(err) => return err.to_compile_error(),
+    }
+    let tracing = args.tracing_path();
+    let fixed_level = args.level.as_deref().unwrap_or("trace");
+    // level_fn: 入口与退出事件的等级在调用时决定, span 仍使用 `level`
+    let level = if args.level_fn.is_some() {
+        DYNAMIC_LEVEL
+    } else {
+        fixed_level
+    };
+
+    // tracing 的 max_level_* feature 在编译期移除了该等级的事件, 保留原函数
+    let static_level = static_level(&args, level);
+    if let Err(err) = report::record(
+        &qualified_name,
+        fixed_level,
+        args.force == Some(true),
+        static_level != StaticLevel::Enabled,
+    ) {
+        return err.to_compile_error();
+    }
+    if static_level == StaticLevel::Disabled {
+        return original;
+    }
+
+    if args.backend == Some(Backend::Etw) && !cfg!(feature = "win-etw") {
+        return syn::Error::new_spanned(
+            &sig.ident,
+            "`backend = \"etw\"` 需要启用 tracing-fn 的 win-etw feature",
+        )
+        .to_compile_error();
+    }
+
+    let entry_only = args.entry_only == Some(true);
+    let exit_only = args.exit_only == Some(true);
+    // entry_only: 不输出退出事件, 也不需要格式化返回值
+    if entry_only {
+        args.skip_ret = Some(true);
+    }
+
+    // test_subscriber: subscriber 由 tracing-subscriber 提供, 需要启用对应的 feature
+    if args.test_subscriber == Some(true)
+        && cfg!(feature = "std")
+        && !cfg!(feature = "test-subscriber")
+    {
+        return syn::Error::new_spanned(
+            &sig.ident,
+            "`test_subscriber = true` 需要启用 tracing-fn 的 test-subscriber feature",
+        )
+        .to_compile_error();
+    }
+
+    // 未启用 std feature 时使用不分配内存、不依赖 std 的实现
+    if !cfg!(feature = "std") {
+        return match no_std::instrument(&args, sig, block) {
+            Ok(instrumented) => wrap(
+                &args,
+                &outer_attrs,
+                &inner_attrs,
+                &vis,
+                sig,
+                stmts,
+                instrumented,
+            ),
+            Err(err) => err.to_compile_error(),
+        };
+    }
+
+    // poll: 手动实现的 poll 函数只在就绪状态变化时输出事件
+    if args.poll.is_some() {
+        return match poll::instrument(&args, sig, block) {
+            Ok(instrumented) => wrap(
+                &args,
+                &outer_attrs,
+                &inner_attrs,
+                &vis,
+                sig,
+                stmts,
+                instrumented,
+            ),
+            Err(err) => err.to_compile_error(),
+        };
+    }
+
+    let fn_name = &sig.ident;
+    let is_async = sig.asyncness.is_some();
+
+    // allocs: 分配计数同样基于线程局部变量, 只支持同步函数
+    let allocs = args.allocs == Some(true);
+    if allocs && !cfg!(feature = "alloc-count") {
+        return syn::Error::new_spanned(
+            fn_name,
+            "`allocs = true` 需要启用 tracing-fn 的 alloc-count feature",
+        )
+        .to_compile_error();
+    }
+
+    // measure: 线程的 CPU 时间只对不会在线程间切换的同步函数有意义
+    let measure = args.measure.unwrap_or(Measure::Wall);
+    if measure.cpu() && !cfg!(feature = "cpu") {
+        return syn::Error::new_spanned(
+            fn_name,
+            "`measure = \"cpu\"` 需要启用 tracing-fn 的 cpu feature",
+        )
+        .to_compile_error();
+    }
+
+    // err: 错误链从函数返回的错误中取得, 未指定时 anyhow 与 eyre 的错误以 `{:#}` 输出
+    let err_mode = err_mode(&args, sig);
+
+    // json: 参数通过 serde_json 序列化, 只附加到入口处输出的入口事件
+    if !args.json.is_empty() && !cfg!(feature = "serde") {
+        return syn::Error::new_spanned(fn_name, "`json` 需要启用 tracing-fn 的 serde feature")
+            .to_compile_error();
+    }
+
+    // record: 回放文件的读写依赖 serde, 需要启用对应的 feature
+    // replay: 生成以记录重新执行函数的伴生函数, 同时记录调用
+    let replay = args.record == Some(true) || args.replay == Some(true);
+    if replay && !cfg!(feature = "record-replay") {
+        let name = if args.replay == Some(true) {
+            "replay"
+        } else {
+            "record"
+        };
+        return syn::Error::new_spanned(
+            fn_name,
+            format!("`{name} = true` 需要启用 tracing-fn 的 record-replay feature"),
+        )
+        .to_compile_error();
+    }
+    // binary: 记录文件的写入依赖内存映射, 需要启用对应的 feature
+    if args.binary == Some(true) && !cfg!(feature = "binary") {
+        return syn::Error::new_spanned(
+            fn_name,
+            "`binary = true` 需要启用 tracing-fn 的 binary feature",
+        )
+        .to_compile_error();
+    }
+    let companion = if args.replay == Some(true) {
+        match replay::companion(&args, &vis, sig, &qualified_name) {
+            Ok(companion) => companion,
+            Err(err) => return err.to_compile_error(),
+        }
+    } else {
+        quote!()
+    };
+
+    // 获取所有参数名
+    let mut arg_values = Vec::new();
+    let mut value_fields = Vec::new();
+    // 解构的参数 (例如 `(x, y): (i32, i32)`) 按其中绑定的变量逐个输出
+    let mut bindings = Vec::new();
+    for arg in &sig.inputs {
+        if let syn::FnArg::Typed(pat_type) = arg {
+            collect_bindings(&pat_type.pat, &mut bindings);
+        }
+    }
+    // record: 参数按顺序序列化为数组, 跳过的参数记录为 null
+    let replay_values: Vec<_> = bindings
+        .iter()
+        .map(|ident| {
+            if args.skip.contains(&ident.to_string()) {
+                quote!(&())
+            } else {
+                quote!(&#ident)
+            }
+        })
+        .collect();
+    let callables = callable_args(sig);
+    let runtime_skip = args.runtime_skip == Some(true);
+    for ident in bindings {
+        let arg_name = ident.to_string();
+        let pushed = arg_values.len();
+        // format: 使用自定义的格式化函数代替参数的 Debug 输出, 跳过的参数不调用
+        let format = args.format.iter().find(|(name, _)| name == ident);
+        if args.skip.contains(&arg_name) {
+            if let Some(redacted) = redacted(&args, ident) {
+                arg_values.push(quote! {
+                    ::std::format!("{}={}", #arg_name, #redacted)
+                });
+            }
+        } else if args.json.contains(&arg_name) {
+            // json: 序列化结果作为与参数同名的结构化字段输出, 参数列表中只保留占位
+            let placeholder = format!("{arg_name}=<json>");
+            arg_values.push(quote!(::std::string::String::from(#placeholder)));
+            value_fields.push(quote!(#ident = %::tracing_fn::runtime::to_json(&#ident),));
+        } else if args.valuable.contains(&arg_name) {
+            // valuable: 由 subscriber 访问嵌套的字段, 参数列表中只保留占位
+            let placeholder = format!("{arg_name}=<valuable>");
+            arg_values.push(quote!(::std::string::String::from(#placeholder)));
+            value_fields.push(quote!(#ident = #tracing::field::valuable(&#ident),));
+        } else if let Some((_, format)) = format {
+            let formatted = truncated(&args, quote!(#format(&#ident)));
+            arg_values.push(quote! {
+                ::std::format!("{}={}", #arg_name, #formatted)
+            });
+        } else if args.summarize.contains(&arg_name) {
+            // summarize: 只输出集合的类型与长度
+            let summary = summary_value(ident);
+            arg_values.push(quote! {
+                ::std::format!("{}={}", #arg_name, #summary)
+            });
+        } else if callables.contains(ident) {
+            // 函数指针与闭包只输出类型名
+            let callable = truncated(&args, callable_value(ident));
+            arg_values.push(quote! {
+                ::std::format!("{}={:?}", #arg_name, #callable)
+            });
+        } else {
+            let debug = truncated(&args, debug_value(quote!(#ident)));
+            arg_values.push(quote! {
+                ::std::format!("{}={:?}", #arg_name, #debug)
+            });
+        }
+        // runtime_skip: 输出的参数在运行时被设置为跳过时按 redact 指定的方式输出, omit 时输出为空并在拼接前去掉
+        if runtime_skip
+            && !args.skip.contains(&arg_name)
+            && let Some(value) = arg_values.get_mut(pushed)
+        {
+            // 编译期无法确定参数实现了 Debug, sha256 对 Debug 的回退输出取哈希
+            let redacted = match args.redact {
+                Some(Redact::Sha256) => {
+                    let debug = debug_value(quote!(#ident));
+                    quote!(
+                        ::std::format!("{}={}", #arg_name, ::tracing_fn::runtime::Sha256Prefix::of(&#debug))
+                    )
+                }
+                Some(Redact::Omit) => quote!(::std::string::String::new()),
+                Some(Redact::Mask) | None => {
+                    let placeholder = args.redacted_as.as_deref().unwrap_or("***");
+                    quote!(::std::format!("{}={}", #arg_name, #placeholder))
+                }
+            };
+            *value = quote! {
+                if __tracing_fn_redacted.contains(#arg_name) {
+                    #redacted
+                } else {
+                    #value
+                }
+            };
+        }
+    }
+    // runtime_skip: 同一次调用中的所有参数使用同一份设置
+    let redaction = runtime_skip.then(|| {
+        let function = match (&args.impl_block, args.scope.last()) {
+            (Some(_), Some(ty)) => format!("::{ty}::{fn_name}"),
+            _ => format!("::{fn_name}"),
+        };
+        quote! {
+            let __tracing_fn_redacted =
+                ::tracing_fn::redaction::current(::std::concat!(::std::module_path!(), #function));
+        }
+    });
+    let retain = runtime_skip.then(|| quote!(__tracing_fn_args.retain(|arg| !arg.is_empty());));
+
+    // skip_empty_args: 没有需要输出的参数时 (包括全部被 `redact = "omit"` 省略), 事件中不输出参数部分
+    let args_clause = args.skip_empty_args != Some(true) || !arg_values.is_empty();
+
+    let level_ident = syn::Ident::new(&fixed_level.to_uppercase(), proc_macro2::Span::call_site());
+    let fn_name_str = display_name(&args, sig);
+    // 使用函数名的 span, 使 impl 块或模块中的函数也能输出各自的位置
+    let location = quote_spanned!(fn_name.span()=> ::core::file!(), ::core::line!());
+
+    // once_per: 同一 key 只在首次调用时输出事件, 之后的调用计数并周期性汇总
+    // 与 first 相同, 只观察事件会被记录的调用, 未启用该等级时的调用不占用 key, 也不查询 LRU
+    let once_per = args.once_per.as_ref().map(|once_per| {
+        let key = &once_per.key;
+        let key_str = quote!(#key).to_string();
+        let capacity = once_per.capacity;
+        let period = once_per.period.as_nanos() as u64;
+        let event_summary = dispatched(
+            &args,
+            event(
+                &args,
+                EventKind::Event,
+                level,
+                quote!(),
+                quote! {
+                    "=== [{}] #OncePer({}): suppressed {} repeated calls, {} keys tracked",
+                    #fn_name_str,
+                    #key_str,
+                    __tracing_fn_summary.suppressed,
+                    __tracing_fn_summary.keys
+                },
+            ),
+        );
+        quote! {
+            static __TRACING_FN_ONCE_PER: ::tracing_fn::runtime::OncePer =
+                ::tracing_fn::runtime::OncePer::new(
+                    #capacity,
+                    ::std::time::Duration::from_nanos(#period),
+                );
+            let __tracing_fn_enabled = __tracing_fn_enabled && {
+                let __tracing_fn_once_per = __TRACING_FN_ONCE_PER.observe(&(#key));
+                if let ::std::option::Option::Some(__tracing_fn_summary) = __tracing_fn_once_per.summary {
+                    #event_summary
+                }
+                __tracing_fn_once_per.emit
+            };
+        }
+    });
+
+    let args_str = quote! {
+        {
+            #redaction
+            #[allow(unused_mut)]
+            let mut __tracing_fn_args: ::std::vec::Vec<::std::string::String> = ::std::vec![#(#arg_values),*];
+            #retain
+            if __tracing_fn_args.is_empty() {
+                ::std::borrow::ToOwned::to_owned("()")
+            } else {
+                __tracing_fn_args.join(", ")
+            }
+        }
+    };
+
+    // compress_prefix: 入口事件中重复出现的前 len 个参数替换为引用编号
+    // 黑匣子中的记录仍使用完整参数
+    let compressed_args = args.compress_prefix.as_ref().map(|compress| {
+        let len = compress.len;
+        let window = compress.window.as_nanos() as u64;
+        quote! {
+            {
+                static __TRACING_FN_PREFIX: ::tracing_fn::runtime::PrefixCache =
+                    ::tracing_fn::runtime::PrefixCache::new(::std::time::Duration::from_nanos(#window));
+                #redaction
+                let mut __tracing_fn_args: ::std::vec::Vec<::std::string::String> = ::std::vec![#(#arg_values),*];
+                #retain
+                if __tracing_fn_args.is_empty() {
+                    ::std::borrow::ToOwned::to_owned("()")
+                } else {
+                    let __tracing_fn_rest = __tracing_fn_args.split_off(::core::cmp::Ord::min(__tracing_fn_args.len(), #len));
+                    let mut __tracing_fn_head = ::std::vec![__TRACING_FN_PREFIX.compress(__tracing_fn_args.join(", "))];
+                    ::core::iter::Extend::extend(&mut __tracing_fn_head, __tracing_fn_rest);
+                    __tracing_fn_head.join(", ")
+                }
+            }
+        }
+    });
+
+    // abort_safe: 入口参数写入黑匣子, panic 时由 panic hook 输出, 不依赖析构
+    // async fn 可能在不同线程间切换, 只写入最近调用记录, 不记录未返回的调用
+    // 黑匣子中的记录与入口、退出事件使用相同的调用 ID
+    let (abort_safe, entry_args) = if args.abort_safe == Some(true) {
+        let record = if is_async {
+            quote! {
+                ::tracing_fn::runtime::InFlight::record(
+                    ::core::clone::Clone::clone(&__tracing_fn_id),
+                    #fn_name_str,
+                    ::core::clone::Clone::clone(&__tracing_fn_args_str),
+                );
+            }
+        } else {
+            quote! {
+                let __tracing_fn_in_flight = ::tracing_fn::runtime::InFlight::enter(
+                    ::core::clone::Clone::clone(&__tracing_fn_id),
+                    #fn_name_str,
+                    ::core::clone::Clone::clone(&__tracing_fn_args_str),
+                );
+            }
+        };
+        let report = event(
+            &args,
+            EventKind::Event,
+            "error",
+            quote!(),
+            quote!("!!! #Panic: {}", report),
+        );
+        let prelude = quote! {
+            fn __tracing_fn_report(report: &str) {
+                #report
+            }
+            ::tracing_fn::runtime::install_panic_hook(__tracing_fn_report);
+            let __tracing_fn_id = ::tracing_fn::InvocationId::next();
+            let __tracing_fn_args_str: ::std::string::String = #args_str;
+            #record
+            let __tracing_fn_id = __tracing_fn_enabled.then_some(__tracing_fn_id);
+        };
+        let entry_args = compressed_args
+            .clone()
+            .unwrap_or_else(|| quote!(__tracing_fn_args_str));
+        (prelude, entry_args)
+    } else {
+        let prelude = quote! {
+            let __tracing_fn_id = __tracing_fn_enabled.then(::tracing_fn::InvocationId::next);
+        };
+        (prelude, compressed_args.unwrap_or_else(|| args_str.clone()))
+    };
+
+    // pressure: 事件会被记录时在入口处读取一次日志管道的压力, 按策略丢弃事件或降级为不输出参数与返回值。
+    // 未指定策略时由 priority 决定
+    let shedding = args
+        .pressure
+        .or(args.priority.map(Priority::shedding))
+        .unwrap_or(Shedding::Downgrade);
+    let pressure = match shedding {
+        Shedding::Keep => quote!(),
+        Shedding::Downgrade | Shedding::Drop => {
+            let drop_at = if shedding == Shedding::Drop {
+                quote!(High)
+            } else {
+                quote!(Critical)
+            };
+            quote! {
+                let __tracing_fn_pressure = if __tracing_fn_enabled {
+                    ::tracing_fn::pressure()
+                } else {
+                    ::tracing_fn::Pressure::Normal
+                };
+                let __tracing_fn_enabled =
+                    __tracing_fn_enabled && __tracing_fn_pressure < ::tracing_fn::Pressure::#drop_at;
+            }
+        }
+    };
+
+    // verbosity_tiers: 入口处读取一次全局详细程度, 低于对应等级时不输出参数或返回值
+    let mut verbosity = TokenStream::new();
+    let mut show_args = Vec::new();
+    let mut show_ret = Vec::new();
+    if let Some(tiers) = &args.verbosity_tiers {
+        let (args_tier, ret_tier) = (tiers.args, tiers.ret);
+        verbosity.extend(quote!(let __tracing_fn_verbosity = ::tracing_fn::verbosity();));
+        show_args.push(quote!(__tracing_fn_verbosity >= #args_tier));
+        show_ret.push(quote!(__tracing_fn_verbosity >= #ret_tier));
+    }
+    if shedding == Shedding::Downgrade {
+        let normal = quote!(__tracing_fn_pressure == ::tracing_fn::Pressure::Normal);
+        show_args.push(normal.clone());
+        show_ret.push(normal);
+    }
+    let (entry_args, ret_field, ret_init, ret_visible, ret_omitted) = if show_args.is_empty() {
+        (entry_args, quote!(), quote!(), quote!(), quote!())
+    } else {
+        (
+            quote! {
+                if #(#show_args)&&* {
+                    #entry_args
+                } else {
+                    ::std::borrow::ToOwned::to_owned("<omitted>")
+                }
+            },
+            quote!(show_ret: bool,),
+            quote!(show_ret: #(#show_ret)&&*,),
+            quote!(&&self.show_ret),
+            quote!(::std::option::Option::None if !self.show_ret => "<omitted>",),
+        )
+    };
+
+    // tenant: 入口处获取一次租户标识, 作为结构化字段附加到入口与退出事件
+    let (tenant, tenant_field, tenant_init, entry_tenant, exit_tenant) =
+        if args.tenant == Some(true) {
+            (
+                quote! {
+                    let __tracing_fn_tenant = if __tracing_fn_enabled {
+                        ::tracing_fn::runtime::current_tenant()
+                    } else {
+                        ::std::option::Option::None
+                    };
+                },
+                quote!(tenant: ::std::option::Option<::std::string::String>,),
+                quote!(tenant: __tracing_fn_tenant,),
+                quote!(tenant = __tracing_fn_tenant.as_deref(),),
+                quote!(tenant = self.tenant.as_deref(),),
+            )
+        } else {
+            (quote!(), quote!(), quote!(), quote!(), quote!())
+        };
+
+    // with: 入口处调用一次上下文提供函数, 键值对格式化为结构化字段 ctx 附加到入口与退出事件
+    let (context, context_field, context_init, entry_context, exit_context) = match &args.with {
+        Some(provider) => (
+            quote! {
+                let __tracing_fn_ctx = if __tracing_fn_enabled {
+                    ::tracing_fn::runtime::render_context(#provider())
+                } else {
+                    ::std::option::Option::None
+                };
+            },
+            quote!(ctx: ::std::option::Option<::std::string::String>,),
+            quote!(ctx: __tracing_fn_ctx,),
+            quote!(ctx = __tracing_fn_ctx.as_deref(),),
+            quote!(ctx = self.ctx.as_deref(),),
+        ),
+        None => (quote!(), quote!(), quote!(), quote!(), quote!()),
+    };
+
+    // fields: 固定的字段, 每次输出事件时求值
+    let static_fields = static_fields(&args);
+
+    // batch: 每次调用创建进度计数, 函数体中的 `batch_tick!()` 替换为累加计数并在跨过 chunk 时输出进度事件
+    // 计数以引用的形式绑定, 闭包与 `async move` 块中使用时只复制引用
+    let (batch, batch_tick) = match &args.batch {
+        Some(batch) => {
+            let chunk = batch.chunk;
+            let total = match &batch.items {
+                Some(items) => quote! {
+                    (&&::tracing_fn::runtime::BatchItems(&#items)).__tracing_fn_batch_len()
+                },
+                None => quote!(::std::option::Option::None),
+            };
+            let event_tick = dispatched(
+                &args,
+                event(
+                    &args,
+                    EventKind::Event,
+                    level,
+                    quote! {
+                        #static_fields
+                        batch.processed = __tracing_fn_tick.processed,
+                        batch.total = __tracing_fn_tick.total,
+                    },
+                    quote! {
+                        "=== [{}] #{} #Batch: processed: {}, elapsed: {:?}, rate: {:.1}/s",
+                        #fn_name_str,
+                        __tracing_fn_tick.id,
+                        __tracing_fn_tick,
+                        __tracing_fn_tick.elapsed,
+                        __tracing_fn_tick.rate()
+                    },
+                ),
+            );
+            (
+                quote! {
+                    #[allow(unused_imports)]
+                    use ::tracing_fn::runtime::{ViaBatchLen as _, ViaUnknownLen as _};
+                    let __tracing_fn_batch = &::tracing_fn::runtime::BatchProgress::new(
+                        ::core::clone::Clone::clone(&__tracing_fn_id),
+                        #total,
+                        #chunk,
+                    );
+                },
+                Some(quote! {
+                    if let ::std::option::Option::Some(__tracing_fn_tick) =
+                        __tracing_fn_batch.tick(__tracing_fn_count)
+                    {
+                        #event_tick
+                    }
+                }),
+            )
+        }
+        None => (quote!(), None),
+    };
+
+    // record: 参数在入口处序列化, 函数体可能移动参数
+    let replay_args = if replay {
+        quote! {
+            let __tracing_fn_replay_args =
+                ::tracing_fn::runtime::replay_args(&(#(#replay_values,)*));
+        }
+    } else {
+        quote!()
+    };
+
+    // err_code: 守卫以错误码的类型为泛型参数, 由函数体中对错误码的赋值推断, 数值类型的错误码因此仍以数值输出
+    let (err_code_params, err_code_args, err_code_field, err_code_init, exit_err_code) =
+        if args.err_code.is_some() {
+            (
+                quote!(<__TracingFnErrCode: #tracing::Value>),
+                quote!(<__TracingFnErrCode>),
+                quote!(err_code: ::std::option::Option<__TracingFnErrCode>,),
+                quote!(err_code: ::std::option::Option::None,),
+                quote!(error.code = self.err_code.as_ref(),),
+            )
+        } else {
+            (quote!(), quote!(), quote!(), quote!(), quote!())
+        };
+
+    // 嵌套调用统计基于线程局部变量, async fn 不参与统计
+    let fanout = args.fanout == Some(true) && !is_async;
+
+    // thread: 在事件中附加输出事件时所在线程的名称与 ID, async fn 的入口与退出可能位于不同线程
+    let thread_fields = if args.thread == Some(true) {
+        quote! {
+            thread.name = ::std::thread::current().name(),
+            thread.id = ?::std::thread::current().id(),
+        }
+    } else {
+        quote!()
+    };
+
+    // 启用 tokio feature 时, async fn 的事件附加当前 tokio 任务的 ID
+    let task_field = if cfg!(feature = "tokio") && is_async {
+        quote! {
+            tokio.task.id = ::tracing_fn::runtime::task_id().map(#tracing::field::display),
+        }
+    } else {
+        quote!()
+    };
+
+    // paired: 不输出入口事件, 参数在入口处格式化后随退出事件一起输出
+    // err_only 同样只输出合并的事件, 且只在失败时输出
+    let err_only = args.err_only == Some(true);
+    let paired = args.paired == Some(true) || err_only;
+    let paired_args = paired && args_clause;
+
+    // entry_on_slow: 参数同样在入口处格式化, 耗时超过阈值时才在退出处补发入口事件
+    let slow_entry = args.entry_on_slow.is_some();
+
+    // caller: 调用方的位置在入口处取得, 不输出入口事件时随退出事件输出
+    let caller = args.caller == Some(true);
+    let exit_caller = caller && (paired || exit_only);
+    let (caller_eval, caller_field, caller_init) = if exit_caller || (caller && slow_entry) {
+        (
+            quote!(let __tracing_fn_caller = ::std::panic::Location::caller();),
+            quote!(caller: &'static ::std::panic::Location<'static>,),
+            quote!(caller: __tracing_fn_caller,),
+        )
+    } else if caller {
+        (
+            quote!(let __tracing_fn_caller = ::std::panic::Location::caller();),
+            quote!(),
+            quote!(),
+        )
+    } else {
+        (quote!(), quote!(), quote!())
+    };
+
+    // generics: 类型参数的类型名在入口处取得, 附加在事件中的函数名之后
+    let (generics_eval, generics_field, generics_init, generics_part) =
+        match generic_args(&args, sig) {
+            Some((eval, ty)) => (
+                eval,
+                quote!(generics: #ty,),
+                quote!(generics: __tracing_fn_generics,),
+                "{}",
+            ),
+            None => (quote!(), quote!(), quote!(), ""),
+        };
+    let entry_generics = (!generics_part.is_empty()).then(|| quote!(__tracing_fn_generics,));
+    let exit_generics = (!generics_part.is_empty()).then(|| quote!(self.generics,));
+
+    // caller_fn: 入口处从当前线程的栈帧取得最近的外层被追踪调用的函数名
+    let caller_fn = args.caller_fn == Some(true);
+    let exit_caller_fn = caller_fn && (paired || exit_only);
+    let (caller_fn_eval, caller_fn_field, caller_fn_init) = if caller_fn {
+        let eval = quote! {
+            let __tracing_fn_caller_fn = __tracing_fn_frame.caller().unwrap_or("<root>");
+        };
+        if exit_caller_fn || slow_entry {
+            (
+                eval,
+                quote!(caller_fn: &'static str,),
+                quote!(caller_fn: __tracing_fn_caller_fn,),
+            )
+        } else {
+            (eval, quote!(), quote!())
+        }
+    } else {
+        (quote!(), quote!(), quote!())
+    };
+
+    // 退出事件的各个部分
+    let mut exit_parts = Vec::new();
+    let mut exit_values = Vec::new();
+    if paired_args {
+        exit_values.push(quote!(self.args.as_deref().unwrap_or_default()));
+    }
+    if exit_caller {
+        exit_values.push(quote!(self.caller));
+    }
+    if exit_caller_fn {
+        exit_values.push(quote!(self.caller_fn));
+    }
+    if args.skip_ret != Some(true) {
+        exit_parts.push("#Ret: {}");
+        exit_values.push(quote!(__tracing_fn_ret));
+    }
+    // 耗时同时作为以微秒为单位的数值字段输出, 便于日志管道聚合
+    let mut exit_duration = quote!();
+    // duration_unit: 以固定的单位输出耗时, 代替单位随大小变化的 Debug 输出
+    let (duration_part, cpu_part, in_unit) = match args.duration_unit {
+        Some(unit) => {
+            let unit = Ident::new(unit.variant(), Span::call_site());
+            (
+                "duration: {}",
+                "cpu: {}",
+                Some(quote!(::tracing_fn::runtime::DurationUnit::#unit)),
+            )
+        }
+        None => ("duration: {:?}", "cpu: {:?}", None),
+    };
+    let in_unit = |duration: TokenStream| match &in_unit {
+        Some(unit) => quote!(::tracing_fn::runtime::InUnit(#duration, #unit)),
+        None => duration,
+    };
+    if measure.wall() {
+        exit_parts.push(duration_part);
+        exit_values.push(in_unit(quote!(__tracing_fn_duration)));
+        exit_duration = quote!(duration_us = __tracing_fn_duration.as_micros() as u64,);
+    }
+    if measure.cpu() {
+        exit_parts.push(cpu_part);
+        exit_values.push(in_unit(quote!(self.cpu_start.elapsed())));
+    }
+    if allocs {
+        exit_parts.push("allocs: {}, bytes: {}");
+        exit_values.push(quote!(__tracing_fn_allocs.allocs));
+        exit_values.push(quote!(__tracing_fn_allocs.bytes));
+    }
+    if fanout {
+        exit_parts.push("calls: {}, failed: {}");
+        exit_values.push(quote!(__tracing_fn_calls.calls));
+        exit_values.push(quote!(__tracing_fn_calls.failed));
+    }
+    // location: 合并的事件同时代替入口事件, 除 `none` 外都输出位置
+    let location_mode = args.location();
+    let (entry_location, exit_location) = if paired {
+        (false, location_mode != Location::None)
+    } else {
+        (location_mode.entry(), location_mode.exit())
+    };
+    if paired {
+        exit_parts.push("outcome: {}");
+        exit_values.push(quote! {
+            if __tracing_fn_panicking {
+                "panicked"
+            } else if self.failed {
+                "err"
+            } else {
+                "ok"
+            }
+        });
+    }
+    // record_on_current!: 函数体中记录到栈帧的字段附加在退出事件的末尾, async fn 不参与栈帧
+    let recorded = !is_async;
+    if recorded {
+        exit_values.push(quote!(__tracing_fn_recorded));
+    }
+    if exit_location {
+        exit_values.push(location.clone());
+    }
+
+    // depth: 按当前线程中外层被追踪调用的层数缩进事件, 并附加结构化字段 depth
+    // 调用深度同样基于线程局部变量的栈帧, async fn 不参与
+    let depth = args.depth == Some(true) && !is_async;
+    let (depth_prefix, depth_save, entry_depth, entry_indent, exit_depth, exit_indent) = if depth {
+        (
+            "{}",
+            quote! {
+                let __tracing_fn_depth = self
+                    .frame
+                    .as_ref()
+                    .map_or(0, ::tracing_fn::runtime::Frame::depth);
+            },
+            quote!(depth = __tracing_fn_frame.depth(),),
+            quote!(::tracing_fn::runtime::indent(__tracing_fn_frame.depth()),),
+            quote!(depth = __tracing_fn_depth,),
+            quote!(::tracing_fn::runtime::indent(__tracing_fn_depth),),
+        )
+    } else {
+        ("", quote!(), quote!(), quote!(), quote!(), quote!())
+    };
+    let entry_format = format!(
+        "{depth_prefix}>>> [{{}}{generics_part}] #{{}}{}{}{}{}",
+        if args_clause { " #Args: {}" } else { "" },
+        if caller { " #Caller: {}" } else { "" },
+        if caller_fn { " #From: {}" } else { "" },
+        if entry_location { " --- {}:{}" } else { "" }
+    );
+    let exit_format = format!(
+        "{depth_prefix}<<< [{{}}{generics_part}] #{{}} {}{}{}{}{}{}",
+        if paired_args { "#Args: {} | " } else { "" },
+        if exit_caller { "#Caller: {} | " } else { "" },
+        if exit_caller_fn { "#From: {} | " } else { "" },
+        exit_parts.join(", "),
+        if recorded { "{}" } else { "" },
+        if exit_location { " --- {}:{}" } else { "" }
+    );
+    let entry_location = entry_location.then(|| location.clone());
+    let entry_caller = caller.then(|| quote!(__tracing_fn_caller,));
+    let entry_caller_fn = caller_fn.then(|| quote!(__tracing_fn_caller_fn,));
+
+    let (frame_enter, frame_field, frame_init, frame_exit) = if is_async {
+        (
+            quote!(),
+            quote!(),
+            quote!(),
+            quote!(<::tracing_fn::runtime::CallCounts as ::core::default::Default>::default()),
+        )
+    } else {
+        (
+            quote!(let __tracing_fn_frame = ::tracing_fn::runtime::Frame::enter(#fn_name_str);),
+            quote!(frame: ::std::option::Option<::tracing_fn::runtime::Frame>,),
+            quote!(frame: ::std::option::Option::Some(__tracing_fn_frame),),
+            quote! {
+                match self.frame.take() {
+                    ::std::option::Option::Some(frame) => {
+                        frame.exit(self.failed || __tracing_fn_panicking)
+                    }
+                    ::std::option::Option::None => <::tracing_fn::runtime::CallCounts as ::core::default::Default>::default(),
+                }
+            },
+        )
+    };
+
+    let recorded_take = recorded.then(|| {
+        quote! {
+            #[allow(unused_variables)]
+            let __tracing_fn_recorded = match (&self.id, &self.frame) {
+                (::std::option::Option::Some(_), ::std::option::Option::Some(frame)) => frame.recorded(),
+                _ => <::tracing_fn::runtime::Recorded as ::core::default::Default>::default(),
+            };
+        }
+    });
+
+    // usdt: 探针与日志等级无关, 只要执行了添加 tracing 的函数体就经过探针
+    let (usdt_entry, usdt_exit) = if cfg!(feature = "usdt") {
+        (
+            quote!(::tracing_fn::runtime::usdt_entry(#qualified_name);),
+            quote! {
+                ::tracing_fn::runtime::usdt_exit(
+                    #qualified_name,
+                    __tracing_fn_duration,
+                    self.failed || __tracing_fn_panicking,
+                );
+            },
+        )
+    } else {
+        (quote!(), quote!())
+    };
+
+    // main-stats: #[tracing_main] 的退出统计只包含输出了事件的调用, 未启用时不生成统计与原子读取
+    let main_stats = cfg!(feature = "main-stats").then(|| {
+        quote! {
+            {
+                static __TRACING_FN_STATS: ::tracing_fn::runtime::FnStats =
+                    ::tracing_fn::runtime::FnStats::new(#qualified_name);
+                __TRACING_FN_STATS.record(
+                    __tracing_fn_duration,
+                    self.failed || __tracing_fn_panicking,
+                );
+            }
+        }
+    });
+
+    // binary: 与 usdt 相同, 记录与日志等级无关, 打开记录文件后每次调用都写入
+    let (binary_entry, binary_field, binary_init, binary_exit) = if args.binary == Some(true) {
+        (
+            quote! {
+                let __tracing_fn_binary = {
+                    static __TRACING_FN_BINARY: ::tracing_fn::runtime::BinaryFn =
+                        ::tracing_fn::runtime::BinaryFn::new(#qualified_name);
+                    __TRACING_FN_BINARY.enter()
+                };
+            },
+            quote!(binary: ::std::option::Option<::tracing_fn::runtime::BinaryCall>,),
+            quote!(binary: __tracing_fn_binary,),
+            quote! {
+                if let ::std::option::Option::Some(call) = self.binary.take() {
+                    call.exit(self.failed, __tracing_fn_panicking);
+                }
+            },
+        )
+    } else {
+        (quote!(), quote!(), quote!(), quote!())
+    };
+
+    // audit: 事件消息先格式化为字符串并追加到该函数的审计链, 与审计链的序号和哈希一起输出
+    let audit = args.audit == Some(true);
+    let audited_event =
+        |kind: EventKind, level: &str, fields: TokenStream, message: TokenStream| {
+            if !audit {
+                return event(&args, kind, level, fields, message);
+            }
+            let event = event(
+                &args,
+                kind,
+                level,
+                quote! {
+                    #fields
+                    audit.seq = __tracing_fn_audit.seq,
+                    audit.prev = %::core::format_args!("{:016x}", __tracing_fn_audit.prev),
+                    audit.hash = %::core::format_args!("{:016x}", __tracing_fn_audit.hash),
+                },
+                quote!("{}", __tracing_fn_message),
+            );
+            quote! {
+                let __tracing_fn_message = ::std::format!(#message);
+                let __tracing_fn_audit = __TRACING_FN_AUDIT.append(&__tracing_fn_message);
+                #event
+            }
+        };
+    let audit_chain = if audit {
+        quote! {
+            static __TRACING_FN_AUDIT: ::tracing_fn::runtime::AuditChain =
+                ::tracing_fn::runtime::AuditChain::new();
+        }
+    } else {
+        quote!()
+    };
+
+    let (entry, paired_field, paired_init) = if exit_only || (paired || slow_entry) && !args_clause
+    {
+        (quote!(), quote!(), quote!())
+    } else if paired || slow_entry {
+        (
+            quote! {
+                let __tracing_fn_paired_args = if __tracing_fn_id.is_some() {
+                    ::std::option::Option::Some(#entry_args)
+                } else {
+                    ::std::option::Option::None
+                };
+            },
+            quote!(args: ::std::option::Option<::std::string::String>,),
+            quote!(args: __tracing_fn_paired_args,),
+        )
+    } else {
+        let entry_args_value = args_clause.then(|| quote!(__tracing_fn_args_str,));
+        let event_entry = dispatched(
+            &args,
+            audited_event(
+                EventKind::Entry,
+                level,
+                quote! {
+                    #entry_tenant
+                    #entry_context
+                    #(#value_fields)*
+                    #static_fields
+                    #entry_depth
+                    #thread_fields
+                    #task_field
+                },
+                quote! {
+                    #entry_format,
+                    #entry_indent
+                    #fn_name_str,
+                    #entry_generics
+                    __tracing_fn_id,
+                    #entry_args_value
+                    #entry_caller
+                    #entry_caller_fn
+                    #entry_location
+                },
+            ),
+        );
+        let entry = quote! {
+            if let ::std::option::Option::Some(__tracing_fn_id) = &__tracing_fn_id {
+                let __tracing_fn_args_str = #entry_args;
+                #event_entry
+            }
+        };
+        (entry, quote!(), quote!())
+    };
+
+    let exit_event = |level: &str| {
+        dispatched(
+            &args,
+            audited_event(
+                EventKind::Exit,
+                level,
+                quote! {
+                    #exit_tenant
+                    #exit_context
+                    #exit_err_code
+                    #exit_duration
+                    #static_fields
+                    #exit_depth
+                    #thread_fields
+                    #task_field
+                },
+                quote! {
+                    #exit_format,
+                    #exit_indent
+                    #fn_name_str,
+                    #exit_generics
+                    __tracing_fn_id,
+                    #(#exit_values),*
+                },
+            ),
+        )
+    };
+    // err_level: 返回 Err 时以更高的等级输出退出事件
+    let event_exit = match &args.err_level {
+        Some(err_level) => {
+            let event_err = exit_event(err_level);
+            let event_exit = exit_event(level);
+            quote! {
+                if self.failed {
+                    #event_err
+                } else {
+                    #event_exit
+                }
+            }
+        }
+        None => exit_event(level),
+    };
+    let event_exit = if let Some(threshold) = args.entry_on_slow {
+        let threshold = threshold.as_nanos() as u64;
+        let slow_args_value =
+            args_clause.then(|| quote!(self.args.as_deref().unwrap_or_default(),));
+        let slow_caller = caller.then(|| quote!(self.caller,));
+        let slow_caller_fn = caller_fn.then(|| quote!(self.caller_fn,));
+        let event_entry = dispatched(
+            &args,
+            audited_event(
+                EventKind::Entry,
+                level,
+                quote! {
+                    #exit_tenant
+                    #exit_context
+                    #static_fields
+                    #exit_depth
+                    #thread_fields
+                    #task_field
+                },
+                quote! {
+                    #entry_format,
+                    #exit_indent
+                    #fn_name_str,
+                    #exit_generics
+                    __tracing_fn_id,
+                    #slow_args_value
+                    #slow_caller
+                    #slow_caller_fn
+                    #entry_location
+                },
+            ),
+        );
+        quote! {
+            if __tracing_fn_duration >= ::std::time::Duration::from_nanos(#threshold) {
+                #event_entry
+                #event_exit
+            }
+        }
+    } else if err_only {
+        quote! {
+            if self.failed || __tracing_fn_panicking {
+                #event_exit
+            }
+        }
+    } else if entry_only {
+        quote!()
+    } else {
+        event_exit
+    };
+
+    // clock: 使用自定义的计时函数 (以微秒为单位) 代替 `Instant`
+    let (start_ty, start, elapsed) = match &args.clock {
+        Some(clock) => (
+            quote!(u64),
+            quote!(#clock()),
+            quote!(::std::time::Duration::from_micros(#clock().saturating_sub(self.start))),
+        ),
+        None => (
+            quote!(::tracing_fn::runtime::Instant),
+            quote!(::tracing_fn::runtime::Instant::now()),
+            quote!(self.start.elapsed()),
+        ),
+    };
+
+    let (cpu_field, cpu_init) = if measure.cpu() {
+        (
+            quote!(cpu_start: ::tracing_fn::runtime::CpuTime,),
+            quote!(cpu_start: ::tracing_fn::runtime::CpuTime::now(),),
+        )
+    } else {
+        (quote!(), quote!())
+    };
+
+    let (alloc_field, alloc_init, alloc_elapsed) = if allocs {
+        (
+            quote!(alloc_start: ::tracing_fn::runtime::AllocSnapshot,),
+            quote!(alloc_start: ::tracing_fn::runtime::AllocSnapshot::now(),),
+            quote!(let __tracing_fn_allocs = self.alloc_start.elapsed();),
+        )
+    } else {
+        (quote!(), quote!(), quote!())
+    };
+
+    let residual = truncated(&args, quote!(residual(&value)));
+
+    // level_fn: 在入口处求值一次, 守卫保存该等级供退出事件使用
+    let (level_eval, level_field, level_init, level_restore) = match &args.level_fn {
+        Some(level_fn) => (
+            quote!(let __tracing_fn_level: #tracing::Level = #level_fn;),
+            quote!(level: #tracing::Level,),
+            quote!(level: __tracing_fn_level,),
+            quote!(let __tracing_fn_level = self.level;),
+        ),
+        None => (quote!(), quote!(), quote!(), quote!()),
+    };
+
+    // 退出事件由守卫在析构时输出, 无论函数经由哪条路径返回 (包括 panic 时的栈展开) 都会输出
+    let guard = quote! {
+        struct __TracingFnGuard #err_code_params {
+            start: #start_ty,
+            /// 不输出事件时为 `None`
+            id: ::std::option::Option<::tracing_fn::InvocationId>,
+            /// 返回值的格式化结果, 较短时不分配内存
+            ret: ::std::option::Option<::tracing_fn::runtime::InlineString>,
+            failed: bool,
+            #level_field
+            #caller_field
+            #caller_fn_field
+            #generics_field
+            #paired_field
+            #tenant_field
+            #context_field
+            #frame_field
+            #cpu_field
+            #alloc_field
+            #binary_field
+            #ret_field
+            #err_code_field
+        }
+
+        impl #err_code_params __TracingFnGuard #err_code_args {
+            #[allow(dead_code)]
+            fn record<T: ::std::fmt::Debug + ?::core::marker::Sized>(&mut self, value: &T) {
+                if self.id.is_some() #ret_visible {
+                    self.ret = ::std::option::Option::Some(
+                        ::tracing_fn::runtime::InlineString::debug(value),
+                    );
+                }
+            }
+
+            /// ret_with: 只在输出事件时调用格式化函数
+            #[allow(dead_code)]
+            fn record_with<T: ::std::fmt::Display>(&mut self, format: impl ::core::ops::FnOnce() -> T) {
+                if self.id.is_some() #ret_visible {
+                    self.ret = ::std::option::Option::Some(
+                        ::tracing_fn::runtime::InlineString::display(&format()),
+                    );
+                }
+            }
+
+            #[allow(dead_code)]
+            fn observe<T: ::tracing_fn::runtime::TryValue>(
+                &mut self,
+                value: T,
+                residual: impl ::core::ops::FnOnce(&T) -> ::tracing_fn::runtime::MaybeDebug<'_>,
+            ) -> T {
+                if value.is_break() {
+                    self.failed = true;
+                    if self.id.is_some() #ret_visible {
+                        self.ret = ::std::option::Option::Some(
+                            ::tracing_fn::runtime::InlineString::debug(&#residual),
+                        );
+                    }
+                }
+                value
+            }
+        }
+
+        impl #err_code_params ::std::ops::Drop for __TracingFnGuard #err_code_args {
+            fn drop(&mut self) {
+                self.finish();
+            }
+        }
+
+        impl #err_code_params __TracingFnGuard #err_code_args {
+            /// process_exit: `process::exit` 与 `process::abort` 不运行析构函数, 在调用之前输出退出事件
+            #[allow(dead_code)]
+            fn process_exit(&mut self, code: ::std::option::Option<i32>) {
+                if self.id.is_some() #ret_visible {
+                    self.ret = ::std::option::Option::Some(match code {
+                        ::std::option::Option::Some(code) => ::tracing_fn::runtime::InlineString::display(
+                            &::std::format_args!("<exit({})>", code),
+                        ),
+                        ::std::option::Option::None => {
+                            ::tracing_fn::runtime::InlineString::display("<abort>")
+                        }
+                    });
+                }
+                self.failed |= code != ::std::option::Option::Some(0);
+                self.finish();
+                // 调用返回时 (例如被同名的函数遮蔽) 析构函数不再重复输出
+                self.id = ::std::option::Option::None;
+            }
+
+            fn finish(&mut self) {
+                #[allow(unused_variables)]
+                let __tracing_fn_duration = #elapsed;
+                #alloc_elapsed
+                let __tracing_fn_panicking = ::std::thread::panicking();
+                #depth_save
+                #recorded_take
+                #[allow(unused_variables)]
+                let __tracing_fn_calls = #frame_exit;
+                #usdt_exit
+                #binary_exit
+                #level_restore
+                if let ::std::option::Option::Some(__tracing_fn_id) = &self.id {
+                    #main_stats
+                    #[allow(unused_variables)]
+                    let __tracing_fn_ret = match &self.ret {
+                        ::std::option::Option::Some(ret) => ret.as_str(),
+                        ::std::option::Option::None if __tracing_fn_panicking => "<panicked>",
+                        #ret_omitted
+                        ::std::option::Option::None => "<unknown>",
+                    };
+                    #event_exit
+                }
+            }
+        }
+    };
+
+    // 记录返回值, 返回 Result 的函数在返回 Err 时计为失败的调用
+    let mut record = TokenStream::new();
+    // ret_with: 使用自定义的格式化函数代替返回值的 Debug 输出, `?` 提前返回时仍输出其中导致返回的部分
+    let record_ret = match (&args.ret_with, args.skip_ret) {
+        (_, Some(true)) => quote!(),
+        (Some(format), _) => {
+            let formatted = truncated(&args, quote!(#format(&__tracing_fn_ret)));
+            quote!(__tracing_fn_guard.record_with(|| #formatted);)
+        }
+        (None, _) => {
+            let debug = truncated(&args, debug_value(quote!(__tracing_fn_ret)));
+            quote!(__tracing_fn_guard.record(&#debug);)
+        }
+    };
+    // err: 返回 Err 时以 Display 输出错误及其 source() 链或以 `{:#}` 输出错误, 返回 Ok 时不变
+    if err_mode != ErrMode::Off && args.skip_ret != Some(true) {
+        let chain = if err_mode == ErrMode::Chain {
+            quote! {{
+                #[allow(unused_imports)]
+                use ::tracing_fn::runtime::{ViaDerefError as _, ViaError as _};
+                (&&::tracing_fn::runtime::ErrSource(err)).__tracing_fn_err_chain()
+            }}
+        } else {
+            quote!(::tracing_fn::runtime::ErrAlternate(err))
+        };
+        let chain = truncated(&args, chain);
+        record.extend(quote! {
+            if let ::std::result::Result::Err(err) = &__tracing_fn_ret {
+                __tracing_fn_guard.record_with(|| #chain);
+            } else {
+                #record_ret
+            }
+        });
+    } else {
+        record.extend(record_ret);
+    }
+    if returns_result(sig) {
+        record.extend(quote! {
+            __tracing_fn_guard.failed |= ::std::result::Result::is_err(&__tracing_fn_ret);
+        });
+    }
+    // err_code: 在返回 Err 时求值
+    if let Some(code) = &args.err_code {
+        record.extend(quote! {
+            if let ::std::result::Result::Err(err) = &__tracing_fn_ret {
+                __tracing_fn_guard.err_code = ::std::option::Option::Some(#code);
+            }
+        });
+    }
+    // err_code 与 err: `?` 展开为 match 以便取得转换后的错误
+    let try_record = (args.err_code.is_some() || err_mode != ErrMode::Off).then(|| record.clone());
+    // record: 只记录正常返回的调用, 经由 `?` 提前返回或 panic 时不记录
+    if replay {
+        record.extend(quote! {
+            ::tracing_fn::runtime::replay_append(
+                #qualified_name,
+                &__tracing_fn_replay_args,
+                &__tracing_fn_ret,
+            );
+        });
+    }
+
+    let ret_ty = explicit_return_type(sig);
+    let mut body = rewrite_body(
+        block,
+        ret_ty.as_ref(),
+        &record,
+        try_record.as_ref(),
+        args.process_exit != Some(false),
+    );
+    if let Some(tick) = &batch_tick {
+        rewrite_batch_ticks(&mut body.block, tick);
+    }
+    let body = if returns_never(sig) {
+        // 永不返回的函数只需要守卫处理 panic
+        body.never()
+    } else {
+        body.eval(ret_ty.as_ref(), is_async, &record)
+    };
+
+    // slow_poll: 将 async fn 的函数体包装为记录每次 poll 耗时的 future,
+    // 最长的单次 poll 超过阈值时以 warn 等级输出, 不受函数日志等级的限制
+    let body = match args.slow_poll {
+        Some(threshold) if is_async => {
+            let threshold = threshold.as_nanos() as u64;
+            let event_slow_poll = dispatched(
+                &args,
+                event(
+                    &args,
+                    EventKind::Event,
+                    "warn",
+                    quote!(),
+                    quote! {
+                        "!!! [{}] #SlowPoll: longest poll {:?} exceeded {:?}, polls: {}",
+                        #fn_name_str,
+                        __tracing_fn_polls.longest,
+                        __tracing_fn_threshold,
+                        __tracing_fn_polls.polls
+                    },
+                ),
+            );
+            // 不使用 `move`, 守卫只被借用, child_span 附加到该 future 上时退出事件仍在 span 之外输出
+            quote! {
+                let (__tracing_fn_ret, __tracing_fn_polls) =
+                    ::tracing_fn::runtime::PollTimer::new(async { #body }).await;
+                let __tracing_fn_threshold = ::std::time::Duration::from_nanos(#threshold);
+                if __tracing_fn_polls.longest > __tracing_fn_threshold {
+                    #event_slow_poll
+                }
+                __tracing_fn_ret
+            }
+        }
+        _ => body,
+    };
+    let body = wrap_child_span(&args, &level_ident, is_async, body);
+    let (span_enter, body) = fn_span(&args, &level_ident, sig, body);
+
+    // first: 只输出前 N 次会被记录的调用, 超过时输出一条提示
+    let first = args.first.map(|limit| {
+        let event_notice = dispatched(
+            &args,
+            event(
+                &args,
+                EventKind::Event,
+                level,
+                static_fields.clone(),
+                quote! {
+                    "=== [{}] #First: further events suppressed after {} calls",
+                    #fn_name_str,
+                    #limit
+                },
+            ),
+        );
+        quote! {
+            static __TRACING_FN_FIRST: ::tracing_fn::runtime::FirstN =
+                ::tracing_fn::runtime::FirstN::new(#limit);
+            let __tracing_fn_enabled = __tracing_fn_enabled
+                && match __TRACING_FN_FIRST.admit() {
+                    ::tracing_fn::runtime::FirstDecision::Emit => true,
+                    ::tracing_fn::runtime::FirstDecision::Notice => {
+                        #event_notice
+                        false
+                    }
+                    ::tracing_fn::runtime::FirstDecision::Suppress => false,
+                };
+        }
+    });
+    // dedup: 参数与上一次调用相同时不输出该调用的事件, 参数变化时汇总省略的次数
+    // 比较时使用未压缩的完整参数, 输出的入口事件另行格式化
+    let dedup = (args.dedup == Some(true)).then(|| {
+        let event_repeated = dispatched(
+            &args,
+            event(
+                &args,
+                EventKind::Event,
+                level,
+                static_fields.clone(),
+                quote! {
+                    "=== [{}] #Dedup: repeated x{}",
+                    #fn_name_str,
+                    __tracing_fn_repeated
+                },
+            ),
+        );
+        quote! {
+            static __TRACING_FN_DEDUP: ::tracing_fn::runtime::Dedup =
+                ::tracing_fn::runtime::Dedup::new();
+            let __tracing_fn_enabled = __tracing_fn_enabled && {
+                let __tracing_fn_dedup = __TRACING_FN_DEDUP.observe(#args_str);
+                if let ::std::option::Option::Some(__tracing_fn_repeated) = __tracing_fn_dedup.repeated {
+                    #event_repeated
+                }
+                __tracing_fn_dedup.emit
+            };
+        }
+    });
+    // condition: 只在事件会被记录时求值, 此时参数尚未被函数体使用
+    let condition = args
+        .condition
+        .as_ref()
+        .map(|condition| quote!(&& (#condition)));
+    // err_level: 只启用了更高的等级时同样记录调用, 以便在返回 Err 时输出退出事件
+    let enabled = match &args.err_level {
+        Some(err_level) => {
+            let base = dispatched(&args, enabled(&args, level));
+            let err = dispatched(&args, enabled(&args, err_level));
+            quote!((#base || #err))
+        }
+        None => dispatched(&args, enabled(&args, level)),
+    };
+
+    let autoref_imports = autoref_imports();
+    let instrumented = quote! {
+        #caller_eval
+        #generics_eval
+        #autoref_imports
+        #level_eval
+        #audit_chain
+
+        // 参数格式化开销较大, 仅在该等级的事件会被记录时才进行
+        let __tracing_fn_enabled = #enabled #condition;
+        #once_per
+        #dedup
+        #first
+        #pressure
+        #verbosity
+        #abort_safe
+        #tenant
+        #context
+        #replay_args
+        #span_enter
+        #frame_enter
+        #caller_fn_eval
+        #usdt_entry
+        #binary_entry
+
+        #entry
+        #batch
+
+        #guard
+
+        #[allow(unused_mut)]
+        let mut __tracing_fn_guard = __TracingFnGuard {
+            start: #start,
+            id: __tracing_fn_id,
+            ret: ::std::option::Option::None,
+            failed: false,
+            #level_init
+            #caller_init
+            #caller_fn_init
+            #generics_init
+            #paired_init
+            #tenant_init
+            #context_init
+            #frame_init
+            #cpu_init
+            #alloc_init
+            #binary_init
+            #ret_init
+            #err_code_init
+        };
+
+        #body
+    };
+
+    let instrumented = wrap(
+        &args,
+        &outer_attrs,
+        &inner_attrs,
+        &vis,
+        sig,
+        stmts,
+        instrumented,
+    );
+    quote! {
+        #instrumented
+        #companion
+    }
+}
+
+/// generics: 取得函数的类型参数的类型名, 返回求值 `__tracing_fn_generics` 的语句与其类型, 没有类型参数时返回 `None`
+pub(crate) fn generic_args(args: &Args, sig: &Signature) -> Option<(TokenStream, TokenStream)> {
+    if args.generics != Some(true) {
+        return None;
+    }
+    let params: Vec<_> = sig
+        .generics
+        .type_params()
+        .map(|param| &param.ident)
+        .collect();
+    if params.is_empty() {
+        return None;
+    }
+    let len = params.len();
+    Some((
+        quote! {
+            let __tracing_fn_generics = ::tracing_fn::runtime::GenericArgs([
+                #(::core::any::type_name::<#params>()),*
+            ]);
+        },
+        quote!(::tracing_fn::runtime::GenericArgs<#len>),
+    ))
+}
+
+/// 事件中标识函数的名称, `qualified = true` 时为包含模块路径的全限定名 (例如 `my_crate::auth::login`)
+///
+/// 模块路径由 `module_path!()` 在展开处求值。设置 `self_type` 时方法名之前附带类型名,
+/// 全限定名中 `#[tracing_impl]` 的方法总是附带类型名。
+pub(crate) fn display_name(args: &Args, sig: &Signature) -> TokenStream {
+    let fn_name = sig.ident.to_string();
+    if args.qualified != Some(true) {
+        let name = match args.self_type_name() {
+            Some(ty) => format!("{ty}::{fn_name}"),
+            None => fn_name,
+        };
+        return quote!(#name);
+    }
+    let name = match args.self_type_name().or(args.impl_type()) {
+        Some(ty) => format!("::{ty}::{fn_name}"),
+        None => format!("::{fn_name}"),
+    };
+    quote!(::core::concat!(::core::module_path!(), #name))
+}
+
+/// span: 为每次调用创建以函数名命名的 span, 返回进入 span 的语句与包装后的函数体
+///
+/// 同步函数在入口事件之前进入 span, 退出事件输出之后离开, 被追踪的嵌套调用因此成为该 span 的子 span,
+/// `tracing-flame` 等按 span 统计耗时的工具可以据此得到调用树。
+/// async fn 的 span 通过 `Instrument` 只附加到函数体上, 入口与退出事件位于 span 之外。
+/// span 绑定到 `handle` 指定的变量, `record` 中的字段声明为空值, 由函数体通过 `Span::record` 填充。
+pub(crate) fn fn_span(
+    args: &Args,
+    level_ident: &Ident,
+    sig: &Signature,
+    body: TokenStream,
+) -> (TokenStream, TokenStream) {
+    let tracing = args.tracing_path();
+    let Some(fn_span) = args.enabled_span() else {
+        return (quote!(), body);
+    };
+    let handle = &fn_span.handle;
+    let record = &fn_span.record;
+    let fn_name = &sig.ident;
+    let fn_name_str = fn_name.to_string();
+    // 使用函数名的 span, 使 span 的源码位置指向函数定义
+    let span = quote_spanned! {fn_name.span()=>
+        #tracing::span!(
+            #tracing::Level::#level_ident,
+            #fn_name_str,
+            #(#record = #tracing::field::Empty),*
+        )
+    };
+    if sig.asyncness.is_some() {
+        (
+            quote!(let #handle = #span;),
+            quote! {
+                #tracing::Instrument::instrument(async { #body }, #handle.clone()).await
+            },
+        )
+    } else {
+        (
+            quote! {
+                let #handle = #span;
+                let __tracing_fn_span_entered = #handle.enter();
+            },
+            body,
+        )
+    }
+}
+
+/// child_span: 在函数体外创建以指定名称命名的 span, 函数自身的入口与退出事件位于该 span 之外
+///
+/// async fn 的 span 通过 `Instrument` 附加到函数体对应的 future 上, 避免在 await 点之间持有 span 的进入状态。
+/// 该 future 不使用 `move`, 守卫只被借用, 退出事件因此仍在 span 之外输出。
+pub(crate) fn wrap_child_span(
+    args: &Args,
+    level_ident: &Ident,
+    is_async: bool,
+    body: TokenStream,
+) -> TokenStream {
+    let Some(name) = &args.child_span else {
+        return body;
+    };
+    let tracing = args.tracing_path();
+    let span = quote!(#tracing::span!(#tracing::Level::#level_ident, #name));
+    if is_async {
+        quote! {
+            #tracing::Instrument::instrument(async { #body }, #span).await
+        }
+    } else {
+        quote! {
+            let __tracing_fn_child_span = #span;
+            let __tracing_fn_child_entered = __tracing_fn_child_span.enter();
+            #body
+        }
+    }
+}
+
+/// `skip` 中的参数的输出, 实现了 `Display`
+///
+/// `redact = "sha256"` 时输出参数 `Debug` 输出的哈希前缀, 可以关联重复出现的值而不泄露明文。
+/// `redact = "omit"` 时返回 `None`, 参数不出现在事件中。
+pub(crate) fn redacted(args: &Args, ident: &Ident) -> Option<TokenStream> {
+    match args.redact {
+        Some(Redact::Sha256) => Some(quote!(::tracing_fn::runtime::Sha256Prefix::of(&#ident))),
+        Some(Redact::Omit) => None,
+        Some(Redact::Mask) | None => {
+            let placeholder = args.redacted_as.as_deref().unwrap_or("***");
+            Some(quote!(#placeholder))
+        }
+    }
+}
+
+/// 参数或返回值的 `Debug` 输出, 未实现 `Debug` 时输出 `<non-Debug 类型名>`, 需要 [`autoref_imports`] 引入的 trait
+pub(crate) fn debug_value(value: TokenStream) -> TokenStream {
+    quote!((&&::tracing_fn::runtime::DebugWrap(&#value)).__tracing_fn_debug())
+}
+
+/// 函数指针与闭包参数的类型名, 实现了 `Debug`
+pub(crate) fn callable_value(ident: &Ident) -> TokenStream {
+    quote!(::tracing_fn::runtime::MaybeDebug::Callable(::core::any::type_name_of_val(&#ident)))
+}
+
+/// max_len: 把值包装为超过最大字节数时截断输出的 `Truncate`, 未设置时原样返回
+pub(crate) fn truncated(args: &Args, value: TokenStream) -> TokenStream {
+    match args.max_len {
+        Some(max_len) => quote!(::tracing_fn::runtime::Truncate(#value, #max_len)),
+        None => value,
+    }
+}
+
+/// `summarize` 中的参数的摘要, 实现了 `Display`, 需要 [`autoref_imports`] 引入的 trait
+pub(crate) fn summary_value(ident: &Ident) -> TokenStream {
+    quote!((&&::tracing_fn::runtime::SummaryWrap(&#ident)).__tracing_fn_summary())
+}
+
+/// 引入 [`debug_value`]、[`summary_value`] 与函数体中改写后的 `?` 通过 autoref 选择实现所需的 trait
+pub(crate) fn autoref_imports() -> TokenStream {
+    quote! {
+        #[allow(unused_imports)]
+        use ::tracing_fn::runtime::{
+            ViaDebug as _, ViaFmtBreak as _, ViaOpaque as _, ViaSummarize as _, ViaTypeName as _,
+        };
+    }
+}
+
+/// `fields(...)`、`priority` 与 `span_id` 参数对应的结构化字段
+pub(crate) fn static_fields(args: &Args) -> TokenStream {
+    let tracing = args.tracing_path();
+    let fields = args
+        .fields
+        .iter()
+        .map(|(name, value)| quote!(#name = #value,));
+    // priority: 指定时作为字段输出, 供下游的采样与限流按优先级取舍
+    let priority = args.priority.map(|priority| {
+        let priority = priority.name();
+        quote!(priority = #priority,)
+    });
+    // span_id: 输出事件时的当前 span, 没有 span 时不记录该字段
+    let span_id = (args.span_id == Some(true))
+        .then(|| quote!(span.id = #tracing::Span::current().id().map(|id| id.into_u64()),));
+    quote!(#(#fields)* #priority #span_id)
+}
+
+/// 事件的类别, etw 后端据此把入口与退出事件分别写为 Start 与 Stop
+#[derive(Clone, Copy)]
+pub(crate) enum EventKind {
+    Entry,
+    Exit,
+    Event,
+}
+
+/// 生成以指定等级输出事件的语句
+///
+/// `fields` 为结构化字段, 使用 log、stderr 或 etw 后端时不输出。`message` 为格式字符串及其参数。
+pub(crate) fn event(
+    args: &Args,
+    kind: EventKind,
+    level: &str,
+    fields: TokenStream,
+    message: TokenStream,
+) -> TokenStream {
+    let tracing = args.tracing_path();
+    match args.backend {
+        Some(Backend::Log) => {
+            let level = log_level(level);
+            quote!(::log::log!(::log::Level::#level, #message);)
+        }
+        Some(Backend::Etw) => {
+            let level = etw_level(level);
+            let kind = match kind {
+                EventKind::Entry => quote!(Entry),
+                EventKind::Exit => quote!(Exit),
+                EventKind::Event => quote!(Event),
+            };
+            quote! {
+                ::tracing_fn::runtime::etw_write(
+                    ::tracing_fn::runtime::EtwKind::#kind,
+                    #level,
+                    ::std::module_path!(),
+                    ::std::format_args!(#message),
+                );
+            }
+        }
+        Some(Backend::Stderr) => {
+            let level = format!("{:<5}", level.to_uppercase());
+            quote! {
+                ::std::eprintln!(
+                    "{} {}: {}",
+                    #level,
+                    ::std::module_path!(),
+                    ::std::format_args!(#message)
+                );
+            }
+        }
+        _ if level == DYNAMIC_LEVEL => dynamic_level(
+            &tracing,
+            |level| quote!(#tracing::event!(#tracing::Level::#level, #fields #message)),
+        ),
+        _ => {
+            let level = Ident::new(&level.to_uppercase(), Span::call_site());
+            quote!(#tracing::event!(#tracing::Level::#level, #fields #message);)
+        }
+    }
+}
+
+/// 生成判断指定等级的事件是否会被记录的表达式
+pub(crate) fn enabled(args: &Args, level: &str) -> TokenStream {
+    let tracing = args.tracing_path();
+    match args.backend {
+        Some(Backend::Log) => {
+            let level = log_level(level);
+            quote!(::log::log_enabled!(::log::Level::#level))
+        }
+        // stderr 后端没有 subscriber, 满足启用条件时总是输出
+        Some(Backend::Stderr) => quote!(true),
+        Some(Backend::Etw) => {
+            let level = etw_level(level);
+            quote!(::tracing_fn::runtime::etw_enabled(#level))
+        }
+        _ if level == DYNAMIC_LEVEL => dynamic_level(
+            &tracing,
+            |level| quote!(#tracing::enabled!(#tracing::Level::#level)),
+        ),
+        _ => {
+            let level = Ident::new(&level.to_uppercase(), Span::call_site());
+            quote!(#tracing::enabled!(#tracing::Level::#level))
+        }
+    }
+}
+
+/// `level_fn` 时传给 [`event`] 与 [`enabled`] 的等级, 由调用时求值的 `__tracing_fn_level` 决定
+pub(crate) const DYNAMIC_LEVEL: &str = "dynamic";
+
+/// tracing 的事件等级必须是常量, 按 `__tracing_fn_level` 的取值分别展开
+fn dynamic_level(tracing: &TokenStream, expand: impl Fn(Ident) -> TokenStream) -> TokenStream {
+    let arms = ["ERROR", "WARN", "INFO", "DEBUG"].map(|name| {
+        let level = Ident::new(name, Span::call_site());
+        let expanded = expand(level.clone());
+        quote!(#tracing::Level::#level => #expanded,)
+    });
+    let trace = expand(Ident::new("TRACE", Span::call_site()));
+    quote! {
+        match __tracing_fn_level {
+            #(#arms)*
+            _ => #trace,
+        }
+    }
+}
+
+/// `log::Level` 中对应的变体, 例如 `trace` 对应 `Trace`
+fn log_level(level: &str) -> Ident {
+    let mut chars = level.chars();
+    let name = chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+        .unwrap_or_default();
+    Ident::new(&name, Span::call_site())
+}
+
+/// ETW 中对应的等级, debug 与 trace 均为 Verbose
+fn etw_level(level: &str) -> u8 {
+    match level {
+        "error" => 2,
+        "warn" => 3,
+        "info" => 4,
+        _ => 5,
+    }
+}
+
+/// dispatch: 在指定的 `Dispatch` 下执行输出事件或检查等级的代码, 不影响函数体中的事件
+///
+/// 未设置 `dispatch` 时原样返回, 使用当前的默认 subscriber。
+pub(crate) fn dispatched(args: &Args, tokens: TokenStream) -> TokenStream {
+    let tracing = args.tracing_path();
+    match &args.dispatch {
+        Some(dispatch) => quote! {
+            #tracing::dispatcher::with_default(&#dispatch, || { #tokens })
+        },
+        None => tokens,
+    }
+}
+
+/// 根据 `force` 与 `cfg` 参数, 把添加了 tracing 的函数体与原函数体组合为最终的函数
+fn wrap(
+    args: &Args,
+    outer_attrs: &[&Attribute],
+    inner_attrs: &[&Attribute],
+    vis: &TokenStream,
+    sig: &Signature,
+    stmts: &[syn::Stmt],
+    instrumented: TokenStream,
+) -> TokenStream {
+    let doc = doc::instrumentation_doc(args, outer_attrs, sig);
+    let env_dependencies = env_dependencies();
+    // test_subscriber: 在入口事件之前初始化, 已有全局 subscriber 时不做任何事
+    let instrumented = match args.test_subscriber {
+        Some(true) => quote! {
+            ::tracing_fn::runtime::init_test_subscriber();
+            #instrumented
+        },
+        _ => instrumented,
+    };
+    // tracing 的 release_max_level_* feature 在 release 模式下移除了该等级的事件, 此时 force 不再生效
+    let level = match args.level_fn {
+        Some(_) => DYNAMIC_LEVEL,
+        None => args.level.as_deref().unwrap_or("trace"),
+    };
+    let debug_only = static_level(args, level) == StaticLevel::DebugOnly;
+    // caller: 调用方的位置需要 `#[track_caller]`
+    let track_caller = (args.caller == Some(true)).then(|| quote!(#[track_caller]));
+    // 根据force参数决定是否在release模式下强制启用
+    if args.force == Some(true) && !debug_only {
+        // 如果force=true，则无论什么模式都启用tracing
+        quote! {
+            #(#outer_attrs)*
+            #doc
+            #track_caller
+            #vis #sig {
+                #(#inner_attrs)*
+                #env_dependencies
+                #instrumented
+            }
+        }
+    } else {
+        // 否则仅在满足cfg条件时启用tracing，默认为debug模式
+        let cfg = match &args.cfg {
+            Some(cfg) if debug_only => quote!(all(#cfg, debug_assertions)),
+            Some(cfg) => quote!(#cfg),
+            None => quote!(debug_assertions),
+        };
+        quote! {
+            #(#outer_attrs)*
+            #doc
+            #track_caller
+            #vis #sig {
+                #(#inner_attrs)*
+                #env_dependencies
+
+                #[cfg(#cfg)]
+                {
+                    #instrumented
+                }
+
+                // 不满足cfg条件时直接执行原函数
+                #[cfg(not(#cfg))]
+                {
+                    #(#stmts)*
+                }
+            }
+        }
+    }
+}
+
+/// 不输出入口事件的模式: 只输出一条合并事件的 `paired`、`err_only` 与只输出退出事件的 `exit_only`
+fn single_event_mode(args: &Args) -> Option<&'static str> {
+    if args.err_only == Some(true) {
+        Some("err_only")
+    } else if args.paired == Some(true) {
+        Some("paired")
+    } else if args.exit_only == Some(true) {
+        Some("exit_only")
+    } else {
+        None
+    }
+}
+
+/// 不在入口处输出入口事件的模式: [`single_event_mode`] 与在退出时补发入口事件的 `entry_on_slow`
+fn deferred_entry_mode(args: &Args) -> Option<&'static str> {
+    single_event_mode(args).or(args.entry_on_slow.is_some().then_some("entry_on_slow"))
+}
+
+/// tracing 的静态最高等级对某个等级的事件的影响
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StaticLevel {
+    /// 未被移除
+    Enabled,
+    /// 只在 debug 模式下保留 (`release_max_level_*`)
+    DebugOnly,
+    /// 总是被移除 (`max_level_*`)
+    Disabled,
+}
+
+/// 根据转发给 tracing 的 `max_level_*` 与 `release_max_level_*` feature 判断该等级的事件是否在编译期被移除
+///
+/// 与 tracing 相同, 同时启用多个 feature 时以最严格的为准。只作用于 tracing 后端。
+pub(crate) fn static_level(args: &Args, level: &str) -> StaticLevel {
+    if !matches!(args.backend, None | Some(Backend::Tracing)) {
+        return StaticLevel::Enabled;
+    }
+    // 按 error、warn、info、debug、trace 的顺序, 前 n 个等级保留
+    // 调用时决定的等级中被移除的部分由 tracing 处理, 只有全部等级都被移除时才保留原函数
+    let level = if level == DYNAMIC_LEVEL {
+        "error"
+    } else {
+        level
+    };
+    let rank = |level: &str| {
+        ["error", "warn", "info", "debug", "trace"]
+            .iter()
+            .position(|name| *name == level)
+            .map_or(5, |index| index + 1)
+    };
+    // err_level: 返回 Err 时的退出事件使用更高的等级, 按两者中更严重的等级判断
+    let rank = match &args.err_level {
+        Some(err_level) => rank(level).min(rank(err_level)),
+        None => rank(level),
+    };
+    let max_level = [
+        cfg!(feature = "max_level_off"),
+        cfg!(feature = "max_level_error"),
+        cfg!(feature = "max_level_warn"),
+        cfg!(feature = "max_level_info"),
+        cfg!(feature = "max_level_debug"),
+        cfg!(feature = "max_level_trace"),
+    ]
+    .iter()
+    .position(|enabled| *enabled);
+    let release_max_level = [
+        cfg!(feature = "release_max_level_off"),
+        cfg!(feature = "release_max_level_error"),
+        cfg!(feature = "release_max_level_warn"),
+        cfg!(feature = "release_max_level_info"),
+        cfg!(feature = "release_max_level_debug"),
+        cfg!(feature = "release_max_level_trace"),
+    ]
+    .iter()
+    .position(|enabled| *enabled);
+    if max_level.is_some_and(|max| rank > max) {
+        StaticLevel::Disabled
+    } else if release_max_level.is_some_and(|max| rank > max) {
+        StaticLevel::DebugOnly
+    } else {
+        StaticLevel::Enabled
+    }
+}
+
+/// 校验与 feature 无关的参数组合与函数签名
+///
+/// 在 `disabled` feature、白名单与 `max_level_*` 的提前返回之前调用, 无效的参数在所有构建中都报错。
+fn validate(args: &Args, sig: &Signature) -> syn::Result<()> {
+    if let Some(constness) = &sig.constness {
+        return Err(syn::Error::new_spanned(
+            constness,
+            "#[tracing_fn] 不支持 const fn",
+        ));
+    }
+    check_reserved(sig)?;
+
+    // log、stderr 与 etw 后端不支持结构化字段与 span, 只依赖这些功能的参数不可用
+    if let Some(backend @ (Backend::Log | Backend::Stderr | Backend::Etw)) = args.backend {
+        let unsupported = [
+            ("tenant", args.tenant == Some(true)),
+            ("thread", args.thread == Some(true)),
+            ("span_id", args.span_id == Some(true)),
+            ("span", args.enabled_span().is_some()),
+            ("child_span", args.child_span.is_some()),
+            ("dispatch", args.dispatch.is_some()),
+            ("audit", args.audit == Some(true)),
+            ("with", args.with.is_some()),
+            ("fields", !args.fields.is_empty()),
+            ("err_code", args.err_code.is_some()),
+            ("json", !args.json.is_empty()),
+            ("valuable", !args.valuable.is_empty()),
+            ("level_fn", args.level_fn.is_some()),
+        ];
+        if let Some((name, _)) = unsupported.iter().find(|(_, used)| *used) {
+            return Err(syn::Error::new_spanned(
+                &sig.ident,
+                format!("`backend = \"{}\"` 时不支持 `{name}` 参数", backend.name()),
+            ));
+        }
+    }
+
+    check_strict_args(args, sig)?;
+
+    // 函数的 span 创建于当前的默认 subscriber, 与 dispatch 指定的 Dispatch 无法形成父子关系
+    if args.enabled_span().is_some() && args.dispatch.is_some() {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "`span` 与 `dispatch` 不能同时使用",
+        ));
+    }
+    // span_id 取自默认 subscriber 中的当前 span, 对 dispatch 指定的 Dispatch 没有意义
+    if args.span_id == Some(true) && args.dispatch.is_some() {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "`span_id` 与 `dispatch` 不能同时使用",
+        ));
+    }
+
+    // dedup 会省略整个调用, 与只在失败时输出的 err_only 一起使用时可能丢失失败的调用
+    if args.dedup == Some(true) && args.err_only == Some(true) {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "`dedup` 与 `err_only` 不能同时使用",
+        ));
+    }
+
+    // entry_only 与 exit_only 只保留其中一个事件, 与合并或依赖退出事件的参数互斥
+    let entry_only = args.entry_only == Some(true);
+    let exit_only = args.exit_only == Some(true);
+    let conflict = if entry_only && exit_only {
+        Some(("entry_only", "exit_only"))
+    } else {
+        let with = [
+            ("paired", args.paired == Some(true)),
+            ("err_only", args.err_only == Some(true)),
+            ("err_level", args.err_level.is_some() && entry_only),
+        ];
+        let mode = if entry_only {
+            "entry_only"
+        } else {
+            "exit_only"
+        };
+        with.iter()
+            .find(|(_, used)| (entry_only || exit_only) && *used)
+            .map(|(name, _)| (mode, *name))
+    };
+    if let Some((mode, name)) = conflict {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            format!("`{mode}` 与 `{name}` 不能同时使用"),
+        ));
+    }
+    // entry_on_slow: 入口事件推迟到退出时决定是否输出, 需要同时保留入口与退出事件
+    if args.entry_on_slow.is_some()
+        && let Some(mode) = single_event_mode(args).or(entry_only.then_some("entry_only"))
+    {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            format!("`entry_on_slow` 与 `{mode}` 不能同时使用"),
+        ));
+    }
+
+    // self_type: 单独标注的方法无法得知所在 impl 块的类型
+    if args.self_type == Some(SelfType::Detect) && args.impl_block.is_none() {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "`self_type = true` 只能用于 #[tracing_impl] 中的方法, 单独标注时需要指定类型名, 例如 `self_type = \"UserService\"`",
+        ));
+    }
+    let is_async = sig.asyncness.is_some();
+    // caller: async fn 上的 `#[track_caller]` 尚未稳定
+    if args.caller == Some(true) && is_async {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "`caller` 不支持 async fn",
+        ));
+    }
+    // caller_fn: 栈帧保存在线程局部变量中, async fn 不参与
+    if args.caller_fn == Some(true) && is_async {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "`caller_fn` 不支持 async fn",
+        ));
+    }
+    // allocs 与 measure = "cpu" 基于线程局部变量与线程的 CPU 时间, 只支持同步函数
+    if args.allocs == Some(true) && is_async {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "async fn 可能在线程间切换, 不支持统计内存分配",
+        ));
+    }
+    if args.measure.is_some_and(Measure::cpu) && is_async {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "async fn 可能在线程间切换, 不支持测量 CPU 时间",
+        ));
+    }
+
+    // err_code: 错误码从函数返回的错误中取得
+    if args.err_code.is_some() && !returns_result(sig) {
+        return Err(syn::Error::new_spanned(
+            &sig.output,
+            "`err_code` 要求函数的返回值为 `Result`",
+        ));
+    }
+    // err_level: 只有返回 Result 的函数能区分成功与失败的返回
+    if args.err_level.is_some() && !returns_result(sig) {
+        return Err(syn::Error::new_spanned(
+            &sig.output,
+            "`err_level` 要求函数的返回值为 `Result`",
+        ));
+    }
+    // err: 错误链从函数返回的错误中取得
+    if err_mode(args, sig) != ErrMode::Off && !returns_result(sig) {
+        return Err(syn::Error::new_spanned(
+            &sig.output,
+            "`err` 要求函数的返回值为 `Result`",
+        ));
+    }
+
+    // json 与 valuable 的参数只附加到入口处输出的入口事件
+    for (name, used) in [
+        ("json", !args.json.is_empty()),
+        ("valuable", !args.valuable.is_empty()),
+    ] {
+        if used && let Some(mode) = deferred_entry_mode(args) {
+            return Err(syn::Error::new_spanned(
+                &sig.ident,
+                format!("`{name}` 不能与 `{mode}` 同时使用"),
+            ));
+        }
+    }
+    // runtime_skip: json 与 valuable 的参数作为结构化字段输出, 无法在运行时跳过
+    if args.runtime_skip == Some(true) && (!args.json.is_empty() || !args.valuable.is_empty()) {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "`runtime_skip` 不能与 `json`、`valuable` 同时使用",
+        ));
+    }
+
+    // batch: 集合参数在入口处取得条目总数, 必须是函数的参数
+    if let Some(items) = args.batch.as_ref().and_then(|batch| batch.items.as_ref()) {
+        let mut bindings = Vec::new();
+        for arg in &sig.inputs {
+            if let syn::FnArg::Typed(pat_type) = arg {
+                collect_bindings(&pat_type.pat, &mut bindings);
+            }
+        }
+        if !bindings.contains(&items) {
+            return Err(syn::Error::new_spanned(
+                items,
+                format!("`batch` 中的 `{items}` 不是函数的参数"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// strict_args: 每个参数都需要明确出现在输出或跳过的参数列表中
+fn check_strict_args(args: &Args, sig: &Signature) -> syn::Result<()> {
+    if args.strict_args != Some(true) {
+        return Ok(());
+    }
+    let mut bindings = Vec::new();
+    for arg in &sig.inputs {
+        if let syn::FnArg::Typed(pat_type) = arg {
+            collect_bindings(&pat_type.pat, &mut bindings);
+        }
+    }
+    for ident in bindings {
+        let name = ident.to_string();
+        let handled = args.skip.contains(&name)
+            || args.log.contains(&name)
+            || args.summarize.contains(&name)
+            || args.json.contains(&name)
+            || args.valuable.contains(&name)
+            || args.format.iter().any(|(format, _)| *format == name);
+        if !handled {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!(
+                    "`strict_args = true` 时参数 `{name}` 必须出现在 `log`、`format`、`summarize`、`json`、`valuable` 或 `skip` 中"
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 宏生成的局部变量、类型与静态变量使用的前缀
+const RESERVED_PREFIXES: [&str; 3] = ["__tracing_fn", "__TracingFn", "__TRACING_FN"];
+
+/// 检查参数与泛型参数的名称是否与宏生成的标识符冲突
+///
+/// 同名的参数会被生成的局部变量遮蔽, 导致函数体读取到错误的值, 因此直接报错。
+fn check_reserved(sig: &Signature) -> syn::Result<()> {
+    let mut idents = Vec::new();
+    for arg in &sig.inputs {
+        if let syn::FnArg::Typed(pat_type) = arg {
+            collect_bindings(&pat_type.pat, &mut idents);
+        }
+    }
+    for param in &sig.generics.params {
+        match param {
+            syn::GenericParam::Type(param) => idents.push(&param.ident),
+            syn::GenericParam::Const(param) => idents.push(&param.ident),
+            syn::GenericParam::Lifetime(_) => {}
+        }
+    }
+    for ident in idents {
+        let name = ident.to_string();
+        let name = name.strip_prefix("r#").unwrap_or(&name);
+        if RESERVED_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+        {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!(
+                    "`{name}` 与 #[tracing_fn] 生成的标识符冲突, 以 {} 开头的名称为宏保留, 请重命名",
+                    RESERVED_PREFIXES.join("、")
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 按出现顺序收集参数模式中绑定的变量
+pub(crate) fn collect_bindings<'a>(pat: &'a Pat, bindings: &mut Vec<&'a Ident>) {
+    match pat {
+        Pat::Ident(pat) => {
+            bindings.push(&pat.ident);
+            if let Some((_, subpat)) = &pat.subpat {
+                collect_bindings(subpat, bindings);
+            }
+        }
+        Pat::Tuple(pat) => pat
+            .elems
+            .iter()
+            .for_each(|elem| collect_bindings(elem, bindings)),
+        Pat::TupleStruct(pat) => pat
+            .elems
+            .iter()
+            .for_each(|elem| collect_bindings(elem, bindings)),
+        Pat::Struct(pat) => pat
+            .fields
+            .iter()
+            .for_each(|field| collect_bindings(&field.pat, bindings)),
+        Pat::Slice(pat) => pat
+            .elems
+            .iter()
+            .for_each(|elem| collect_bindings(elem, bindings)),
+        Pat::Reference(pat) => collect_bindings(&pat.pat, bindings),
+        Pat::Paren(pat) => collect_bindings(&pat.pat, bindings),
+        Pat::Type(pat) => collect_bindings(&pat.pat, bindings),
+        // 参数模式必须是不可反驳的, 或模式的各个分支绑定相同的变量, 取第一个分支即可
+        Pat::Or(pat) => {
+            if let Some(first) = pat.cases.first() {
+                collect_bindings(first, bindings);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `skip_types` 匹配的参数中绑定的变量名
+///
+/// 参数类型中任意位置 (引用、泛型参数、元组等) 出现的类型路径以 `skip_types` 中的某一项结尾即视为匹配,
+/// 例如 `&Option<secrecy::SecretString>` 匹配 `SecretString` 与 `secrecy::SecretString`。
+fn skipped_by_type(args: &Args, sig: &Signature) -> Vec<String> {
+    struct Finder<'a> {
+        skip_types: &'a [String],
+        found: bool,
+    }
+
+    impl<'ast> Visit<'ast> for Finder<'_> {
+        fn visit_path(&mut self, path: &'ast syn::Path) {
+            let segments: Vec<_> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+            self.found |= self.skip_types.iter().any(|ty| {
+                let ty: Vec<_> = ty.split("::").collect();
+                segments.len() >= ty.len() && segments[segments.len() - ty.len()..] == ty[..]
+            });
+            visit::visit_path(self, path);
+        }
+    }
+
+    let mut names = Vec::new();
+    if args.skip_types.is_empty() {
+        return names;
+    }
+    for arg in &sig.inputs {
+        let syn::FnArg::Typed(pat_type) = arg else {
+            continue;
+        };
+        let mut finder = Finder {
+            skip_types: &args.skip_types,
+            found: false,
+        };
+        finder.visit_type(&pat_type.ty);
+        if finder.found {
+            let mut bindings = Vec::new();
+            collect_bindings(&pat_type.pat, &mut bindings);
+            names.extend(bindings.into_iter().map(Ident::to_string));
+        }
+    }
+    names
+}
+
+/// 类型为函数指针或闭包的参数
+///
+/// 按语法识别 `fn(..)`、`impl Fn..`、`dyn Fn..`、它们的引用与 `Box`、`Rc`、`Arc`，以及带有 `Fn` 约束的泛型参数。
+pub(crate) fn callable_args(sig: &Signature) -> Vec<Ident> {
+    fn is_fn_bound(bound: &syn::TypeParamBound) -> bool {
+        matches!(bound, syn::TypeParamBound::Trait(bound)
+        if bound.path.segments.last().is_some_and(|segment| {
+            segment.ident == "Fn" || segment.ident == "FnMut" || segment.ident == "FnOnce"
+        }))
+    }
+
+    fn is_callable(ty: &Type, generics: &[&Ident]) -> bool {
+        match ty {
+            Type::BareFn(_) => true,
+            Type::ImplTrait(ty) => ty.bounds.iter().any(is_fn_bound),
+            Type::TraitObject(ty) => ty.bounds.iter().any(is_fn_bound),
+            Type::Reference(ty) => is_callable(&ty.elem, generics),
+            Type::Paren(ty) => is_callable(&ty.elem, generics),
+            Type::Group(ty) => is_callable(&ty.elem, generics),
+            Type::Path(ty) if ty.qself.is_none() => {
+                if let Some(ident) = ty.path.get_ident() {
+                    return generics.contains(&ident);
+                }
+                let Some(last) = ty.path.segments.last() else {
+                    return false;
+                };
+                if !["Box", "Rc", "Arc"].iter().any(|name| last.ident == name) {
+                    return false;
+                }
+                let syn::PathArguments::AngleBracketed(arguments) = &last.arguments else {
+                    return false;
+                };
+                matches!(arguments.args.first(), Some(syn::GenericArgument::Type(ty)) if is_callable(ty, generics))
+            }
+            _ => false,
+        }
+    }
+
+    // 带有 Fn 约束的泛型参数, 约束可以在参数列表或 where 子句中
+    let mut generics = Vec::new();
+    for param in sig.generics.type_params() {
+        if param.bounds.iter().any(is_fn_bound) {
+            generics.push(&param.ident);
+        }
+    }
+    if let Some(where_clause) = &sig.generics.where_clause {
+        for predicate in &where_clause.predicates {
+            if let syn::WherePredicate::Type(predicate) = predicate
+                && predicate.bounds.iter().any(is_fn_bound)
+                && let Type::Path(ty) = &predicate.bounded_ty
+                && let Some(ident) = ty.path.get_ident()
+                && let Some(param) = sig
+                    .generics
+                    .type_params()
+                    .find(|param| param.ident == *ident)
+            {
+                generics.push(&param.ident);
+            }
+        }
+    }
+
+    let mut callables = Vec::new();
+    for arg in &sig.inputs {
+        if let syn::FnArg::Typed(pat_type) = arg
+            && let syn::Pat::Ident(pat) = &*pat_type.pat
+            && is_callable(&pat_type.ty, &generics)
+        {
+            callables.push(pat.ident.clone());
+        }
+    }
+    callables
+}
+
+/// 判断函数的返回值是否为 `Result` (包括 `io::Result` 等别名)
+pub(crate) fn returns_result(sig: &Signature) -> bool {
+    let ReturnType::Type(_, ty) = &sig.output else {
+        return false;
+    };
+    let Type::Path(path) = &**ty else {
+        return false;
+    };
+    path.path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "Result")
+}
+
+/// 生效的 `err` 参数
+///
+/// 未指定时, 返回值为 `anyhow::Result<T>`、`eyre::Result<T>` 或错误类型为 `anyhow::Error`、`eyre::Report` 的
+/// `Result` 时以 `{:#}` 输出错误, 它们的 `Debug` 输出跨越多行, 不适合单行的日志。只能识别带有 crate 路径的类型。
+pub(crate) fn err_mode(args: &Args, sig: &Signature) -> ErrMode {
+    if let Some(mode) = args.err {
+        return mode;
+    }
+    // no_std 环境与 poll 参数不支持输出错误链
+    if !cfg!(feature = "std") || args.poll.is_some() {
+        return ErrMode::Off;
+    }
+    let ReturnType::Type(_, ty) = &sig.output else {
+        return ErrMode::Off;
+    };
+    let is_report_crate = |path: &syn::Path| {
+        path.segments.first().is_some_and(|segment| {
+            ["anyhow", "eyre", "color_eyre"]
+                .iter()
+                .any(|name| segment.ident == name)
+        })
+    };
+    let Type::Path(ty) = &**ty else {
+        return ErrMode::Off;
+    };
+    let Some(last) = ty.path.segments.last() else {
+        return ErrMode::Off;
+    };
+    if last.ident != "Result" {
+        return ErrMode::Off;
+    }
+    let error = match &last.arguments {
+        syn::PathArguments::AngleBracketed(generics) => generics
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .nth(1),
+        _ => None,
+    };
+    let reports =
+        match error {
+            // Result<T, anyhow::Error>, T 可以是 `()`、`&str` 等任意类型
+            Some(Type::Path(error)) => {
+                is_report_crate(&error.path)
+                    && error.path.segments.last().is_some_and(|segment| {
+                        segment.ident == "Error" || segment.ident == "Report"
+                    })
+            }
+            Some(_) => false,
+            // anyhow::Result<T>
+            None => is_report_crate(&ty.path),
+        };
+    if reports {
+        ErrMode::Alternate
+    } else {
+        ErrMode::Off
+    }
+}
+
+pub(crate) fn returns_never(sig: &Signature) -> bool {
+    matches!(&sig.output, ReturnType::Type(_, ty) if matches!(**ty, Type::Never(_)))
+}
+
+/// 可以用于标注局部变量的返回值类型
+///
+/// 返回值中包含 `impl Trait` 或为 `!` 时无法标注, 返回 `None` 由编译器推断。
+pub(crate) fn explicit_return_type(sig: &Signature) -> Option<Type> {
+    match &sig.output {
+        ReturnType::Default => Some(syn::parse_quote!(())),
+        ReturnType::Type(_, ty) => {
+            let has_impl = quote!(#ty)
+                .to_string()
+                .split_whitespace()
+                .any(|token| token == "impl");
+            (!has_impl && !matches!(**ty, Type::Never(_))).then(|| (**ty).clone())
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! 表达式的计时
+//!
+//! `trace_expr!(expensive_call(a, b))` 求值表达式后输出一条包含表达式源码、值与耗时的事件，
+//! 用于临时测量单个表达式的耗时。
+
+use proc_macro2::TokenStream;
+use quote::{ToTokens, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Expr, MetaNameValue, Token};
+
+use crate::args::{Args, env_dependencies};
+use crate::call::{compact, gate, parse_options};
+use crate::expand::{
+    EventKind, StaticLevel, autoref_imports, debug_value, enabled, event, static_level, truncated,
+};
+
+/// `trace_expr!` 的输入: 逗号分隔的参数与一个表达式
+pub(crate) struct TracedExpr {
+    options: Punctuated<MetaNameValue, Token![,]>,
+    expr: Expr,
+}
+
+impl Parse for TracedExpr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // 赋值表达式需要加括号
+        let options = parse_options(input)?;
+        let expr = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        if !input.is_empty() {
+            return Err(input.error("表达式之后不能有其他内容"));
+        }
+        Ok(Self { options, expr })
+    }
+}
+
+/// 展开 `trace_expr!`
+pub(crate) fn expand(input: TracedExpr) -> syn::Result<TokenStream> {
+    let TracedExpr { options, expr } = input;
+    let args = Args::parse_call_options(options.to_token_stream())?;
+    // 环境变量提供项目级默认值, 宏参数优先
+    let args = Args::from_env()?.merge(&args);
+
+    let level = args.level.as_deref().unwrap_or("trace");
+    // 启用 disabled feature 或该等级被 tracing 的 max_level_* feature 移除时原样求值
+    let static_level = static_level(&args, level);
+    if cfg!(feature = "disabled") || static_level == StaticLevel::Disabled {
+        return Ok(expr.to_token_stream());
+    }
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &expr,
+            "`trace_expr!` 需要启用 tracing-fn 的 std feature",
+        ));
+    }
+
+    let name = compact(&expr);
+    let gate = gate(&args, static_level);
+    let enabled = enabled(&args, level);
+    let message = if args.skip_ret == Some(true) {
+        quote!(
+            "=== [{}] duration: {:?} --- {}:{}",
+            #name,
+            __tracing_fn_duration,
+            ::core::file!(),
+            ::core::line!()
+        )
+    } else {
+        let ret = truncated(&args, debug_value(quote!(__tracing_fn_ret)));
+        quote!(
+            "=== [{}] #Ret: {:?}, duration: {:?} --- {}:{}",
+            #name,
+            #ret,
+            __tracing_fn_duration,
+            ::core::file!(),
+            ::core::line!()
+        )
+    };
+    let event = event(&args, EventKind::Event, level, quote!(), message);
+
+    let imports = autoref_imports();
+    let env_dependencies = env_dependencies();
+    Ok(quote! {
+        {
+            #env_dependencies
+            let __tracing_fn_start = (#gate && #enabled).then(::tracing_fn::runtime::Instant::now);
+            let __tracing_fn_ret = #expr;
+            if let ::std::option::Option::Some(__tracing_fn_start) = __tracing_fn_start {
+                #imports
+                let __tracing_fn_duration = __tracing_fn_start.elapsed();
+                #event
+            }
+            __tracing_fn_ret
+        }
+    })
+}
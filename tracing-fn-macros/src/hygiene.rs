@@ -0,0 +1,43 @@
+//! 生成的标识符的卫生性
+//!
+//! 宏生成的局部变量均以 `__tracing_fn` 开头。展开结束后把这些标识符的 span 改为 `Span::mixed_site()`，
+//! 使其只能被同一次展开中生成的代码引用：函数体中的 `let __tracing_fn_ret = ...` 不会遮蔽生成代码中的变量，
+//! `trace_block!` 等函数式宏中生成的变量也不会遮蔽调用处的同名变量。
+//!
+//! 用户编写的标识符按其源码文本识别 (span 的源码文本即标识符本身) 并保持原样，
+//! 函数体中按文档使用的 `__tracing_fn_span` 与生成代码中绑定它的变量同样保持原样，因此仍可以引用。
+//!
+//! mixed_site 只作用于局部变量与标签，守卫的结构体、静态变量等条目以及与常量的匹配仍按调用处解析，
+//! 因此 `__tracing_fn`、`__TracingFn`、`__TRACING_FN` 开头的条目名称依然保留给宏使用。
+
+use proc_macro2::{Group, Ident, Span, TokenStream, TokenTree};
+
+/// 函数体中可以访问的 span 变量的默认名称
+const SPAN_HANDLE: &str = "__tracing_fn_span";
+
+/// 把生成代码中所有以 `__tracing_fn` 开头的标识符解析为 mixed_site, 保留其源码位置
+pub(crate) fn mixed_site(tokens: TokenStream) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Ident(mut ident) if is_generated(&ident) => {
+                ident.set_span(ident.span().resolved_at(Span::mixed_site()));
+                TokenTree::Ident(ident)
+            }
+            TokenTree::Group(group) => {
+                let mut mapped = Group::new(group.delimiter(), mixed_site(group.stream()));
+                mapped.set_span(group.span());
+                TokenTree::Group(mapped)
+            }
+            token => token,
+        })
+        .collect()
+}
+
+/// 生成的标识符的 span 指向宏的调用处或其他 token, 其源码文本与标识符不同
+fn is_generated(ident: &Ident) -> bool {
+    let name = ident.to_string();
+    name.starts_with("__tracing_fn")
+        && name != SPAN_HANDLE
+        && ident.span().source_text().as_deref() != Some(name.as_str())
+}
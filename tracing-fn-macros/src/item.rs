@@ -0,0 +1,168 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    Attribute, Block, Expr, ImplItem, Item, ItemImpl, ItemMod, Signature, Stmt, Token, Visibility,
+    braced,
+};
+
+use crate::args::{Args, ImplBlock, take_attr};
+use crate::expand::expand_fn;
+
+/// 为 impl 块中的所有方法添加 tracing
+///
+/// 方法上的 `#[tracing_fn(...)]` 会在 `defaults` 的基础上追加或覆盖参数。
+pub(crate) fn instrument_impl(defaults: &Args, item: &mut ItemImpl) -> syn::Result<()> {
+    let mut defaults = defaults.clone();
+    defaults.scope.push(type_name(&item.self_ty));
+    defaults.impl_block = Some(if item.trait_.is_some() {
+        ImplBlock::Trait
+    } else {
+        ImplBlock::Inherent
+    });
+    for impl_item in &mut item.items {
+        let ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+        // const fn 中无法调用 tracing
+        if method.sig.constness.is_some() {
+            continue;
+        }
+        let args = match take_attr(&mut method.attrs, "tracing_fn")? {
+            Some(own) => defaults.merge(&own),
+            None => defaults.clone(),
+        };
+        let vis = &method.vis;
+        let defaultness = &method.defaultness;
+        let tokens = expand_fn(
+            &args,
+            &method.attrs,
+            quote!(#vis #defaultness),
+            &method.sig,
+            &method.block,
+        );
+        *impl_item = ImplItem::Verbatim(tokens);
+    }
+    Ok(())
+}
+
+/// 为内联模块中的所有函数、impl 块以及嵌套模块添加 tracing
+///
+/// 内部的 `#[tracing_fn]`、`#[tracing_impl]`、`#[tracing_mod]` 会在 `defaults` 的基础上追加或覆盖参数。
+pub(crate) fn instrument_mod(defaults: &Args, item: &mut ItemMod) -> syn::Result<()> {
+    let Some((_, items)) = &mut item.content else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[tracing_mod] 仅支持内联模块 (`mod name { ... }`)",
+        ));
+    };
+    let mut defaults = defaults.clone();
+    defaults.scope.push(item.ident.to_string());
+    for inner in items {
+        match inner {
+            Item::Fn(item_fn) if item_fn.sig.constness.is_none() => {
+                let args = match take_attr(&mut item_fn.attrs, "tracing_fn")? {
+                    Some(own) => defaults.merge(&own),
+                    None => defaults.clone(),
+                };
+                let vis = &item_fn.vis;
+                let tokens = expand_fn(
+                    &args,
+                    &item_fn.attrs,
+                    quote!(#vis),
+                    &item_fn.sig,
+                    &item_fn.block,
+                );
+                *inner = Item::Verbatim(tokens);
+            }
+            Item::Impl(item_impl) => {
+                let args = match take_attr(&mut item_impl.attrs, "tracing_impl")? {
+                    Some(own) => defaults.merge(&own),
+                    None => defaults.clone(),
+                };
+                instrument_impl(&args, item_impl)?;
+            }
+            Item::Mod(item_mod) if item_mod.content.is_some() => {
+                let args = match take_attr(&mut item_mod.attrs, "tracing_mod")? {
+                    Some(own) => defaults.merge(&own),
+                    None => defaults.clone(),
+                };
+                instrument_mod(&args, item_mod)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// impl 块的类型名, 路径类型取最后一段, 其余类型取其源码形式
+fn type_name(ty: &syn::Type) -> String {
+    match ty {
+        syn::Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .map_or_else(String::new, |segment| segment.ident.to_string()),
+        _ => quote!(#ty).to_string().replace(' ', ""),
+    }
+}
+
+/// 函数体无法被 syn 解析的函数 (例如使用了 nightly 语法)
+///
+/// 函数体作为原样的 token 保留, 不改写其中的 `return` 与 `?`,
+/// 经由它们提前返回时退出事件中的返回值为 `<unknown>`。
+pub(crate) struct VerbatimFn {
+    pub(crate) attrs: Vec<Attribute>,
+    pub(crate) vis: Visibility,
+    pub(crate) sig: Signature,
+    pub(crate) block: Block,
+}
+
+impl Parse for VerbatimFn {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let sig = input.parse()?;
+        let content;
+        let brace_token = braced!(content in input);
+        attrs.extend(content.call(Attribute::parse_inner)?);
+        let body: TokenStream = content.parse()?;
+        Ok(Self {
+            attrs,
+            vis,
+            sig,
+            block: Block {
+                brace_token,
+                stmts: vec![Stmt::Expr(Expr::Verbatim(body), None)],
+            },
+        })
+    }
+}
+
+/// 判断无法解析的条目是否为 impl 块, 用于区分 syn 尚不支持的语法与误用
+pub(crate) fn is_impl(tokens: TokenStream) -> bool {
+    peek_item(tokens, |input| {
+        input.parse::<Option<Token![default]>>()?;
+        input.parse::<Option<Token![unsafe]>>()?;
+        Ok(input.peek(Token![impl]))
+    })
+}
+
+/// 判断无法解析的条目是否为模块, 用于区分 syn 尚不支持的语法与误用
+pub(crate) fn is_mod(tokens: TokenStream) -> bool {
+    peek_item(tokens, |input| {
+        input.parse::<Option<Token![unsafe]>>()?;
+        Ok(input.peek(Token![mod]))
+    })
+}
+
+fn peek_item(tokens: TokenStream, peek: impl FnOnce(ParseStream) -> syn::Result<bool>) -> bool {
+    let parser = |input: ParseStream| {
+        input.call(Attribute::parse_outer)?;
+        input.parse::<Visibility>()?;
+        let matched = peek(input)?;
+        input.parse::<TokenStream>()?;
+        Ok(matched)
+    };
+    syn::parse::Parser::parse2(parser, tokens).unwrap_or(false)
+}
@@ -0,0 +1,630 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{ItemFn, ItemImpl, ItemMod};
+
+mod allow;
+mod args;
+mod block;
+mod body;
+mod call;
+mod closure;
+mod doc;
+mod expand;
+mod expr;
+mod hygiene;
+mod item;
+mod no_std;
+mod poll;
+mod preset;
+mod program;
+mod replay;
+mod report;
+
+use args::Args;
+use item::VerbatimFn;
+
+/// 为函数添加 tracing 功能的过程宏
+///
+/// 函数体原样展开在函数中，`return`、`?`、`.await` 以及参数的借用语义与原函数一致，支持 `async fn`。
+/// 退出事件由析构时的守卫输出，经由 `?` 提前返回时输出 `?` 的操作数中导致返回的部分，panic 时输出 `<panicked>`。
+/// 参数、返回值或 `?` 的操作数的类型未实现 `Debug` 时输出 `<non-Debug 类型名>`，不会导致编译失败。
+/// 是否实现了 `Debug` 按调用处的具体类型判断，泛型参数只有带有 `Debug` 约束时才会输出其 `Debug`。
+/// 类型为函数指针或闭包的参数 (按语法识别 `fn(..)`、`impl Fn..`、`dyn Fn..` 与带有 `Fn` 约束的泛型参数) 输出为 `<fn 类型名>`。
+/// 函数体中包含 syn 无法解析的语法 (例如 nightly 语法) 时不改写函数体，经由 `return` 或 `?` 提前返回时返回值为 `<unknown>`。
+/// 以 `__tracing_fn`、`__TracingFn`、`__TRACING_FN` 开头的名称为宏保留，参数或泛型参数使用这些名称时编译报错。
+/// 生成的局部变量使用 mixed-site 卫生性，不会与函数体中的同名变量冲突；生成的代码以绝对路径引用 `::core`、`::std` 与 `::tracing`。
+/// 同一次调用的入口与退出事件包含相同的调用 ID (例如 `>>> [f] #42 ...`)，ID 生成方式参见 `tracing_fn::set_id_generator`。
+///
+/// # 参数
+/// - `level`: 日志等级 (trace, debug, info, warn, error)，默认为 trace
+/// - `level_fn`: 在调用时返回 `tracing::Level` 的函数 (例如 `level_fn = "crate::log_cfg::level_for"`)，
+///   也可以是引用参数的调用表达式 (例如 `level_fn = "crate::log_cfg::level_for(user_id)"`)。
+///   在入口处求值一次，代替 `level` 决定入口与退出事件的等级，`span`、`child_span` 仍使用 `level`。只支持 tracing 后端
+/// - `skip`: 跳过的参数列表，支持 `skip = "a, b"` 与 `skip(a, b)` 两种写法。
+///   解构的参数 (例如 `(x, y): (i32, i32)`) 按其中绑定的变量逐个输出与跳过
+/// - `skip_types`: 按类型跳过的参数，支持 `skip_types = "SecretString, secrecy::SecretBox"` 与 `skip_types(SecretString)` 两种写法。
+///   参数类型中任意位置 (引用、`Option` 等泛型参数、元组) 出现的类型路径以其中某一项结尾即跳过该参数，与参数名无关
+/// - `summarize`: 只输出集合类型与长度 (例如 `items=Vec(len=10432)`) 的参数列表，支持 `summarize = "a, b"` 与 `summarize(a, b)` 两种写法。
+///   支持切片、数组、`str`、`Vec`、`String`、`HashMap` 等标准库集合及其引用、`Box`、`Option`，其余类型只输出类型名
+/// - `json`: 通过 `serde_json` 序列化为结构化字段的参数列表，支持 `json = "a, b"` 与 `json(a, b)` 两种写法。
+///   参数需要实现 `serde::Serialize`，JSON 字符串作为与参数同名的字段附加到入口事件，`#Args` 中输出为 `a=<json>`。
+///   需要启用 serde feature，不能与 `paired`、`err_only` 或 `exit_only` 同时使用
+/// - `valuable`: 作为结构化值记录的参数列表，支持 `valuable = "a, b"` 与 `valuable(a, b)` 两种写法。
+///   参数需要实现 `valuable::Valuable`，通过 `tracing::field::valuable` 作为与参数同名的字段附加到入口事件，
+///   subscriber 可以访问其中嵌套的字段，`#Args` 中输出为 `a=<valuable>`。需要启用 tracing 的 valuable feature
+///   并以 `--cfg tracing_unstable` 编译，不能与 `paired`、`err_only` 或 `exit_only` 同时使用
+/// - `log`: 明确需要输出的参数列表，支持 `log = "a, b"` 与 `log(a, b)` 两种写法，只用于 `strict_args` 的检查
+/// - `strict_args`: 是否要求每个参数都出现在 `log`、`format`、`summarize`、`json`、`valuable` 或 `skip` (包括按 `skip_types` 跳过的参数) 中，
+///   否则编译失败，默认为false。适用于需要逐一确认输出内容的安全敏感模块，可以在 `#[tracing_mod]` 中统一设置
+/// - `redact`: `skip` 中的参数的输出方式 (mask, sha256, omit)，默认为 mask，输出为占位符。
+///   sha256 输出参数 `Debug` 输出的 SHA-256 哈希前缀 (例如 `sha256:c1980264fc223a89`)，此时参数需要实现 `Debug`。
+///   omit 不输出参数名与值
+/// - `redacted_as`: mask 方式使用的占位符，默认为 `***`
+/// - `runtime_skip`: 是否在输出事件时查询通过 `tracing_fn::redaction::set` 为该函数设置的参数名，
+///   这些参数按 `redact` 指定的方式输出 (sha256 不要求参数实现 `Debug`)，不能与 `json`、`valuable` 同时使用，默认为false
+/// - `format`: 按参数名指定的格式化函数的路径 (例如 `format(body = "crate::log_fmt::summarize_body")`)，
+///   函数接收参数的引用，返回实现了 `Display` 的值，代替参数的 `Debug` 输出。在外层属性的基础上追加，同名参数由内层覆盖
+/// - `force`: 是否强制在release模式下启用tracing，默认为false
+/// - `cfg`: 自定义启用 tracing 的 cfg 条件，默认为 `debug_assertions`，不能与 `force` 同时使用
+/// - `condition`: 运行时判断是否输出本次调用的事件的表达式 (例如 `condition = "user_id == ADMIN_ID"`)，可以引用函数的参数，
+///   只在事件会被记录时于入口处求值一次。表达式不能移动参数
+/// - `once_per`: 按 key 去重输出，同一 key 只有首次调用输出事件，之后的调用只计数并按周期汇总输出
+///   - `key`: 区分调用的表达式，通常为参数名
+///   - `capacity`: 最多记录的 key 数量，超出后淘汰最久未出现的 key，默认为 1024
+///   - `period`: 汇总输出的周期，默认为 `"60s"`
+/// - `first`: 只输出前 N 次调用的事件 (例如 `first = 20`)，之后的调用输出一条 `further events suppressed` 提示后不再输出。
+///   只统计事件会被记录的调用
+/// - `dedup`: 是否省略参数与上一次调用相同的调用的事件，参数变化时输出一条 `repeated xN` 汇总被省略的次数，
+///   用于以相同参数反复调用的轮询循环，不能与 `err_only` 同时使用，默认为false
+/// - `pressure`: `tracing_fn::set_pressure_fn` 报告日志管道压力较高时的输出策略 (keep, downgrade, drop)，默认为 downgrade。
+///   downgrade 在压力为 `High` 时只输出函数名与耗时 (参数与返回值输出为 `<omitted>`)，为 `Critical` 时不输出事件；
+///   drop 在压力为 `High` 及以上时不输出事件；keep 不受压力影响
+/// - `priority`: 函数的优先级 (high, normal, low)，未指定 `pressure` 时分别对应 keep、downgrade、drop 策略，
+///   使业务关键的函数在高负载下仍完整输出，频繁调用的辅助函数最先被丢弃。指定时事件中附带 `priority` 字段，供下游的采样与限流使用
+/// - `verbosity_tiers`: 根据 `tracing_fn::set_verbosity` 设置的全局详细程度决定是否输出参数与返回值
+///   - `args`: 输出参数所需的最低详细程度，默认为 2
+///   - `ret`: 输出返回值所需的最低详细程度，默认为 3
+/// - `skip_ret`: 是否不输出返回值，默认为false
+/// - `skip_empty_args`: 没有需要输出的参数时是否省略入口事件中的 `#Args: ()`，默认为false
+/// - `ret_with`: 格式化返回值的函数的路径 (例如 `ret_with = "summarize"`)，函数接收 `&返回值类型`，
+///   返回实现了 `Display` 的值，代替返回值的 `Debug` 输出。经由 `?` 提前返回时仍输出导致返回的部分。
+///   返回值类型不需要实现 `Debug`
+/// - `max_len`: 每个参数与返回值格式化结果的最大字节数 (例如 `max_len = 256`)，超出的部分截断为 `…(+N bytes)`，
+///   跳过的参数的占位符与 `json`、`valuable` 字段不受影响
+/// - `doc`: 是否在函数的文档末尾追加 `# Instrumentation` 一节，说明日志等级、跳过的参数、阈值与启用条件，默认为true，没有文档的函数不追加
+/// - `err`: 函数返回 `Err` 时错误的输出方式，要求函数的返回值为 `Result`，此时函数体中的 `?` 展开为等价的 `match`
+///   - `true` 或 `"chain"`: 以 `Err(外层错误: 内层错误: ...)` 的形式输出错误的 `Display` 及其 `source()` 链，要求错误类型实现 `std::error::Error` 或解引用为实现了它的类型 (例如 `Box<dyn Error>`)
+///   - `"alternate"`: 以 `{:#}` 输出错误，适用于 `anyhow::Error` 与 `eyre::Report`
+///   - `false`: 与返回 `Ok` 时一样输出 `Debug`
+///
+///   未指定时，返回值为 `anyhow::Result<T>`、`eyre::Result<T>` 或 `Result<T, anyhow::Error>` 等带有 crate 路径的类型时为 `"alternate"`，否则为 `false`
+/// - `err_level`: 函数返回 `Err` (包括经由 `?` 提前返回) 时退出事件的日志等级，返回 `Ok` 时仍使用 `level`。
+///   只启用了该等级时同样会记录调用并输出失败的退出事件。要求函数的返回值为 `Result`
+/// - `err_code`: 从错误中取得错误码的表达式 (例如 `err_code = "err.code()"`)，其中 `err` 为 `&错误类型`，
+///   返回整数、`String` 等 `tracing::Value`。函数返回 `Err` 时错误码作为结构化字段 `error.code` 附加到退出事件。
+///   要求函数的返回值为 `Result`，此时函数体中的 `?` 展开为等价的 `match`
+/// - `abort_safe`: 是否启用 abort 安全模式，默认为false。启用后入口参数会写入黑匣子 (最近调用的环形缓冲区)，
+///   并安装 panic hook，在 panic 时以 error 等级输出当前线程尚未返回的调用及最近的调用记录。
+///   该模式不依赖析构，在 `panic = "abort"` 下同样有效
+/// - `fanout`: 是否在退出事件中输出本次调用期间发生的嵌套调用次数及其中失败 (返回 `Err` 或 panic) 的次数，默认为false。
+///   async fn 不参与统计
+/// - `tenant`: 是否在入口与退出事件中附带结构化字段 `tenant`，其值由 `tracing_fn::set_tenant_provider`
+///   注册的提供函数在调用时获取，默认为false
+/// - `fields`: 附加到入口与退出事件的固定字段 (例如 `fields(subsystem = "auth")`)，
+///   在外层属性的基础上追加，同名字段由内层覆盖
+/// - `with`: 在入口处调用的上下文提供函数的路径 (例如 `with = "ctx_provider"`)，函数返回由 `(key, value)` 组成的可迭代对象，
+///   格式化后作为结构化字段 `ctx` 附加到入口与退出事件
+/// - `clock`: 代替 `Instant::now()` 的计时函数的路径 (例如 `clock = "my_hal::now_micros"`)，函数返回以微秒为单位的 `u64` 时间戳
+/// - `measure`: 退出事件中输出的耗时 (wall, cpu, both)，默认为 wall。wall 耗时同时以微秒为单位输出为数值字段 `duration_us`，
+///   cpu 为当前线程消耗的 CPU 时间，需要启用 `cpu` feature，不支持 async fn
+/// - `location`: 在哪些事件的消息中输出源码位置 (entry, exit, both, none)，默认为 entry。none 时不在消息中输出，
+///   依赖 tracing 事件元数据中的位置，`paired` 等合并的事件除 none 外都输出
+/// - `duration_unit`: 退出事件中耗时的输出单位 (ns, us, ms, s)，例如 `duration: 2.813us`，
+///   默认使用 `Duration` 的 Debug 输出，单位随耗时的大小变化
+/// - `allocs`: 是否在退出事件中输出调用期间当前线程的分配次数与字节数，需要启用 `alloc-count` feature
+///   并使用 `tracing_fn::CountingAllocator` 作为全局分配器，不支持 async fn，默认为false
+/// - `record`: 是否在每次正常返回时把参数与返回值通过 serde 序列化追加到进程的回放文件，需要启用 `record-replay` feature，
+///   参数与返回值需要实现 `serde::Serialize`，回放文件参见 `tracing_fn::load_replay`，默认为false
+/// - `replay`: 是否生成以回放文件中的记录重新执行函数的伴生函数 `<函数名>__replay(&ReplayRecord) -> ReplayResult`，
+///   同时启用 `record`。参数需要实现 `serde::Deserialize`，只支持不带 self、泛型参数的函数，默认为false
+/// - `binary`: 是否在入口与退出处把调用记录以定长的二进制格式写入 `tracing_fn::binary::open` 打开的内存映射文件，
+///   不格式化参数与返回值且与日志等级无关，需要启用 `binary` feature，默认为false
+/// - `test_subscriber`: 是否在 `#[test]` 函数的开头初始化输出所有等级事件、写入测试输出的 subscriber，
+///   已设置全局 subscriber 时不做任何事，需要启用 `test-subscriber` feature，默认为false
+/// - `compress_prefix`: 连续调用中前几个参数与上一次调用相同时，入口事件中只输出引用编号 (例如 `[@1]`)
+///   - `len`: 作为前缀的参数个数，默认为 1
+///   - `window`: 重新输出完整前缀的周期，默认为 `"60s"`
+/// - `slow_poll`: async fn 中单次 poll 耗时的阈值 (例如 `"50ms"`)，完成时最长的单次 poll 超过阈值则以 warn 等级输出。
+///   对非 async fn 无效
+/// - `poll`: 用于手动实现的 `Future::poll`、`Stream::poll_next` 等返回 `Poll<T>` 的函数，不输出入口与退出事件，
+///   只在实例由未等待变为返回 `Pending` 时与返回 `Ready` 时输出事件，后者附带等待期间的 poll 次数与累计等待时间
+///   - `id`: 区分 future 实例的表达式 (例如 `id = "self.id"`)，在函数体之前求值，以 `Debug` 输出区分
+/// - `batch`: 用于处理集合的函数，函数体中每次调用 `tracing_fn::batch_tick!()` (或 `batch_tick!(n)`) 记录已处理的条目，
+///   已处理的条目数每跨过 `chunk` 的整数倍时输出一次包含已处理条目数、耗时与速率的进度事件，
+///   例如 `batch(rows, chunk = 1000)`。未启用 std feature 时不支持
+///   - 第一项: 被处理的集合参数，可以省略。参数为切片、`Vec`、`HashMap` 等集合或其引用时，进度中附带条目总数
+///   - `chunk`: 每处理多少个条目输出一次进度，默认为 100
+/// - `paired`: 是否只在退出时输出一条同时包含参数、返回值、耗时与结果 (`ok`、`err`、`panicked`) 的事件，默认为false
+/// - `process_exit`: 是否在函数体中的 `process::exit`、`process::abort` 调用之前输出退出事件，返回值输出为 `<exit(code)>` 或 `<abort>`，
+///   只识别路径以 `process::exit`、`process::abort` 结尾且不在闭包中的调用，默认为true
+/// - `entry_on_slow`: 耗时的阈值 (例如 `"100ms"`)，入口事件推迟到退出时，只有耗时超过阈值的调用才补发入口事件并输出退出事件，
+///   耗时较短的调用不输出任何事件。补发的入口事件的时间戳为退出时的时间，不能与 `paired`、`err_only`、`entry_only`、`exit_only`、`json`、`valuable` 同时使用
+/// - `qualified`: 是否在事件中以包含模块路径的全限定名 (例如 `my_crate::auth::login`) 代替函数名，
+///   impl 块中的方法附带类型名 (`my_crate::auth::Session::login`)，默认为false
+/// - `generics`: 是否在泛型函数的事件中的函数名之后附带类型参数的类型名，例如 `parse::<u32>`，
+///   类型名来自 `core::any::type_name`，不包括 impl 块的类型参数，默认为false
+/// - `self_type`: 事件中方法名之前附带的类型名，例如 `self_type = "UserService"` 输出 `UserService::create`，
+///   在 `#[tracing_impl]` 中可以设置为 `true` 使用 impl 块的类型名，默认不附带
+/// - `caller`: 是否以 `#[track_caller]` 标注函数，并在入口事件中输出调用方的源码位置 (`#Caller: src/main.rs:12:5`)，
+///   只输出退出事件时随退出事件输出。函数中的 panic 也将报告调用方的位置，不支持 async fn，默认为false
+/// - `caller_fn`: 是否在入口事件中输出当前线程中最近的外层被追踪调用的函数名 (`#From: load_config`)，没有外层调用时输出 `<root>`，
+///   只输出退出事件时随退出事件输出。基于与 `fanout` 相同的线程局部栈帧，不支持 async fn，默认为false
+/// - `err_only`: 是否在调用成功时不输出任何事件，只在返回 `Err` (包括经由 `?` 提前返回) 或 panic 时
+///   输出一条与 `paired` 相同的合并事件，默认为false
+/// - `entry_only`: 是否只输出入口事件，不格式化返回值，不能与 `paired`、`err_only`、`err_level` 同时使用，默认为false
+/// - `exit_only`: 是否只输出包含返回值与耗时的退出事件，不格式化参数，不能与 `paired`、`err_only` 同时使用，默认为false
+/// - `span`: 是否为每次调用创建以函数名命名的 span，被追踪的嵌套调用位于其中，
+///   tracing-flame 等工具可以据此生成调用树，不能与 `dispatch` 同时使用，默认为false。
+///   span 绑定到变量 `__tracing_fn_span`，也可以使用列表形式设置：
+///   - `handle`: 函数体中访问该 span 的变量名 (例如 `handle = "span"`)
+///   - `record`: 创建时声明为空值的字段 (例如 `record(rows, bytes)`)，由函数体通过 `Span::record` 填充
+/// - `child_span`: 在函数体外创建以指定名称命名的 span (例如 `child_span = "tx"`)，函数体中的事件位于该 span 之内，
+///   函数自身的入口与退出事件位于该 span 之外
+/// - `dispatch`: 输出事件使用的 `tracing::Dispatch` 静态变量的路径 (例如 `dispatch = crate::AUDIT`)，
+///   宏生成的事件经由 `tracing::dispatcher::with_default` 输出到该 `Dispatch`，函数体中的事件不受影响
+/// - `audit`: 是否把入口与退出事件作为审计记录，附带该函数内单调递增的序号与前后相接的哈希
+///   (`audit.seq`、`audit.prev`、`audit.hash` 字段)，校验方式参见 `tracing_fn::audit_hash`，默认为false
+/// - `backend`: 输出事件使用的日志库 (tracing, log, stderr, etw)，默认为 tracing。stderr 通过 `eprintln!` 输出，
+///   不依赖任何日志库且不进行等级过滤。etw 需要启用 win-etw feature，事件写入 `TracingFn` provider。
+///   使用 log、stderr 或 etw 时不输出结构化字段，
+///   不能与 `tenant`、`thread`、`span_id`、`span`、`child_span`、`dispatch`、`audit` 同时使用
+/// - `thread`: 是否在入口与退出事件中附加当前线程的名称与 ID (`thread.name`、`thread.id` 字段)，默认为false
+/// - `span_id`: 是否在事件中附加当前 span 的 ID (`span.id` 字段，取自 `tracing::Span::current().id()`)，
+///   便于与基于 span 的遥测数据关联，默认为false。当前没有 span 时不记录该字段，不能与 `dispatch` 同时使用
+/// - `depth`: 是否按当前线程中外层被追踪调用的层数缩进事件，并附加结构化字段 `depth`，默认为false。
+///   async fn 不参与
+/// - `crate`: 生成的代码引用 tracing 的路径 (例如 `crate = "::my_facade::tracing"`)，默认为 `::tracing`，
+///   用于不直接依赖 tracing、经由其他 crate 重新导出 tracing 的 crate
+/// - `use`: 使用 `tracing_fn::opts!` 定义的参数预设 (例如 `use = crate::telemetry::DB_TRACE`)，
+///   属性中的其他参数在预设的基础上追加或覆盖，规则与 [`macro@tracing_impl`] 中方法上的属性相同
+///
+/// 可以通过以下环境变量设置项目级的默认参数，属性中的参数优先于环境变量：
+/// - `TRACING_FN_LEVEL`: 默认日志等级
+/// - `TRACING_FN_FORCE`: 是否默认强制启用 (true/false)
+/// - `TRACING_FN_DEFAULT_SKIP_RET`: 是否默认不输出返回值 (true/false)
+/// - `TRACING_FN_DEFAULT_SKIP_EMPTY_ARGS`: 没有参数时是否默认省略 `#Args: ()` (true/false)
+/// - `TRACING_FN_BACKEND`: 默认日志后端 (tracing/log/stderr/etw)
+/// - `TRACING_FN_CRATE`: 生成的代码引用 tracing 的默认路径
+/// - `TRACING_FN_SKIP_TYPES`: 总是跳过的参数类型，与 `skip_types` 参数追加
+/// - `TRACING_FN_REDACT`: 跳过的参数的默认输出方式 (mask/sha256/omit)
+/// - `TRACING_FN_REDACTED_AS`: 默认的占位符
+/// - `TRACING_FN_REPORT`: 是否在 `$OUT_DIR/tracing_fn_report.tsv` 中生成编译期统计报告 (true/false)，
+///   报告可以通过 `tracing_fn::build_report!()` 引入
+///
+/// 关闭 `std` feature (默认启用) 时，生成不依赖 `std`、不分配内存的代码：参数与返回值作为结构化字段输出，
+/// 耗时由 `tracing_fn::set_tick_source` 注册的计时函数测量，依赖 `std` 的参数不可用。
+/// 同时启用 `critical-section` feature 时，`fanout` 与 `depth` 基于由 critical-section 保护的全局栈帧实现。
+///
+/// 启用 `tokio` feature 时，async fn 的入口与退出事件附带当前 tokio 任务的 ID (`tokio.task.id` 字段)。
+///
+/// 启用 `cpu` feature 时可以使用 `measure = "cpu"` 测量线程的 CPU 时间。
+///
+/// 启用 `alloc-count` feature 时可以使用 `allocs = true` 统计调用期间的内存分配。
+///
+/// 在 `wasm32-unknown-unknown` 上，启用 `wasm` feature 时使用 `performance.now()` 计时，否则不计时 (耗时为 0)。
+///
+/// 启用 `disabled` feature 时，所有被标注的函数都会展开为原函数，忽略 `force` 等参数。
+///
+/// 通过 tracing-fn 启用 `max_level_*` 或 `release_max_level_*` feature (同时转发给 tracing) 时，
+/// 等级被静态移除的函数展开为原函数 (`release_max_level_*` 只在 Release 模式下)，并计入统计报告的 `stripped` 列。
+///
+/// 设置了 `TRACING_FN_ALLOW` (逗号分隔) 或 `TRACING_FN_ALLOW_FILE` (每行一个，`#` 之后为注释) 白名单时，
+/// 只有全限定名 (例如 `my_crate::auth::login`) 匹配白名单的函数才会添加 tracing，白名单支持 `*` 通配符。
+///
+/// # 示例
+/// ```rust
+/// # use tracing_fn::tracing_fn;
+/// #[tracing_fn]
+/// fn example_fn(a: i32, b: String) -> i32 {
+///     a + b.len() as i32
+/// }
+///
+/// #[tracing_fn(level = "info")]
+/// fn example_fn2(a: i32, b: String) -> i32 {
+///     a + b.len() as i32
+/// }
+///
+/// #[tracing_fn(skip = "b")]
+/// fn example_fn3(a: i32, b: String) -> i32 {
+///     a + b.len() as i32
+/// }
+///
+/// // 强制在release模式下也启用tracing
+/// #[tracing_fn(force = true)]
+/// fn example_fn4(a: i32) -> i32 {
+///     a * 2
+/// }
+///
+/// // 仅在满足自定义 cfg 条件时启用tracing
+/// #[tracing_fn(cfg = "feature = \"fn-trace\"")]
+/// fn example_fn5(a: i32) -> i32 {
+///     a * 3
+/// }
+///
+/// // async fn 中的 `?` 与 `.await` 保持原有语义
+/// #[tracing_fn]
+/// async fn example_fn7(input: &str) -> Result<i32, std::num::ParseIntError> {
+///     let value: i32 = input.parse()?;
+///     Ok(value)
+/// }
+///
+/// // 每个 user_id 只输出一次
+/// #[tracing_fn(once_per(key = "user_id", capacity = 4096, period = "30s"))]
+/// fn example_fn6(user_id: u64) -> u64 {
+///     user_id + 1
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn tracing_fn(args: TokenStream, input: TokenStream) -> TokenStream {
+    if let Some(forwarded) =
+        preset::forward("tracing_fn", args.clone().into(), input.clone().into())
+    {
+        return forwarded
+            .unwrap_or_else(|err| err.to_compile_error())
+            .into();
+    }
+    let args = match Args::parse(args.into()) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    // 函数体中包含 syn 无法解析的语法时, 退化为不改写函数体
+    let input_fn = match syn::parse::<ItemFn>(input.clone()) {
+        Ok(input_fn) => VerbatimFn {
+            attrs: input_fn.attrs,
+            vis: input_fn.vis,
+            sig: input_fn.sig,
+            block: *input_fn.block,
+        },
+        Err(err) => match syn::parse::<VerbatimFn>(input) {
+            Ok(input_fn) => input_fn,
+            Err(_) => return err.to_compile_error().into(),
+        },
+    };
+    let vis = &input_fn.vis;
+
+    TokenStream::from(hygiene::mixed_site(expand::expand_fn(
+        &args,
+        &input_fn.attrs,
+        quote!(#vis),
+        &input_fn.sig,
+        &input_fn.block,
+    )))
+}
+
+/// 为 impl 块中的所有方法添加 tracing 功能的过程宏
+///
+/// 参数与 [`macro@tracing_fn`] 相同，作为块内所有方法的默认参数。
+/// 方法上仍可以使用 `#[tracing_fn(...)]` 调整参数：`skip` 在外层的基础上追加，其余参数覆盖外层。
+/// `const fn` 不会被添加 tracing。
+///
+/// # 示例
+/// ```rust
+/// # use tracing_fn::{tracing_fn, tracing_impl};
+/// struct UserService;
+///
+/// #[tracing_impl(level = "debug", skip(password, token))]
+/// impl UserService {
+///     fn login(&self, username: &str, password: &str) -> bool {
+///         !username.is_empty() && !password.is_empty()
+///     }
+///
+///     // 同时跳过 password、token 与 otp
+///     #[tracing_fn(level = "info", skip(otp))]
+///     fn verify(&self, token: &str, otp: u32) -> bool {
+///         !token.is_empty() && otp > 0
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn tracing_impl(args: TokenStream, input: TokenStream) -> TokenStream {
+    if let Some(forwarded) =
+        preset::forward("tracing_impl", args.clone().into(), input.clone().into())
+    {
+        return forwarded
+            .unwrap_or_else(|err| err.to_compile_error())
+            .into();
+    }
+    let args = match Args::parse(args.into()) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    // 无法解析时原样输出, 由编译器报告语法错误或处理 syn 尚不支持的语法
+    let mut input_impl = match syn::parse::<ItemImpl>(input.clone()) {
+        Ok(input_impl) => input_impl,
+        Err(_) if item::is_impl(input.clone().into()) => return input,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    match item::instrument_impl(&args, &mut input_impl) {
+        Ok(()) => TokenStream::from(hygiene::mixed_site(quote!(#input_impl))),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// 为内联模块中的所有函数添加 tracing 功能的过程宏
+///
+/// 参数与 [`macro@tracing_fn`] 相同，作为模块内所有函数、impl 块以及嵌套内联模块的默认参数。
+/// 模块内仍可以使用 `#[tracing_fn(...)]`、`#[tracing_impl(...)]`、`#[tracing_mod(...)]` 调整参数，
+/// 规则与 [`macro@tracing_impl`] 相同。
+///
+/// # 示例
+/// ```rust
+/// # use tracing_fn::{tracing_fn, tracing_mod};
+/// #[tracing_mod(skip(password))]
+/// mod auth {
+///     pub fn login(username: &str, password: &str) -> bool {
+///         !username.is_empty() && !password.is_empty()
+///     }
+///
+///     #[tracing_fn(skip(token))]
+///     pub fn refresh(token: &str, password: &str) -> bool {
+///         !token.is_empty() && !password.is_empty()
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn tracing_mod(args: TokenStream, input: TokenStream) -> TokenStream {
+    if let Some(forwarded) =
+        preset::forward("tracing_mod", args.clone().into(), input.clone().into())
+    {
+        return forwarded
+            .unwrap_or_else(|err| err.to_compile_error())
+            .into();
+    }
+    let args = match Args::parse(args.into()) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    // 无法解析时原样输出, 由编译器报告语法错误或处理 syn 尚不支持的语法
+    let mut input_mod = match syn::parse::<ItemMod>(input.clone()) {
+        Ok(input_mod) => input_mod,
+        Err(_) if item::is_mod(input.clone().into()) => return input,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    match item::instrument_mod(&args, &mut input_mod) {
+        Ok(()) => TokenStream::from(hygiene::mixed_site(quote!(#input_mod))),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// 为程序入口添加 tracing 的属性宏
+///
+/// 标注在 `fn main` 上，在函数体开头安装 panic hook，panic 时以 error 等级输出 panic 的信息后调用原有的 hook；
+/// main 返回或 panic 展开时输出程序的运行时间，以及每个被追踪函数的调用次数、失败次数、总耗时、平均耗时与最长耗时，
+/// 按总耗时从大到小排列。统计只包含输出了事件的调用，`process::exit` 不运行析构函数，此时不输出退出统计。
+///
+/// # 参数
+/// - `level`: 退出统计的日志等级 (trace, debug, info, warn, error)，默认为 info
+/// - `force`: 是否强制在release模式下启用，默认为false
+/// - `cfg`: 自定义启用的 cfg 条件，默认为 `debug_assertions`
+/// - `backend`: 输出事件使用的日志库 (tracing, log, stderr, etw)，默认为 tracing
+/// - `crate`: 生成的代码引用 tracing 的路径，默认为 `::tracing`
+///
+/// 需要启用 `std` feature。
+///
+/// # 示例
+/// ```rust
+/// use tracing_fn::{tracing_fn, tracing_main};
+///
+/// #[tracing_fn]
+/// fn parse(text: &str) -> Result<i32, std::num::ParseIntError> {
+///     text.parse()
+/// }
+///
+/// #[tracing_main]
+/// fn main() {
+///     let _ = parse("42");
+///     let _ = parse("x");
+/// }
+/// // INFO example: === [main] #Exit: duration: 1.3ms, functions: 1
+/// // INFO example: === [main] #Stats: example::parse calls: 2, failed: 1, total: 3.1µs, mean: 1.5µs, max: 2.2µs
+/// ```
+#[proc_macro_attribute]
+pub fn tracing_main(args: TokenStream, input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::ItemFn);
+    match program::expand(args.into(), input) {
+        Ok(expanded) => TokenStream::from(hygiene::mixed_site(expanded)),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// 测量表达式耗时的过程宏
+///
+/// 参数依次为可选的参数与表达式。求值表达式后输出一条事件，包含表达式的源码、值的 `Debug` 与耗时，宏的值为表达式的值。
+/// 表达式中的 `?` 与 `return` 提前离开时不输出事件。
+///
+/// # 参数
+/// - `level`: 日志等级 (trace, debug, info, warn, error)，默认为 trace
+/// - `force`: 是否强制在release模式下启用tracing，默认为false
+/// - `cfg`: 自定义启用 tracing 的 cfg 条件，默认为 `debug_assertions`
+/// - `backend`: 输出事件使用的日志库 (tracing, log, stderr, etw)，默认为 tracing
+/// - `skip_ret`: 是否不输出表达式的值，默认为false
+/// - `max_len`: 表达式的值格式化结果的最大字节数
+/// - `crate`: 生成的代码引用 tracing 的路径，默认为 `::tracing`
+///
+/// 与 [`macro@tracing_fn`] 一样读取 `TRACING_FN_LEVEL` 等环境变量作为默认值。需要启用 `std` feature。
+///
+/// # 示例
+/// ```rust
+/// # use tracing_fn::trace_expr;
+/// let words = ["a", "bb", "ccc"];
+/// let total = trace_expr!(words.iter().map(|word| word.len()).sum::<usize>());
+/// // TRACE example: === [words.iter().map(|word|word.len()).sum::<usize>()] #Ret: 6, duration: 1.1µs --- src/main.rs:2
+/// assert_eq!(total, 6);
+/// ```
+#[proc_macro]
+pub fn trace_expr(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as expr::TracedExpr);
+    match expr::expand(input) {
+        Ok(expanded) => TokenStream::from(hygiene::mixed_site(expanded)),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// 为闭包添加 tracing 的过程宏
+///
+/// 参数依次为可选的参数、可选的名称 (默认为 `closure`) 与闭包，展开为参数相同的闭包。
+/// 每次调用时在入口事件中输出参数的 `Debug`，调用结束后在退出事件中输出返回值与耗时，
+/// 用于迭代器适配器与回调等无法添加 [`macro@tracing_fn`] 的地方。参数的类型无法从上下文推断时需要标注类型。
+///
+/// # 参数
+/// - `level`: 日志等级 (trace, debug, info, warn, error)，默认为 trace
+/// - `force`: 是否强制在release模式下启用tracing，默认为false
+/// - `cfg`: 自定义启用 tracing 的 cfg 条件，默认为 `debug_assertions`
+/// - `backend`: 输出事件使用的日志库 (tracing, log, stderr, etw)，默认为 tracing
+/// - `skip_ret`: 是否不输出返回值，默认为false
+/// - `max_len`: 参数与返回值格式化结果的最大字节数
+/// - `crate`: 生成的代码引用 tracing 的路径，默认为 `::tracing`
+///
+/// 与 [`macro@tracing_fn`] 一样读取 `TRACING_FN_LEVEL` 等环境变量作为默认值。需要启用 `std` feature，不支持 async 闭包。
+///
+/// # 示例
+/// ```rust
+/// # use tracing_fn::tracing_closure;
+/// let double = tracing_closure!(level = "info", |x: i32| x * 2);
+/// // INFO example: >>> [closure] #Args: x=3 --- src/main.rs:2
+/// // INFO example: <<< [closure] #Ret: 6, duration: 150ns
+/// assert_eq!(double(3), 6);
+///
+/// let total: i32 = [1, 2]
+///     .iter()
+///     .map(tracing_closure!("square", |x: &i32| x * x))
+///     .sum();
+/// # assert_eq!(total, 5);
+/// ```
+#[proc_macro]
+pub fn tracing_closure(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as closure::TracedClosure);
+    match closure::expand(input) {
+        Ok(expanded) => TokenStream::from(hygiene::mixed_site(expanded)),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// 为函数中的一段代码添加 tracing 的过程宏
+///
+/// 参数依次为可选的参数、代码块的名称与代码块。代码块执行前输出入口事件，执行后在退出事件中输出代码块的值与耗时，
+/// 整个宏的值为代码块的值。代码块原地展开，其中的 `?`、`return`、`break` 与 `.await` 的含义不变，
+/// 经由它们离开代码块时退出事件的值输出为 `<unknown>`，panic 时输出为 `<panicked>`。
+///
+/// # 参数
+/// - `level`: 日志等级 (trace, debug, info, warn, error)，默认为 trace
+/// - `force`: 是否强制在release模式下启用tracing，默认为false
+/// - `cfg`: 自定义启用 tracing 的 cfg 条件，默认为 `debug_assertions`
+/// - `backend`: 输出事件使用的日志库 (tracing, log, stderr, etw)，默认为 tracing
+/// - `skip_ret`: 是否不输出代码块的值，默认为false
+/// - `max_len`: 代码块的值格式化结果的最大字节数
+/// - `crate`: 生成的代码引用 tracing 的路径，默认为 `::tracing`
+///
+/// 与 [`macro@tracing_fn`] 一样读取 `TRACING_FN_LEVEL` 等环境变量作为默认值。需要启用 `std` feature。
+///
+/// # 示例
+/// ```rust
+/// # use tracing_fn::trace_block;
+/// let text = "name = app";
+/// let config = trace_block!(level = "debug", "loading config", {
+///     text.split_once(" = ").map(|(_, value)| value.to_string())
+/// });
+/// // DEBUG example: >>> [loading config] --- src/main.rs:3
+/// // DEBUG example: <<< [loading config] #Ret: Some("app"), duration: 1.2µs
+/// # assert_eq!(config.as_deref(), Some("app"));
+/// ```
+#[proc_macro]
+pub fn trace_block(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as block::TracedBlock);
+    match block::expand(input) {
+        Ok(expanded) => TokenStream::from(hygiene::mixed_site(expanded)),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// 在调用处为无法添加 `#[tracing_fn]` 的函数 (例如第三方 crate 中的函数) 添加 tracing 的过程宏
+///
+/// 最后一个参数为函数调用或方法调用表达式，之前为可选的参数。宏先按顺序求值所有参数并输出入口事件，
+/// 入口事件中以参数表达式的源码作为名称输出求值后的值的 `Debug`，再以这些值调用函数并在退出事件中输出返回值与耗时，
+/// 整个宏的值为函数的返回值。方法调用的接收者不输出。
+///
+/// # 参数
+/// - `level`: 日志等级 (trace, debug, info, warn, error)，默认为 trace
+/// - `force`: 是否强制在release模式下启用tracing，默认为false
+/// - `cfg`: 自定义启用 tracing 的 cfg 条件，默认为 `debug_assertions`
+/// - `backend`: 输出事件使用的日志库 (tracing, log, stderr, etw)，默认为 tracing
+/// - `skip_ret`: 是否不输出返回值，默认为false
+/// - `max_len`: 每个参数与返回值格式化结果的最大字节数
+/// - `crate`: 生成的代码引用 tracing 的路径，默认为 `::tracing`
+///
+/// 与 [`macro@tracing_fn`] 一样读取 `TRACING_FN_LEVEL` 等环境变量作为默认值。需要启用 `std` feature。
+///
+/// # 示例
+/// ```rust
+/// # use tracing_fn::traced_extern;
+/// let input = "42";
+/// let value: i32 = traced_extern!(level = "debug", str::parse::<i32>(input)).unwrap();
+/// // DEBUG example: >>> [str::parse::<i32>] #Args: input="42" --- src/main.rs:3
+/// // DEBUG example: <<< [str::parse::<i32>] #Ret: Ok(42), duration: 1.2µs
+///
+/// let mut names = vec!["b", "a"];
+/// traced_extern!(names.push("c"));
+/// # assert_eq!(value, 42);
+/// ```
+#[proc_macro]
+pub fn traced_extern(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as call::TracedCall);
+    match call::expand(input) {
+        Ok(expanded) => TokenStream::from(hygiene::mixed_site(expanded)),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// 定义可以在多个函数间共享的参数预设
+///
+/// 每一项的形式为 `可见性 const 名称 = (参数);`，参数与 [`macro@tracing_fn`] 相同，在定义处检查。
+/// 宏生成一个 `tracing_fn::Opts` 常量，并以同一名称导出一个 macro_rules，
+/// `#[tracing_fn(use = 路径)]`、`#[tracing_impl(use = 路径)]` 与 `#[tracing_mod(use = 路径)]` 经由它取得预设的参数。
+/// 属性中的其他参数在预设的基础上追加或覆盖，环境变量的优先级低于预设。
+///
+/// 预设的 macro_rules 以 `#[macro_export]` 导出到 crate 根，同一 crate 中的预设不能重名。
+/// `#[tracing_impl]`、`#[tracing_mod]` 内部的 `#[tracing_fn]` 不能使用 `use`。
+///
+/// # 示例
+/// ```rust
+/// mod telemetry {
+///     tracing_fn::opts! {
+///         /// 数据库访问使用的参数
+///         pub const DB_TRACE = (level = "debug", skip(password), max_len = 256);
+///     }
+/// }
+///
+/// // 在预设的基础上修改日志等级
+/// #[tracing_fn::tracing_fn(use = crate::telemetry::DB_TRACE, level = "info")]
+/// fn connect(url: &str, password: &str) -> bool {
+///     !url.is_empty() && !password.is_empty()
+/// }
+///
+/// fn main() {
+///     assert!(connect("postgres://db", "secret"));
+///     assert_eq!(
+///         telemetry::DB_TRACE.args(),
+///         r#"level = "debug", skip(password), max_len = 256"#
+///     );
+/// }
+/// ```
+#[proc_macro]
+pub fn opts(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as preset::OptsItems);
+    match preset::expand_opts(input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
@@ -0,0 +1,381 @@
+//! 未启用 std feature 时的展开
+//!
+//! 不分配内存，也不依赖 `std`：参数作为结构化字段直接输出，返回值在返回处直接输出，
+//! 耗时使用 `tracing_fn::set_tick_source` 注册的计时函数，以 tick 为单位输出。
+//! 依赖线程局部变量、锁或内存分配的参数不可用，启用 `critical-section` feature 时 `fanout` 与 `depth` 基于全局的栈帧实现。
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{Block, Signature};
+
+use crate::args::{Args, Backend, ErrMode, Measure};
+use crate::body::rewrite_body;
+use crate::expand::{
+    autoref_imports, callable_args, callable_value, collect_bindings, debug_value, display_name,
+    explicit_return_type, fn_span, generic_args, redacted, returns_never, returns_result,
+    static_fields, summary_value, truncated, wrap_child_span,
+};
+
+/// 生成添加了 tracing 的函数体
+pub(crate) fn instrument(args: &Args, sig: &Signature, block: &Block) -> syn::Result<TokenStream> {
+    let tracing = args.tracing_path();
+    // fanout 与 depth 需要 critical-section 保护的全局栈帧
+    let frames = cfg!(feature = "critical-section");
+    let unsupported = [
+        ("level_fn", args.level_fn.is_some()),
+        ("once_per", args.once_per.is_some()),
+        ("condition", args.condition.is_some()),
+        ("first", args.first.is_some()),
+        ("dedup", args.dedup == Some(true)),
+        ("entry_only", args.entry_only == Some(true)),
+        ("exit_only", args.exit_only == Some(true)),
+        ("runtime_skip", args.runtime_skip == Some(true)),
+        ("ret_with", args.ret_with.is_some()),
+        (
+            "err",
+            matches!(args.err, Some(ErrMode::Chain | ErrMode::Alternate)),
+        ),
+        ("err_code", args.err_code.is_some()),
+        ("json", !args.json.is_empty()),
+        ("fanout", args.fanout == Some(true) && !frames),
+        ("abort_safe", args.abort_safe == Some(true)),
+        ("tenant", args.tenant == Some(true)),
+        ("compress_prefix", args.compress_prefix.is_some()),
+        ("slow_poll", args.slow_poll.is_some()),
+        ("depth", args.depth == Some(true) && !frames),
+        ("paired", args.paired == Some(true)),
+        ("err_only", args.err_only == Some(true)),
+        ("thread", args.thread == Some(true)),
+        ("dispatch", args.dispatch.is_some()),
+        (
+            "backend",
+            matches!(
+                args.backend,
+                Some(Backend::Log | Backend::Stderr | Backend::Etw)
+            ),
+        ),
+        ("audit", args.audit == Some(true)),
+        ("with", args.with.is_some()),
+        ("measure", args.measure.is_some_and(Measure::cpu)),
+        ("duration_unit", args.duration_unit.is_some()),
+        ("allocs", args.allocs == Some(true)),
+        ("verbosity_tiers", args.verbosity_tiers.is_some()),
+        ("pressure", args.pressure.is_some()),
+        ("poll", args.poll.is_some()),
+        ("batch", args.batch.is_some()),
+        ("record", args.record == Some(true)),
+        ("replay", args.replay == Some(true)),
+        ("binary", args.binary == Some(true)),
+        ("test_subscriber", args.test_subscriber == Some(true)),
+        ("entry_on_slow", args.entry_on_slow.is_some()),
+        ("caller_fn", args.caller_fn == Some(true)),
+    ];
+    if let Some((name, _)) = unsupported.iter().find(|(_, used)| *used) {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            format!("未启用 tracing-fn 的 std feature 时不支持 `{name}` 参数"),
+        ));
+    }
+    if args.err_level.is_some() && !returns_result(sig) {
+        return Err(syn::Error::new_spanned(
+            &sig.output,
+            "`err_level` 要求函数的返回值为 `Result`",
+        ));
+    }
+
+    let level = args.level.as_deref().unwrap_or("trace");
+    let level_ident = syn::Ident::new(&level.to_uppercase(), proc_macro2::Span::call_site());
+    let fn_name = &sig.ident;
+    let fn_name_str = display_name(args, sig);
+    let location = quote_spanned!(fn_name.span()=> ::core::file!(), ::core::line!());
+
+    // 参数作为结构化字段输出, 跳过的参数输出为 "***"
+    let mut bindings = Vec::new();
+    for arg in &sig.inputs {
+        if let syn::FnArg::Typed(pat_type) = arg {
+            collect_bindings(&pat_type.pat, &mut bindings);
+        }
+    }
+    let callables = callable_args(sig);
+    let arg_fields = bindings.iter().map(|ident| {
+        let format = args.format.iter().find(|(name, _)| name == *ident);
+        if args.skip.contains(&ident.to_string()) {
+            match redacted(args, ident) {
+                Some(redacted) => quote!(#ident = %#redacted,),
+                None => quote!(),
+            }
+        } else if args.valuable.contains(&ident.to_string()) {
+            quote!(#ident = #tracing::field::valuable(&#ident),)
+        } else if let Some((_, format)) = format {
+            let formatted = truncated(args, quote!(#format(&#ident)));
+            quote!(#ident = %#formatted,)
+        } else if args.summarize.contains(&ident.to_string()) {
+            let summary = summary_value(ident);
+            quote!(#ident = %#summary,)
+        } else if callables.contains(ident) {
+            let callable = truncated(args, callable_value(ident));
+            quote!(#ident = ?#callable,)
+        } else {
+            let debug = truncated(args, debug_value(quote!(#ident)));
+            quote!(#ident = ?#debug,)
+        }
+    });
+
+    // 与 std 下相同, async fn 不参与嵌套调用统计与调用深度
+    let is_async = sig.asyncness.is_some();
+    let fanout = args.fanout == Some(true) && !is_async;
+    let depth = args.depth == Some(true) && !is_async;
+    let (frame_enter, frame_field, frame_init, frame_exit) = if fanout || depth {
+        (
+            quote!(let __tracing_fn_frame = ::tracing_fn::runtime::Frame::enter();),
+            quote!(frame: ::core::option::Option<::tracing_fn::runtime::Frame>,),
+            quote!(frame: ::core::option::Option::Some(__tracing_fn_frame),),
+            quote! {
+                let __tracing_fn_depth = self
+                    .frame
+                    .as_ref()
+                    .map_or(0, ::tracing_fn::runtime::Frame::depth);
+                #[allow(unused_variables)]
+                let __tracing_fn_calls = match self.frame.take() {
+                    ::core::option::Option::Some(frame) => frame.exit(failed),
+                    ::core::option::Option::None => <::tracing_fn::runtime::CallCounts as ::core::default::Default>::default(),
+                };
+            },
+        )
+    } else {
+        (quote!(), quote!(), quote!(), quote!())
+    };
+    let calls_fields = if fanout {
+        quote! {
+            calls = __tracing_fn_calls.calls,
+            failed = __tracing_fn_calls.failed,
+        }
+    } else {
+        quote!()
+    };
+    let (depth_prefix, entry_depth, entry_indent, exit_depth, exit_indent) = if depth {
+        (
+            "{}",
+            quote!(depth = __tracing_fn_frame.depth(),),
+            quote!(::tracing_fn::runtime::indent(__tracing_fn_frame.depth()),),
+            quote!(depth = __tracing_fn_depth,),
+            quote!(::tracing_fn::runtime::indent(__tracing_fn_depth),),
+        )
+    } else {
+        ("", quote!(), quote!(), quote!(), quote!())
+    };
+    let static_fields = static_fields(args);
+    // location: 与 std 下相同, 默认只在入口事件中输出源码位置
+    let location_mode = args.location();
+    let location_part = " --- {}:{}";
+    // generics: 与 std 下相同, 类型参数的类型名附加在函数名之后
+    let (generics_eval, generics_field, generics_init, generics_part) =
+        match generic_args(args, sig) {
+            Some((eval, ty)) => (
+                eval,
+                quote!(generics: #ty,),
+                quote!(generics: __tracing_fn_generics,),
+                "{}",
+            ),
+            None => (quote!(), quote!(), quote!(), ""),
+        };
+    let entry_generics = (!generics_part.is_empty()).then(|| quote!(, __tracing_fn_generics));
+    let exit_generics = (!generics_part.is_empty()).then(|| quote!(, self.generics));
+    let entry_format = format!(
+        "{depth_prefix}>>> [{{}}{generics_part}]{}",
+        if location_mode.entry() {
+            location_part
+        } else {
+            ""
+        }
+    );
+    let exit_format = format!(
+        "{depth_prefix}<<< [{{}}{generics_part}]{}",
+        if location_mode.exit() {
+            location_part
+        } else {
+            ""
+        }
+    );
+    let unknown_format = format!(
+        "{depth_prefix}<<< [{{}}{generics_part}] #Ret: <unknown>{}",
+        if location_mode.exit() {
+            location_part
+        } else {
+            ""
+        }
+    );
+    let entry_location = location_mode.entry().then(|| quote!(, #location));
+    let exit_location = location_mode.exit().then(|| quote!(, #location));
+
+    // caller: 调用方的位置作为结构化字段输出
+    let (caller_eval, caller_field) = if args.caller == Some(true) {
+        (
+            quote!(let __tracing_fn_caller = ::core::panic::Location::caller();),
+            quote!(caller = %__tracing_fn_caller,),
+        )
+    } else {
+        (quote!(), quote!())
+    };
+
+    let ret_field = if args.skip_ret == Some(true) {
+        quote!()
+    } else {
+        quote!(ret = ?value,)
+    };
+
+    // clock: 使用自定义的计时函数 (以微秒为单位) 代替 `set_tick_source` 注册的计时函数
+    let (now, elapsed_field) = match &args.clock {
+        Some(clock) => (
+            quote!(::core::option::Option::Some(#clock())),
+            quote!(elapsed_us),
+        ),
+        None => (
+            quote!(::tracing_fn::runtime::ticks()),
+            quote!(elapsed_ticks),
+        ),
+    };
+
+    let exit_event = |level_ident: &syn::Ident| {
+        quote! {
+            #tracing::event!(
+                #tracing::Level::#level_ident,
+                #ret_field
+                #elapsed_field = self.elapsed(),
+                #calls_fields
+                #static_fields
+                #exit_depth
+                #exit_format,
+                #exit_indent
+                #fn_name_str
+                #exit_generics
+                #exit_location
+            );
+        }
+    };
+    // err_level: 返回 Err 时以更高的等级输出退出事件
+    let event_exit = match &args.err_level {
+        Some(err_level) => {
+            let err_ident =
+                syn::Ident::new(&err_level.to_uppercase(), proc_macro2::Span::call_site());
+            let event_err = exit_event(&err_ident);
+            let event_exit = exit_event(&level_ident);
+            quote! {
+                if failed {
+                    #event_err
+                } else {
+                    #event_exit
+                }
+            }
+        }
+        None => exit_event(&level_ident),
+    };
+
+    // 退出事件在返回处直接输出, 守卫只负责无法观察到返回值的情况
+    let guard = quote! {
+        struct __TracingFnGuard {
+            start: ::core::option::Option<u64>,
+            exited: bool,
+            #generics_field
+            #frame_field
+        }
+
+        impl __TracingFnGuard {
+            fn elapsed(&self) -> ::core::option::Option<u64> {
+                let start = self.start?;
+                ::core::option::Option::Some(#now?.wrapping_sub(start))
+            }
+
+            #[allow(dead_code, unused_variables)]
+            fn exit<T: ::core::fmt::Debug + ?::core::marker::Sized>(&mut self, value: &T, failed: bool) {
+                self.exited = true;
+                #frame_exit
+                #event_exit
+            }
+
+            #[allow(dead_code)]
+            fn observe<T: ::tracing_fn::runtime::TryValue>(
+                &mut self,
+                value: T,
+                residual: impl FnOnce(&T) -> ::tracing_fn::runtime::MaybeDebug<'_>,
+            ) -> T {
+                if value.is_break() {
+                    self.exit(&residual(&value), true);
+                }
+                value
+            }
+        }
+
+        impl ::core::ops::Drop for __TracingFnGuard {
+            fn drop(&mut self) {
+                if !self.exited {
+                    // 无法得知返回值, 不计为失败的调用
+                    let failed = false;
+                    #frame_exit
+                    #tracing::event!(
+                        #tracing::Level::#level_ident,
+                        #elapsed_field = self.elapsed(),
+                        #calls_fields
+                        #static_fields
+                        #exit_depth
+                        #unknown_format,
+                        #exit_indent
+                        #fn_name_str
+                        #exit_generics
+                        #exit_location
+                    );
+                }
+            }
+        }
+    };
+
+    let failed = if returns_result(sig) {
+        quote!(::core::result::Result::is_err(&__tracing_fn_ret))
+    } else {
+        quote!(false)
+    };
+    let debug = truncated(args, debug_value(quote!(__tracing_fn_ret)));
+    let record = quote!(__tracing_fn_guard.exit(&#debug, #failed););
+    let ret_ty = explicit_return_type(sig);
+    let body = rewrite_body(block, ret_ty.as_ref(), &record, None, false);
+    let body = if returns_never(sig) {
+        body.never()
+    } else {
+        body.eval(ret_ty.as_ref(), sig.asyncness.is_some(), &record)
+    };
+    let body = wrap_child_span(args, &level_ident, sig.asyncness.is_some(), body);
+    let (span_enter, body) = fn_span(args, &level_ident, sig, body);
+
+    let autoref_imports = autoref_imports();
+    Ok(quote! {
+        #caller_eval
+        #generics_eval
+        #autoref_imports
+        #span_enter
+        #frame_enter
+        #tracing::event!(
+            #tracing::Level::#level_ident,
+            #(#arg_fields)*
+            #caller_field
+            #static_fields
+            #entry_depth
+            #entry_format,
+            #entry_indent
+            #fn_name_str
+            #entry_generics
+            #entry_location
+        );
+
+        #guard
+
+        #[allow(unused_mut)]
+        let mut __tracing_fn_guard = __TracingFnGuard {
+            start: #now,
+            exited: false,
+            #generics_init
+            #frame_init
+        };
+
+        #body
+    })
+}
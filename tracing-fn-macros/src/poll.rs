@@ -0,0 +1,243 @@
+//! `poll(id = "...")` 参数: 手动实现的 `Future::poll`、`Stream::poll_next` 等返回 `Poll<T>` 的函数
+//!
+//! 每次 poll 都输出入口与退出事件会淹没日志, 该模式只在实例的就绪状态变化时输出事件:
+//! 由未等待变为返回 `Pending` 时输出一次, 返回 `Ready` 时输出本次等待期间的 poll 次数与累计等待时间。
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{Block, ReturnType, Signature, Type};
+
+use crate::args::{Args, ErrMode, Location};
+use crate::body::rewrite_body;
+use crate::expand::{
+    EventKind, autoref_imports, debug_value, dispatched, display_name, enabled, event,
+    explicit_return_type, static_fields, truncated,
+};
+
+pub(crate) fn instrument(args: &Args, sig: &Signature, block: &Block) -> syn::Result<TokenStream> {
+    let Some(poll) = &args.poll else {
+        unreachable!("poll::instrument 只用于设置了 poll 参数的函数");
+    };
+    if sig.asyncness.is_some() {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            "`poll` 用于手动实现的 poll 函数, async fn 请使用 `slow_poll`",
+        ));
+    }
+    if !returns_poll(sig) {
+        return Err(syn::Error::new_spanned(
+            &sig.output,
+            "`poll` 要求函数的返回值为 `Poll<T>`",
+        ));
+    }
+
+    // 只作用于入口与退出事件的参数在该模式下没有意义
+    let unsupported = [
+        ("level_fn", args.level_fn.is_some()),
+        ("once_per", args.once_per.is_some()),
+        ("condition", args.condition.is_some()),
+        ("first", args.first.is_some()),
+        ("dedup", args.dedup == Some(true)),
+        ("entry_only", args.entry_only == Some(true)),
+        ("exit_only", args.exit_only == Some(true)),
+        ("runtime_skip", args.runtime_skip == Some(true)),
+        ("fanout", args.fanout == Some(true)),
+        ("abort_safe", args.abort_safe == Some(true)),
+        (
+            "err",
+            matches!(args.err, Some(ErrMode::Chain | ErrMode::Alternate)),
+        ),
+        ("err_code", args.err_code.is_some()),
+        ("err_level", args.err_level.is_some()),
+        ("tenant", args.tenant == Some(true)),
+        ("compress_prefix", args.compress_prefix.is_some()),
+        ("depth", args.depth == Some(true)),
+        ("paired", args.paired == Some(true)),
+        ("err_only", args.err_only == Some(true)),
+        ("thread", args.thread == Some(true)),
+        ("span", args.enabled_span().is_some()),
+        ("child_span", args.child_span.is_some()),
+        ("audit", args.audit == Some(true)),
+        ("with", args.with.is_some()),
+        ("clock", args.clock.is_some()),
+        ("measure", args.measure.is_some()),
+        ("duration_unit", args.duration_unit.is_some()),
+        ("allocs", args.allocs == Some(true)),
+        ("record", args.record == Some(true)),
+        ("batch", args.batch.is_some()),
+        ("replay", args.replay == Some(true)),
+        ("binary", args.binary == Some(true)),
+        ("test_subscriber", args.test_subscriber == Some(true)),
+        ("caller", args.caller == Some(true)),
+        ("entry_on_slow", args.entry_on_slow.is_some()),
+        ("caller_fn", args.caller_fn == Some(true)),
+        ("generics", args.generics == Some(true)),
+        ("verbosity_tiers", args.verbosity_tiers.is_some()),
+        ("pressure", args.pressure.is_some()),
+    ];
+    if let Some((name, _)) = unsupported.iter().find(|(_, used)| *used) {
+        return Err(syn::Error::new_spanned(
+            &sig.ident,
+            format!("`poll` 模式不支持 `{name}` 参数"),
+        ));
+    }
+
+    let level = args.level.as_deref().unwrap_or("trace");
+    let id = &poll.id;
+    let fn_name = &sig.ident;
+    let fn_name_str = display_name(args, sig);
+    // location: Pending 事件代替入口事件, 除 `none` 外都输出位置
+    let (pending_format, location) = if args.location() == Location::None {
+        ("~~~ [{}] #Pending: id={}", quote!())
+    } else {
+        (
+            "~~~ [{}] #Pending: id={} --- {}:{}",
+            quote_spanned!(fn_name.span()=> , ::core::file!(), ::core::line!()),
+        )
+    };
+    let static_fields = static_fields(args);
+
+    let event_pending = dispatched(
+        args,
+        event(
+            args,
+            EventKind::Event,
+            level,
+            static_fields.clone(),
+            quote! {
+                #pending_format,
+                #fn_name_str,
+                __tracing_fn_id
+                #location
+            },
+        ),
+    );
+    let (ret_part, ret_value) = if args.skip_ret == Some(true) {
+        ("", quote!())
+    } else {
+        ("#Ret: {}, ", quote!(__tracing_fn_ret,))
+    };
+    let event_ready = dispatched(
+        args,
+        event(
+            args,
+            EventKind::Event,
+            level,
+            static_fields,
+            quote! {
+                ::std::concat!("~~~ [{}] #Ready: id={}, ", #ret_part, "polls: {}, pending: {:?}"),
+                #fn_name_str,
+                __tracing_fn_id,
+                #ret_value
+                __tracing_fn_stats.polls,
+                __tracing_fn_stats.pending
+            },
+        ),
+    );
+    let enabled = dispatched(args, enabled(args, level));
+
+    // 返回处与 `?` 提前返回处都经由守卫记录状态变化, 同一次调用只记录一次
+    let guard = quote! {
+        struct __TracingFnGuard {
+            /// 不输出事件时为 `None`
+            id: ::std::option::Option<::tracing_fn::runtime::InlineString>,
+        }
+
+        impl __TracingFnGuard {
+            fn exit(
+                &mut self,
+                pending: bool,
+                format: impl FnOnce() -> ::tracing_fn::runtime::InlineString,
+            ) {
+                if let ::std::option::Option::Some(id) = self.id.take() {
+                    if pending {
+                        if __TRACING_FN_POLL.pending(id.as_str()) {
+                            let __tracing_fn_id = id.as_str();
+                            #event_pending
+                        }
+                    } else {
+                        Self::ready(id.as_str(), &format());
+                    }
+                }
+            }
+
+            #[allow(dead_code)]
+            fn observe<T: ::tracing_fn::runtime::TryValue>(
+                &mut self,
+                value: T,
+                residual: impl FnOnce(&T) -> ::tracing_fn::runtime::MaybeDebug<'_>,
+            ) -> T {
+                if value.is_break() {
+                    if let ::std::option::Option::Some(id) = self.id.take() {
+                        Self::ready(
+                            id.as_str(),
+                            &::tracing_fn::runtime::InlineString::debug(&residual(&value)),
+                        );
+                    }
+                }
+                value
+            }
+
+            #[allow(unused_variables)]
+            fn ready(__tracing_fn_id: &str, ret: &::tracing_fn::runtime::InlineString) {
+                let __tracing_fn_stats = __TRACING_FN_POLL.ready(__tracing_fn_id);
+                let __tracing_fn_ret = ret.as_str();
+                #event_ready
+            }
+        }
+    };
+
+    let format = match &args.ret_with {
+        Some(format) => {
+            let formatted = truncated(args, quote!(#format(&__tracing_fn_ret)));
+            quote!(::tracing_fn::runtime::InlineString::display(&#formatted))
+        }
+        None => {
+            let debug = truncated(args, debug_value(quote!(__tracing_fn_ret)));
+            quote!(::tracing_fn::runtime::InlineString::debug(&#debug))
+        }
+    };
+    let record = quote! {
+        __tracing_fn_guard.exit(::std::task::Poll::is_pending(&__tracing_fn_ret), || #format);
+    };
+    let ret_ty = explicit_return_type(sig);
+    let body = rewrite_body(block, ret_ty.as_ref(), &record, None, false).eval(
+        ret_ty.as_ref(),
+        false,
+        &record,
+    );
+
+    let autoref_imports = autoref_imports();
+    Ok(quote! {
+        #autoref_imports
+        static __TRACING_FN_POLL: ::tracing_fn::runtime::PollTracker =
+            ::tracing_fn::runtime::PollTracker::new();
+
+        #guard
+
+        // id 在函数体之前求值并格式化, 函数体因此可以自由地借用或移动其中的值
+        let mut __tracing_fn_guard = __TracingFnGuard {
+            id: if #enabled {
+                ::std::option::Option::Some(::tracing_fn::runtime::InlineString::debug(&(#id)))
+            } else {
+                ::std::option::Option::None
+            },
+        };
+
+        #body
+    })
+}
+
+/// 判断函数的返回值是否为 `Poll` (包括 `std::task::Poll` 等带路径的写法)
+fn returns_poll(sig: &Signature) -> bool {
+    let ReturnType::Type(_, ty) = &sig.output else {
+        return false;
+    };
+    let Type::Path(path) = &**ty else {
+        return false;
+    };
+    path.path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "Poll")
+}
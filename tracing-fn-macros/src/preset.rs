@@ -0,0 +1,138 @@
+//! 共享的参数预设
+//!
+//! `opts! { pub const DB_TRACE = (level = "debug", skip_ret = true); }` 定义一个 `tracing_fn::Opts` 常量，
+//! 并以同一名称导出一个 macro_rules。过程宏无法读取常量的值，`#[tracing_fn(use = crate::telemetry::DB_TRACE)]`
+//! 展开为对该 macro_rules 的调用，由它以 `use = (...)` 的形式把预设的参数与属性中的其他参数一起重新交给属性宏。
+
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{Attribute, Ident, Path, Token, Visibility};
+
+use crate::args::Args;
+
+/// `opts!` 的输入: 一个或多个 `pub const NAME = (...);`
+pub(crate) struct OptsItems(Vec<OptsItem>);
+
+struct OptsItem {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    args: TokenStream,
+}
+
+impl Parse for OptsItems {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut items = Vec::new();
+        while !input.is_empty() {
+            let attrs = input.call(Attribute::parse_outer)?;
+            let vis = input.parse()?;
+            input.parse::<Token![const]>()?;
+            let name = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let content;
+            syn::parenthesized!(content in input);
+            let args = content.parse()?;
+            input.parse::<Token![;]>()?;
+            items.push(OptsItem {
+                attrs,
+                vis,
+                name,
+                args,
+            });
+        }
+        Ok(Self(items))
+    }
+}
+
+/// 展开 `opts!`
+pub(crate) fn expand_opts(input: OptsItems) -> syn::Result<TokenStream> {
+    let mut expanded = TokenStream::new();
+    for item in input.0 {
+        let OptsItem {
+            attrs,
+            vis,
+            name,
+            args,
+        } = item;
+        // 在定义处检查参数, 使用预设的函数不再报告预设中的错误
+        Args::parse(args.clone())?;
+        let text = args.to_string();
+        // `#[macro_export]` 的宏位于 crate 根, 再以常量的名称从定义的模块导出
+        let rules = format_ident!("__tracing_fn_opts_{}", name);
+        expanded.extend(quote! {
+            #(#attrs)*
+            #vis const #name: ::tracing_fn::Opts = ::tracing_fn::Opts::new(#text);
+
+            #[doc(hidden)]
+            #[macro_export]
+            macro_rules! #rules {
+                ($attr:ident ($($args:tt)*) $($item:tt)*) => {
+                    #[::tracing_fn::$attr(use = (#args), $($args)*)]
+                    $($item)*
+                };
+            }
+
+            #[doc(hidden)]
+            #[allow(unused_imports)]
+            #vis use #rules as #name;
+        });
+    }
+    Ok(expanded)
+}
+
+/// 属性参数中包含 `use = 路径` 时, 把属性所在的条目转交给预设的 macro_rules
+///
+/// `attr` 为属性宏的名称。返回 `None` 表示没有使用预设, 按普通参数解析。
+pub(crate) fn forward(
+    attr: &str,
+    args: TokenStream,
+    item: TokenStream,
+) -> Option<syn::Result<TokenStream>> {
+    let mut preset: Option<Path> = None;
+    let mut rest = Vec::new();
+    for param in split_params(args) {
+        let mut tokens = param.clone().into_iter();
+        let is_use = matches!(
+            (tokens.next(), tokens.next()),
+            (Some(TokenTree::Ident(ident)), Some(TokenTree::Punct(punct)))
+                if ident == "use" && punct.as_char() == '='
+        );
+        let value = tokens.collect::<TokenStream>();
+        // `use = (...)` 是预设展开后传入的参数
+        let expanded = matches!(
+            value.clone().into_iter().next(),
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis
+        );
+        if !is_use || expanded {
+            rest.push(param);
+            continue;
+        }
+        if preset.is_some() {
+            return Some(Err(syn::Error::new_spanned(param, "`use` 只能设置一次")));
+        }
+        match syn::parse2(value) {
+            Ok(path) => preset = Some(path),
+            Err(err) => return Some(Err(err)),
+        }
+    }
+
+    let preset = preset?;
+    let attr = format_ident!("{}", attr);
+    Some(Ok(quote! {
+        #preset! { #attr (#(#rest),*) #item }
+    }))
+}
+
+/// 按顶层的逗号拆分属性参数, 去掉空的参数
+fn split_params(args: TokenStream) -> Vec<TokenStream> {
+    let mut params = vec![TokenStream::new()];
+    for token in args {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => params.push(TokenStream::new()),
+            _ => params.last_mut().unwrap().extend([token]),
+        }
+    }
+    params.retain(|param| !param.is_empty());
+    params
+}
@@ -0,0 +1,120 @@
+//! `#[tracing_main]`: 程序入口的 tracing
+//!
+//! 在 main 的开头安装通过 tracing 输出 panic 的 panic hook 并开始统计被追踪函数的调用，
+//! main 结束时输出程序的运行时间，启用 main-stats feature 时还输出每个被追踪函数的调用次数、失败次数与耗时。
+
+use proc_macro2::TokenStream;
+use quote::{ToTokens, quote};
+use syn::{Block, ItemFn, parse_quote};
+
+use crate::args::{Args, env_dependencies};
+use crate::call::gate;
+use crate::expand::{EventKind, StaticLevel, enabled, event, static_level};
+
+/// 展开 `#[tracing_main]`
+pub(crate) fn expand(attr: TokenStream, mut item: ItemFn) -> syn::Result<TokenStream> {
+    let args = Args::parse_call_options(attr)?;
+    // 环境变量提供项目级默认值, 宏参数优先
+    let args = Args::from_env()?.merge(&args);
+    let env_dependencies = env_dependencies();
+    let env_dependencies: Block = parse_quote!({ #env_dependencies });
+    item.block.stmts.splice(0..0, env_dependencies.stmts);
+
+    let level = args.level.as_deref().unwrap_or("info");
+    // 启用 disabled feature 或该等级被 tracing 的 max_level_* feature 移除时原样展开
+    let static_level = static_level(&args, level);
+    if cfg!(feature = "disabled") || static_level == StaticLevel::Disabled {
+        return Ok(item.to_token_stream());
+    }
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &item.sig.ident,
+            "`#[tracing_main]` 需要启用 tracing-fn 的 std feature",
+        ));
+    }
+
+    let name = item.sig.ident.to_string();
+    let gate = gate(&args, static_level);
+    let enabled = enabled(&args, level);
+    let panic = event(
+        &args,
+        EventKind::Event,
+        "error",
+        quote!(),
+        quote!("!!! [{}] #Panic: {}", #name, report),
+    );
+    // main-stats: 未启用时被追踪的函数不统计调用, 只输出运行时间
+    let main_stats = cfg!(feature = "main-stats");
+    let exit = if main_stats {
+        quote!(
+            "=== [{}] #Exit: duration: {:?}, functions: {}",
+            #name,
+            __tracing_fn_duration,
+            __tracing_fn_summary.len()
+        )
+    } else {
+        quote!("=== [{}] #Exit: duration: {:?}", #name, __tracing_fn_duration)
+    };
+    let exit = event(&args, EventKind::Event, level, quote!(), exit);
+    let stats = event(
+        &args,
+        EventKind::Event,
+        level,
+        quote!(),
+        quote!(
+            "=== [{}] #Stats: {} calls: {}, failed: {}, total: {:?}, mean: {:?}, max: {:?}",
+            #name,
+            stats.name,
+            stats.calls,
+            stats.failed,
+            stats.total,
+            stats.mean(),
+            stats.max
+        ),
+    );
+
+    let summary = if main_stats {
+        quote! {
+            let __tracing_fn_summary = ::tracing_fn::runtime::main_summary();
+            #exit
+            for stats in &__tracing_fn_summary {
+                #stats
+            }
+        }
+    } else {
+        exit
+    };
+
+    // 守卫在 main 返回或 panic 展开时输出退出统计, `process::exit` 不运行析构函数
+    item.block.stmts.insert(
+        0,
+        parse_quote! {
+            let __tracing_fn_main = {
+                struct __TracingFnMain(::std::option::Option<::tracing_fn::runtime::Instant>);
+
+                impl ::std::ops::Drop for __TracingFnMain {
+                    fn drop(&mut self) {
+                        // subscriber 通常在 main 中才初始化, 在退出时检查等级
+                        if let ::std::option::Option::Some(start) = self.0.take()
+                            && #enabled
+                        {
+                            let __tracing_fn_duration = start.elapsed();
+                            #summary
+                        }
+                    }
+                }
+
+                fn __tracing_fn_report(report: &str) {
+                    #panic
+                }
+
+                let __tracing_fn_enabled = #gate;
+                if __tracing_fn_enabled {
+                    ::tracing_fn::runtime::main_enter(__tracing_fn_report);
+                }
+                __TracingFnMain(__tracing_fn_enabled.then(::tracing_fn::runtime::Instant::now))
+            };
+        },
+    );
+    Ok(item.to_token_stream())
+}
@@ -0,0 +1,122 @@
+//! `replay = true` 参数: 以回放文件中记录的参数重新执行函数的伴生函数
+//!
+//! 伴生函数与原函数位于同一作用域, 名称为 `<函数名>__replay`。参数按记录中的顺序反序列化为各自的所有权类型
+//! (`&str` 对应 `String`, `&[T]` 对应 `Vec<T>`, `&T` 对应 `T`), 再以原函数的传参方式调用。
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{FnArg, Pat, Signature, Type};
+
+use crate::args::{Args, ImplBlock};
+
+pub(crate) fn companion(
+    args: &Args,
+    vis: &TokenStream,
+    sig: &Signature,
+    qualified_name: &str,
+) -> syn::Result<TokenStream> {
+    let fn_name = &sig.ident;
+    if args.impl_block == Some(ImplBlock::Trait) {
+        return Err(syn::Error::new_spanned(
+            fn_name,
+            "trait 的 impl 块中无法添加 `replay` 生成的函数",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            "`replay` 不支持带有泛型参数或生命周期参数的函数",
+        ));
+    }
+    if let Some(unsafety) = &sig.unsafety {
+        return Err(syn::Error::new_spanned(
+            unsafety,
+            "`replay` 不支持 unsafe fn",
+        ));
+    }
+
+    let mut bindings = Vec::new();
+    let mut owned = Vec::new();
+    let mut passed = Vec::new();
+    for input in &sig.inputs {
+        let pat_type = match input {
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "`replay` 不支持带有 self 的方法",
+                ));
+            }
+            FnArg::Typed(pat_type) => pat_type,
+        };
+        let Pat::Ident(pat) = &*pat_type.pat else {
+            return Err(syn::Error::new_spanned(
+                &pat_type.pat,
+                "`replay` 要求参数为简单的变量名",
+            ));
+        };
+        let ident = &pat.ident;
+        match &*pat_type.ty {
+            Type::Reference(reference) => {
+                owned.push(owned_type(&reference.elem));
+                if reference.mutability.is_some() {
+                    bindings.push(quote!(mut #ident));
+                    passed.push(quote!(&mut #ident));
+                } else {
+                    bindings.push(quote!(#ident));
+                    passed.push(quote!(&#ident));
+                }
+            }
+            ty => {
+                owned.push(quote!(#ty));
+                bindings.push(quote!(#ident));
+                passed.push(quote!(#ident));
+            }
+        }
+    }
+
+    let companion = format_ident!("{}__replay", fn_name);
+    let doc = format!(" 以回放文件中的记录重新执行 [`{fn_name}`] 并比较返回值");
+    let path = match args.impl_block {
+        Some(_) => quote!(Self::#fn_name),
+        None => quote!(#fn_name),
+    };
+    let asyncness = &sig.asyncness;
+    let await_ret = asyncness.map(|_| quote!(.await));
+    Ok(quote! {
+        #[doc = #doc]
+        #[allow(non_snake_case)]
+        #vis #asyncness fn #companion(
+            __tracing_fn_record: &::tracing_fn::ReplayRecord,
+        ) -> ::tracing_fn::ReplayResult {
+            if __tracing_fn_record.function != #qualified_name {
+                return ::tracing_fn::ReplayResult::Invalid(::std::format!(
+                    "`{}` cannot replay a record of `{}`",
+                    #qualified_name,
+                    __tracing_fn_record.function
+                ));
+            }
+            let (#(#bindings,)*): (#(#owned,)*) = match __tracing_fn_record.decode_args() {
+                ::std::result::Result::Ok(args) => args,
+                ::std::result::Result::Err(err) => {
+                    return ::tracing_fn::ReplayResult::Invalid(::std::string::ToString::to_string(&err));
+                }
+            };
+            let __tracing_fn_ret = #path(#(#passed),*) #await_ret;
+            ::tracing_fn::runtime::replay_compare(__tracing_fn_record, &__tracing_fn_ret)
+        }
+    })
+}
+
+/// 引用参数的被引用类型对应的所有权类型
+fn owned_type(elem: &Type) -> TokenStream {
+    match elem {
+        Type::Path(path) if path.qself.is_none() && path.path.is_ident("str") => {
+            quote!(::std::string::String)
+        }
+        Type::Slice(slice) => {
+            let elem = &slice.elem;
+            quote!(::std::vec::Vec<#elem>)
+        }
+        ty => quote!(#ty),
+    }
+}
@@ -0,0 +1,116 @@
+//! 编译期统计报告
+//!
+//! 设置环境变量 `TRACING_FN_REPORT` 后，宏会把每个添加了 tracing 的函数记录到 `$OUT_DIR/tracing_fn_report.tsv`，
+//! 按模块统计函数数量、各日志等级的数量、使用 `force` 的数量以及等级被 tracing 的静态最高等级移除的数量，
+//! 便于跟踪 tracing 覆盖率的变化。
+//!
+//! 同一 crate 中的宏在同一个编译进程中展开，因此在内存中累积记录，每次展开后重写整个文件。
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use proc_macro2::Span;
+
+use crate::args::{env_var, parse_env_bool};
+
+/// 报告文件名
+const FILE_NAME: &str = "tracing_fn_report.tsv";
+
+/// 报告文件路径 -> (函数全限定名 -> 记录)
+static RECORDS: Mutex<BTreeMap<String, BTreeMap<String, Record>>> = Mutex::new(BTreeMap::new());
+
+struct Record {
+    module: String,
+    level: String,
+    forced: bool,
+    /// 等级被 `max_level_*` 或 `release_max_level_*` 移除
+    stripped: bool,
+}
+
+/// 记录一个添加了 tracing 的函数, 未设置 `TRACING_FN_REPORT` 时不做任何事
+pub(crate) fn record(
+    qualified_name: &str,
+    level: &str,
+    forced: bool,
+    stripped: bool,
+) -> syn::Result<()> {
+    let Some(enabled) = env_var("TRACING_FN_REPORT") else {
+        return Ok(());
+    };
+    if !parse_env_bool("TRACING_FN_REPORT", &enabled)? {
+        return Ok(());
+    }
+    let out_dir = std::env::var("OUT_DIR").map_err(|_| {
+        syn::Error::new(
+            Span::call_site(),
+            "TRACING_FN_REPORT 需要 OUT_DIR 环境变量, 请为 crate 添加 build.rs (可以为空的 `fn main() {}`)",
+        )
+    })?;
+    let path = format!("{out_dir}/{FILE_NAME}");
+
+    let mut records = RECORDS.lock().unwrap_or_else(|e| e.into_inner());
+    let crate_records = records.entry(path.clone()).or_default();
+    let module = qualified_name
+        .rsplit_once("::")
+        .map_or("", |(module, _)| module)
+        .to_string();
+    crate_records.insert(
+        qualified_name.to_string(),
+        Record {
+            module,
+            level: level.to_string(),
+            forced,
+            stripped,
+        },
+    );
+
+    std::fs::write(&path, render(crate_records)).map_err(|err| {
+        syn::Error::new(
+            Span::call_site(),
+            format!("无法写入 tracing-fn 统计报告 ({path}): {err}"),
+        )
+    })
+}
+
+const LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+
+#[derive(Default)]
+struct Counts {
+    functions: usize,
+    levels: [usize; 5],
+    forced: usize,
+    stripped: usize,
+}
+
+impl Counts {
+    fn add(&mut self, record: &Record) {
+        self.functions += 1;
+        if let Some(i) = LEVELS.iter().position(|level| *level == record.level) {
+            self.levels[i] += 1;
+        }
+        self.forced += usize::from(record.forced);
+        self.stripped += usize::from(record.stripped);
+    }
+}
+
+fn render(records: &BTreeMap<String, Record>) -> String {
+    let mut total = Counts::default();
+    let mut modules: BTreeMap<&str, Counts> = BTreeMap::new();
+    for record in records.values() {
+        total.add(record);
+        modules.entry(&record.module).or_default().add(record);
+    }
+
+    let mut out =
+        String::from("module\tfunctions\ttrace\tdebug\tinfo\twarn\terror\tforced\tstripped\n");
+    let rows = modules.iter().map(|(module, counts)| (*module, counts));
+    for (module, counts) in rows.chain([("(total)", &total)]) {
+        let _ = write!(out, "{module}\t{}", counts.functions);
+        for count in counts.levels {
+            let _ = write!(out, "\t{count}");
+        }
+        let _ = writeln!(out, "\t{}\t{}", counts.forced, counts.stripped);
+    }
+    out
+}